@@ -0,0 +1,47 @@
+use tauri::{Manager, Runtime};
+use crate::error;
+use crate::error::Error;
+use crate::state::{accounts, credentials, launch_profiles, playtime};
+use crate::state::auth::Account;
+use crate::state::tokens::TokenLifecycle;
+use crate::state::webview_profiles;
+
+#[tauri::command]
+pub async fn list_accounts<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Vec<Account>> {
+    accounts::list_accounts(&app_handle)
+}
+
+#[tauri::command]
+pub async fn get_account<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String
+) -> error::Result<Account> {
+    accounts::get_account(&app_handle, &account_id)
+}
+
+#[tauri::command]
+pub async fn remove_account<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String
+) -> error::Result<()> {
+    credentials::revoke_account(&app_handle, &account_id);
+    accounts::remove_account(&app_handle, &account_id)?;
+    app_handle.state::<TokenLifecycle>().untrack(&account_id);
+    launch_profiles::remove_profiles(&app_handle, &account_id)?;
+    playtime::remove_history(&app_handle, &account_id)?;
+    webview_profiles::clear(&app_handle, &account_id)
+}
+
+/// Deletes the login window cookies of an account, so its next login has to go through the login page.
+#[tauri::command]
+pub async fn clear_browser_data<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String
+) -> error::Result<()> {
+    if !webview_profiles::SUPPORTED {
+        return Err(Error::Unsupported("Clearing the browser data of one account".to_string()));
+    }
+    webview_profiles::clear(&app_handle, &account_id)
+}
@@ -2,56 +2,167 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use anyhow::anyhow;
-use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
-use jsonwebtoken::jwk::Jwk;
 use log::info;
 use serde_json::Value;
 use tauri::{Emitter, Manager, Runtime, WebviewUrl, WebviewWindowBuilder};
+use tokio::sync::watch;
 use crate::error;
 use crate::error::Error;
-use crate::state::auth;
+use crate::state::{accounts, auth, credentials};
 use crate::state::auth::game_session;
-use base64::{Engine as _, engine::general_purpose};
-use rsa::{RsaPublicKey, BigUint};
-use rsa::pkcs1::{EncodeRsaPublicKey, LineEnding};
+use crate::state::endpoints::AuthEndpoints;
+use crate::state::login;
+use crate::state::login::{LoginState, AUTHORIZE_TIMEOUT, REQUEST_TIMEOUT};
+use crate::state::tokens::TokenLifecycle;
+use crate::state::webview_profiles;
+use crate::state::webview_profiles::WebviewProfile;
 
 #[tauri::command]
 pub async fn login<R: Runtime>(
-    app_handle: tauri::AppHandle<R>
+    app_handle: tauri::AppHandle<R>,
+    endpoints: tauri::State<'_, AuthEndpoints>,
+    login_state: tauri::State<'_, LoginState>
+) -> error::Result<()> {
+    let mut cancel = login_state.begin();
+    let result = run_login(&app_handle, &endpoints, &mut cancel, None).await;
+    login_state.finish(&cancel);
+
+    if let Err(e) = &result {
+        log::error!("Login failed: {}", e);
+    }
+    result
+}
+
+/// Logs a stored account in again, silently if the provider session is still valid and through
+/// the login window otherwise.
+#[tauri::command]
+pub async fn relogin<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    endpoints: tauri::State<'_, AuthEndpoints>,
+    login_state: tauri::State<'_, LoginState>,
+    account_id: String
 ) -> error::Result<()> {
-    let flow = auth::begin_login().await?;
+    let mut cancel = login_state.begin();
+    let result = run_login(&app_handle, &endpoints, &mut cancel, Some(&account_id)).await;
+    login_state.finish(&cancel);
+
+    if let Err(e) = &result {
+        log::error!("Re-login of account {} failed: {}", account_id, e);
+    }
+    result
+}
+
+/// Cancels the login in progress, closing its windows. Returns false if no login was running.
+#[tauri::command]
+pub async fn cancel_login<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<bool> {
+    Ok(app_handle.state::<LoginState>().cancel())
+}
+
+/// Runs the visible part of the login: the popup window and the code exchange.
+async fn interactive_login<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    endpoints: &AuthEndpoints,
+    cancel: &mut watch::Receiver<bool>,
+    profile: &WebviewProfile
+) -> error::Result<(auth::AuthFlow, auth::OAuthToken)> {
+    let flow = login::stage(cancel, "contacting Jagex", REQUEST_TIMEOUT, auth::begin_login(endpoints)).await?;
     
     // Emit event to update button text to "Authorizing..."
     app_handle.emit("login-progress", "Authorizing...")?;
-    let (code, state) = auth::authorize(app_handle.clone(), flow.clone()).await?;
+    let (code, state) = login::stage(cancel, "waiting for the login window", AUTHORIZE_TIMEOUT,
+        auth::authorize(app_handle.clone(), flow.clone(), profile)).await?;
     
     // Emit event to update button text to "Getting Token..."
     app_handle.emit("login-progress", "Getting Token...")?;
-    let oauth_token = auth::oauth_token(flow.clone(), code.clone(), state.clone()).await?;
+    let oauth_token = login::stage(cancel, "getting the token", REQUEST_TIMEOUT,
+        auth::oauth_token(flow.clone(), code.clone(), state.clone())).await?;
+    Ok((flow, oauth_token))
+}
+
+/// Logs in and stores the account. With `relogin` set, a silent login for that account is tried first.
+///
+/// Interactive logins run in a fresh browser profile, which becomes the account's profile once it is stored.
+async fn run_login<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    endpoints: &AuthEndpoints,
+    cancel: &mut watch::Receiver<bool>,
+    relogin: Option<&str>
+) -> error::Result<()> {
+    let fresh_profile = WebviewProfile::fresh();
+    let result = login_with_profile(app_handle, endpoints, cancel, relogin, &fresh_profile).await;
+
+    if let Ok(account_id) = &result {
+        if let Err(e) = webview_profiles::adopt(app_handle, &fresh_profile, account_id) {
+            log::error!("Failed to store browser profile for account {}: {}", account_id, e);
+        }
+    }
+    webview_profiles::discard(app_handle, &fresh_profile);
+    result.map(|_| ())
+}
+
+async fn login_with_profile<R: Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    endpoints: &AuthEndpoints,
+    cancel: &mut watch::Receiver<bool>,
+    relogin: Option<&str>,
+    fresh_profile: &WebviewProfile
+) -> error::Result<String> {
+    let (flow, oauth_token, profile) = match relogin {
+        Some(account_id) => {
+            app_handle.emit("login-progress", "Renewing Login...")?;
+            let silent = login::stage(cancel, "renewing the login", REQUEST_TIMEOUT,
+                auth::silent_login(app_handle.clone(), endpoints, account_id)).await;
+            match silent {
+                Ok((flow, oauth_token)) => (flow, oauth_token, WebviewProfile::account(account_id)),
+                Err(Error::InteractionRequired) => {
+                    log::info!("Silent login for account {} needs interaction. Opening the login window.", account_id);
+                    let (flow, oauth_token) = interactive_login(app_handle, endpoints, cancel, fresh_profile).await?;
+                    (flow, oauth_token, fresh_profile.clone())
+                }
+                Err(e) => return Err(e)
+            }
+        }
+        None => {
+            let (flow, oauth_token) = interactive_login(app_handle, endpoints, cancel, fresh_profile).await?;
+            (flow, oauth_token, fresh_profile.clone())
+        }
+    };
     
     // Emit event to update button text to "Creating Session..."
     app_handle.emit("login-progress", "Getting Session...")?;
-    let game_session = auth::game_session(app_handle.clone(), flow.clone(), oauth_token.clone()).await?;
+    let game_session = login::stage(cancel, "getting the game session", REQUEST_TIMEOUT,
+        auth::game_session(app_handle.clone(), endpoints, oauth_token.clone(), &profile)).await?;
 
     app_handle.emit("login-progress", "Getting Characters...")?;
-    let characters = auth::characters(game_session.clone()).await?;
+    let characters = login::stage(cancel, "getting characters", REQUEST_TIMEOUT,
+        auth::characters(endpoints, game_session.clone())).await?;
 
     // Create account with character data
 
-    let account_info = auth::account_info(flow.clone(), oauth_token.clone()).await?;
+    let account_info = login::stage(cancel, "getting account info", REQUEST_TIMEOUT,
+        auth::account_info(endpoints, flow.clone(), oauth_token.clone())).await?;
     let account = auth::Account {
-        email: account_info.nickname, // TODO: Extract from OAuth token
-        account_name: account_info.display_name, // TODO: Extract from session data
+        id: account_info.user_id.clone(),
+        email: account_info.nickname.clone(), // TODO: Extract from OAuth token
+        account_name: account_info.display_name.clone(), // TODO: Extract from session data
         characters: characters.clone()
     };
 
+    let secrets = accounts::AccountSecrets::new(oauth_token.clone(), Some(game_session.clone()));
+    accounts::save_account(app_handle, &account, &account_info, &secrets)?;
+    // Logging an account in again replaces its game session.
+    credentials::renew_account(app_handle, &account.id, &game_session);
+    app_handle.state::<TokenLifecycle>().track(&account.id, secrets.token_expires_at);
+
     // Emit event with account data
-    app_handle.emit("account-added", account)?;
+    app_handle.emit("account-added", &account)?;
     
     // Emit event to restore button text to original state
     app_handle.emit("login-complete", "")?;
     
     log::debug!("Game Session: {:?}", game_session);
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(account_info.user_id)
+}
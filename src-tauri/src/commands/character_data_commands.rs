@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use tauri::{Manager, Runtime};
+use crate::error;
+use crate::error::Error;
+use crate::state::{character_data, credentials, launch};
+use crate::state::character_data::DataUsage;
+use crate::state::supervisor::Supervisor;
+
+/// How much disk space a character's client data takes, and how much of that is caches.
+#[tauri::command]
+pub async fn get_character_data_usage<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: String
+) -> error::Result<DataUsage> {
+    let dir = character_data::data_dir(&app_handle, &character_id)?;
+    tauri::async_runtime::spawn_blocking(move || character_data::usage(&character_id, &dir)).await?
+}
+
+/// Copies the client settings of one character to another. Neither may be running. Returns the number of files copied.
+#[tauri::command]
+pub async fn copy_character_settings<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    from_character_id: String,
+    to_character_id: String
+) -> error::Result<u64> {
+    if from_character_id == to_character_id {
+        return Err(Error::InvalidInput("Settings cannot be copied onto the same character".to_string()));
+    }
+    let from = character_data::data_dir(&app_handle, &from_character_id)?;
+    let to = character_data::data_dir(&app_handle, &to_character_id)?;
+
+    // Holding both slots keeps either character from being launched halfway through the copy,
+    // and fails if one is running: a running client may be halfway through writing its settings.
+    let supervisor = app_handle.state::<Supervisor>();
+    let _source = supervisor.reserve(&from_character_id)?;
+    let _destination = supervisor.reserve(&to_character_id)?;
+    let copied = tauri::async_runtime::spawn_blocking(move || character_data::copy_settings(&from, &to)).await??;
+
+    log::info!("Copied {} settings files from character {} to {}.", copied, from_character_id, to_character_id);
+    Ok(copied)
+}
+
+/// Deletes the client caches of a character that is not running. Returns the number of bytes freed.
+#[tauri::command]
+pub async fn wipe_character_cache<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: String
+) -> error::Result<u64> {
+    let dir = character_data::data_dir(&app_handle, &character_id)?;
+
+    let supervisor = app_handle.state::<Supervisor>();
+    let _reservation = supervisor.reserve(&character_id)?;
+    let freed = tauri::async_runtime::spawn_blocking(move || character_data::wipe_caches(&dir)).await??;
+
+    log::info!("Wiped {} bytes of caches of character {}.", freed, character_id);
+    Ok(freed)
+}
+
+/// Writes the session of a character to its RuneLite `credentials.properties`, so RuneLite can be
+/// started from scripts or an IDE. The file is removed again when the session is revoked.
+#[tauri::command]
+pub async fn write_runelite_credentials<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String
+) -> error::Result<PathBuf> {
+    let (session, character) = launch::validated_session(&app_handle, &account_id, &character_id).await?;
+    credentials::write_credentials(&app_handle, &character_id, &launch::session_env(&session, &character))
+}
+
+/// Deletes the RuneLite `credentials.properties` of a character. Returns false if there was none.
+#[tauri::command]
+pub async fn remove_runelite_credentials<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: String
+) -> error::Result<bool> {
+    credentials::remove_credentials(&app_handle, &character_id)
+}
@@ -0,0 +1,27 @@
+use tauri::Runtime;
+use crate::error;
+use crate::state::{accounts, hiscores};
+use crate::state::auth::GameCharacter;
+use crate::state::hiscores::{Game, Hiscore};
+
+/// The skills and activities of `player` on the hiscores of `game`, cached for a few minutes.
+#[tauri::command]
+pub async fn lookup_hiscores<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    game: Game,
+    player: String
+) -> error::Result<Hiscore> {
+    hiscores::lookup(&app_handle, game, &player).await
+}
+
+/// The characters of an account with their levels looked up on the hiscores. Kept out of
+/// `list_accounts` so the accounts show up without waiting for the hiscores.
+#[tauri::command]
+pub async fn get_character_levels<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String
+) -> error::Result<Vec<GameCharacter>> {
+    let mut characters = accounts::get_account(&app_handle, &account_id)?.characters;
+    hiscores::attach_levels(&app_handle, &account_id, &mut characters).await;
+    Ok(characters)
+}
@@ -0,0 +1,34 @@
+use tauri::Runtime;
+use crate::error;
+use crate::state::installer;
+use crate::state::installer::InstalledClient;
+
+/// Downloads and installs RuneLite, replacing any previous install once the download is verified.
+#[tauri::command]
+pub async fn install_runelite<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<InstalledClient> {
+    installer::install_runelite(&app_handle).await
+}
+
+/// Downloads the native RS3 Linux client package and installs the client from it.
+#[tauri::command]
+pub async fn install_rs3<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<InstalledClient> {
+    installer::install_rs3(&app_handle).await
+}
+
+#[tauri::command]
+pub async fn get_installed_rs3<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Option<InstalledClient>> {
+    installer::installed_rs3(&app_handle)
+}
+
+#[tauri::command]
+pub async fn get_installed_runelite<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Option<InstalledClient>> {
+    installer::installed_runelite(&app_handle)
+}
@@ -0,0 +1,55 @@
+use tauri::Runtime;
+use crate::error;
+use crate::state::java;
+use crate::state::java::{JavaRuntime, JavaSelection, JavaSource};
+use crate::state::launch::ClientType;
+
+/// The managed runtimes and every Java installation found on the system.
+#[tauri::command]
+pub async fn list_java_runtimes<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Vec<JavaRuntime>> {
+    java::runtimes(&app_handle).await
+}
+
+/// Downloads and installs the pinned runtime of the configured Java manifest.
+#[tauri::command]
+pub async fn install_java_runtime<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<JavaRuntime> {
+    java::install_runtime(&app_handle).await
+}
+
+#[tauri::command]
+pub async fn get_java_source<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<JavaSource> {
+    java::java_source(&app_handle)
+}
+
+/// Sets the manifest managed runtimes are downloaded from and the runtime in it to install.
+#[tauri::command]
+pub async fn set_java_source<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    source: JavaSource
+) -> error::Result<()> {
+    java::set_java_source(&app_handle, &source)
+}
+
+#[tauri::command]
+pub async fn get_java_runtime<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    client: ClientType
+) -> error::Result<Option<JavaSelection>> {
+    java::pinned_runtime(&app_handle, client)
+}
+
+/// Pins the runtime `client` launches with, or goes back to picking one automatically when `selection` is null.
+#[tauri::command]
+pub async fn pin_java_runtime<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    client: ClientType,
+    selection: Option<JavaSelection>
+) -> error::Result<()> {
+    java::pin_runtime(&app_handle, client, selection)
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+use tauri::{Manager, Runtime};
+use tauri_plugin_dialog::DialogExt;
+use crate::error;
+use crate::state::{client_templates, launch};
+use crate::state::client_templates::ClientTemplate;
+use crate::state::launch::{ClientConfig, ClientType};
+use crate::state::launch_profiles::LaunchProfile;
+use crate::state::relaunch::Relauncher;
+use crate::state::supervisor::{RunningClient, Supervisor};
+use crate::state::wrappers::DryRun;
+
+/// Starts a client logged in as one of the account's characters with its launch profile applied.
+/// The client is `client`, or else the one the profile picks, or else the configured one. Returns the pid.
+#[tauri::command]
+pub async fn launch_character<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String,
+    client: Option<ClientType>
+) -> error::Result<u32> {
+    let overrides = LaunchProfile { client, ..LaunchProfile::default() };
+    launch::launch_character(&app_handle, &account_id, &character_id, &overrides).await
+}
+
+/// Resolves a launch like `launch_character` does, wrappers and Wine included, but only returns the
+/// command line it would run. The session id is redacted.
+#[tauri::command]
+pub async fn dry_run_launch<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String,
+    client: Option<ClientType>
+) -> error::Result<DryRun> {
+    let overrides = LaunchProfile { client, ..LaunchProfile::default() };
+    launch::dry_run(&app_handle, &account_id, &character_id, &overrides).await
+}
+
+#[tauri::command]
+pub async fn list_running_clients<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Vec<RunningClient>> {
+    Ok(app_handle.state::<Supervisor>().running())
+}
+
+/// Kills the client running for a character, or cancels its pending relaunch after a crash.
+/// Returns false if it was neither running nor about to be relaunched.
+#[tauri::command]
+pub async fn kill_client<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: String
+) -> error::Result<bool> {
+    Ok(app_handle.state::<Supervisor>().kill(&character_id) || app_handle.state::<Relauncher>().cancel(&character_id))
+}
+
+/// The clients declared in the client definitions file, or an error naming the first invalid one.
+#[tauri::command]
+pub async fn list_client_templates<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Vec<ClientTemplate>> {
+    client_templates::load(&app_handle)
+}
+
+#[tauri::command]
+pub async fn get_client_config<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<Option<ClientConfig>> {
+    match launch::client_config(&app_handle) {
+        Ok(config) => Ok(Some(config)),
+        Err(error::Error::ClientNotConfigured) => Ok(None),
+        Err(e) => Err(e)
+    }
+}
+
+#[tauri::command]
+pub async fn set_client_config<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    config: ClientConfig
+) -> error::Result<()> {
+    launch::set_client_config(&app_handle, &config)
+}
+
+/// Lets the user pick the client executable with a file dialog. Returns false if they cancelled.
+#[tauri::command]
+pub async fn pick_client_executable<R: Runtime>(
+    app_handle: tauri::AppHandle<R>
+) -> error::Result<bool> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app_handle.dialog().file()
+        .set_title("Choose the game client")
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(false);
+    };
+    let program = path.into_path()
+        .map_err(|e| error::Error::LocalState(format!("Unsupported client path: {}", e)))?;
+
+    let config = ClientConfig {
+        working_dir: program.parent().map(PathBuf::from),
+        program,
+        args: Vec::new(),
+        env: Default::default()
+    };
+    launch::set_client_config(&app_handle, &config)?;
+    Ok(true)
+}
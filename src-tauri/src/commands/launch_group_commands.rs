@@ -0,0 +1,36 @@
+use tauri::Runtime;
+use crate::error;
+use crate::state::launch_groups;
+use crate::state::launch_groups::{GroupReport, LaunchGroup};
+
+#[tauri::command]
+pub async fn list_launch_groups<R: Runtime>(app_handle: tauri::AppHandle<R>) -> error::Result<Vec<LaunchGroup>> {
+    launch_groups::list_groups(&app_handle)
+}
+
+/// Creates the group when it has no id yet, otherwise replaces it. Returns the stored group.
+#[tauri::command]
+pub async fn save_launch_group<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    group: LaunchGroup
+) -> error::Result<LaunchGroup> {
+    launch_groups::save_group(&app_handle, group)
+}
+
+#[tauri::command]
+pub async fn delete_launch_group<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    group_id: String
+) -> error::Result<()> {
+    launch_groups::delete_group(&app_handle, &group_id)
+}
+
+/// Validates the sessions of every member, then starts them in order. Progress is emitted as
+/// `launch-group-progress`; the returned report has the final state of each member.
+#[tauri::command]
+pub async fn launch_group<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    group_id: String
+) -> error::Result<GroupReport> {
+    launch_groups::launch_group(&app_handle, &group_id).await
+}
@@ -0,0 +1,43 @@
+use tauri::Runtime;
+use crate::error;
+use crate::state::launch_profiles;
+use crate::state::launch_profiles::{AccountProfiles, LaunchProfile};
+
+/// The default launch profile of an account and the overrides of its characters.
+#[tauri::command]
+pub async fn get_launch_profiles<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String
+) -> error::Result<AccountProfiles> {
+    launch_profiles::account_profiles(&app_handle, &account_id)
+}
+
+#[tauri::command]
+pub async fn set_account_launch_profile<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    profile: LaunchProfile
+) -> error::Result<()> {
+    launch_profiles::set_account_profile(&app_handle, &account_id, profile)
+}
+
+/// Overrides the account default for one character. A null `profile` makes it use the default again.
+#[tauri::command]
+pub async fn set_character_launch_profile<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String,
+    profile: Option<LaunchProfile>
+) -> error::Result<()> {
+    launch_profiles::set_character_profile(&app_handle, &account_id, &character_id, profile)
+}
+
+/// The profile a character is launched with once the account default is applied.
+#[tauri::command]
+pub async fn get_effective_launch_profile<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String
+) -> error::Result<LaunchProfile> {
+    launch_profiles::effective_profile(&app_handle, &account_id, &character_id)
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use tauri::ipc::Channel;
+use tauri::{Manager, Runtime};
+use tauri_plugin_opener::OpenerExt;
+use crate::error;
+use crate::state::client_logs;
+use crate::state::client_logs::{ClientLogs, LogLine, LogSession};
+
+/// Past and running client sessions of a character, newest first.
+#[tauri::command]
+pub async fn list_log_sessions<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: String
+) -> error::Result<Vec<LogSession>> {
+    client_logs::sessions(&app_handle, &character_id)
+}
+
+/// Streams a session log to `on_line`, following it while the client runs.
+#[tauri::command]
+pub async fn tail_client_log<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: String,
+    session_id: String,
+    on_line: Channel<LogLine>
+) -> error::Result<()> {
+    client_logs::tail(&app_handle, &character_id, &session_id, on_line).await
+}
+
+/// Stops a tail started with `tail_client_log`. Returns false if it had already ended.
+#[tauri::command]
+pub async fn stop_client_log_tail<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    channel_id: u32
+) -> error::Result<bool> {
+    Ok(app_handle.state::<ClientLogs>().stop_tail(channel_id))
+}
+
+/// Opens the client logs of a character, or of all characters, in the file manager.
+#[tauri::command]
+pub async fn open_client_logs_folder<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    character_id: Option<String>
+) -> error::Result<()> {
+    let dir = client_logs::logs_dir(&app_handle, character_id.as_deref())?;
+    fs::create_dir_all(&dir)?;
+    app_handle.opener().open_path(dir.to_string_lossy(), None::<&str>)?;
+    Ok(())
+}
@@ -1 +1,11 @@
-pub mod auth_commands;
\ No newline at end of file
+pub mod auth_commands;
+pub mod account_commands;
+pub mod character_data_commands;
+pub mod hiscores_commands;
+pub mod install_commands;
+pub mod java_commands;
+pub mod launch_commands;
+pub mod launch_group_commands;
+pub mod launch_profile_commands;
+pub mod log_commands;
+pub mod playtime_commands;
@@ -0,0 +1,50 @@
+use tauri::Runtime;
+use crate::error;
+use crate::state::playtime;
+use crate::state::playtime::{AccountPlaytime, DailyPlaytime, PlaySession, PlaytimeSummary};
+
+/// Every recorded session of a character, oldest first.
+#[tauri::command]
+pub async fn list_play_sessions<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String
+) -> error::Result<Vec<PlaySession>> {
+    let mut sessions = playtime::sessions(&app_handle, &account_id)?;
+    sessions.retain(|session| session.character_id == character_id);
+    Ok(sessions)
+}
+
+#[tauri::command]
+pub async fn get_character_playtime<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: String
+) -> error::Result<PlaytimeSummary> {
+    let sessions = playtime::sessions(&app_handle, &account_id)?;
+    Ok(playtime::summarize(sessions.iter().filter(|session| session.character_id == character_id)))
+}
+
+/// The play time of an account in total and per character.
+#[tauri::command]
+pub async fn get_account_playtime<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String
+) -> error::Result<AccountPlaytime> {
+    Ok(playtime::account_summary(&account_id, &playtime::sessions(&app_handle, &account_id)?))
+}
+
+/// Play time per day of an account, or of one of its characters. `utc_offset_minutes` is the
+/// offset of the user's time zone, e.g. 120 for UTC+2, so days start at local midnight.
+#[tauri::command]
+pub async fn get_daily_playtime<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    account_id: String,
+    character_id: Option<String>,
+    utc_offset_minutes: i64
+) -> error::Result<Vec<DailyPlaytime>> {
+    let sessions = playtime::sessions(&app_handle, &account_id)?;
+    let sessions = sessions.iter()
+        .filter(|session| character_id.as_ref().is_none_or(|id| &session.character_id == id));
+    Ok(playtime::daily(sessions, utc_offset_minutes * 60))
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{fmt, io};
 use std::ops::Deref;
 use openidconnect::core::CoreErrorResponseType;
 use serde::{Serialize, Serializer};
@@ -10,6 +10,31 @@ pub enum Error {
     #[error("Error: {0}")]
     Reason(String),
 
+    #[error("{api} request failed with status {status}: {}", .error.as_deref().unwrap_or("no error details"))]
+    JagexApi {
+        api: JagexApi,
+        status: u16,
+        error: Option<String>
+    },
+
+    #[error("Unexpected {api} response: {reason}")]
+    UnexpectedResponse {
+        api: JagexApi,
+        reason: String
+    },
+
+    #[error("Provided state from OAuth flow does not match the CSRF token.")]
+    LoginStateMismatch,
+
+    #[error("The identity provider requires the user to log in interactively.")]
+    InteractionRequired,
+
+    #[error("{0}")]
+    Cancelled(String),
+
+    #[error("{0}")]
+    Timeout(String),
+
     #[error("Redirect URL does not contain the expected parameters: {0}")]
     InvalidRedirectUrl(String),
 
@@ -36,6 +61,381 @@ pub enum Error {
     
     #[error(transparent)]
     JWTError(#[from] jsonwebtoken::errors::Error),
+
+    #[error(transparent)]
+    Store(#[from] tauri_plugin_store::Error),
+
+    #[error(transparent)]
+    Opener(#[from] tauri_plugin_opener::Error),
+
+    #[error("ID token verification failed: {0}")]
+    IdTokenVerification(String),
+
+    #[error("ID token nonce does not match the one sent with the request.")]
+    NonceMismatch,
+
+    #[error("Game session redirect state does not match the one sent with the request.")]
+    SessionStateMismatch,
+
+    #[error("Game session redirect has already been used.")]
+    SessionReplayed,
+
+    #[error("No stored account with id {0}")]
+    AccountNotFound(String),
+
+    #[error("Account vault error: {0}")]
+    Vault(String),
+
+    #[error("The game session of account {0} has expired.")]
+    SessionExpired(String),
+
+    #[error("Account has no character with id {0}")]
+    CharacterNotFound(String),
+
+    #[error("No game client is configured.")]
+    ClientNotConfigured,
+
+    #[error("Character {0} is already running.")]
+    ClientAlreadyRunning(String),
+
+    #[error("No client log session {0}")]
+    LogSessionNotFound(String),
+
+    #[error("Download of {url} failed with status {status}")]
+    DownloadFailed {
+        url: String,
+        status: u16
+    },
+
+    #[error("Invalid client manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("Checksum of {artifact} does not match: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        artifact: String,
+        expected: String,
+        actual: String
+    },
+
+    #[error("A client install is already running.")]
+    InstallInProgress,
+
+    #[error("Invalid client package: {0}")]
+    InvalidPackage(String),
+
+    #[error("Unable to run {tool} to unpack {package}: {reason}")]
+    UnpackToolMissing {
+        tool: String,
+        package: String,
+        reason: String
+    },
+
+    #[error("The {0} client is not installed.")]
+    ClientNotInstalled(String),
+
+    #[error("No usable Java runtime: {0}")]
+    JavaNotFound(String),
+
+    #[error("No Java runtime manifest is configured.")]
+    JavaSourceNotConfigured,
+
+    #[error("Invalid launch profile: {0}")]
+    InvalidLaunchProfile(String),
+
+    #[error("No client named {0} in the client definitions")]
+    ClientTemplateNotFound(String),
+
+    #[error("No client data directory at {0}")]
+    CharacterDataNotFound(String),
+
+    #[error("Invalid client template {name}: {reason}")]
+    InvalidClientTemplate {
+        name: String,
+        reason: String
+    },
+
+    #[error("No launch group {0}")]
+    LaunchGroupNotFound(String),
+
+    #[error("Invalid launch group: {0}")]
+    InvalidLaunchGroup(String),
+
+    #[error("{0} is not on the hiscores")]
+    HiscoresNotFound(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Local state error: {0}")]
+    LocalState(String),
+
+    #[error("{0} is not supported on this platform")]
+    Unsupported(String),
+}
+
+/// The Jagex API a request was made to, used to tell failures of the login pipeline apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JagexApi {
+    Token,
+    GameSession,
+    Characters,
+    DisplayName,
+    Hiscores
+}
+
+impl fmt::Display for JagexApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JagexApi::Token => "Token",
+            JagexApi::GameSession => "Game session",
+            JagexApi::Characters => "Characters",
+            JagexApi::DisplayName => "Display name",
+            JagexApi::Hiscores => "Hiscores"
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCategory {
+    Network,
+    AuthRejected,
+    Cancelled,
+    Server,
+    LocalIo
+}
+
+/// The shape errors take when they cross the IPC boundary.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorPayload {
+    pub code: &'static str,
+    pub category: ErrorCategory,
+    pub retryable: bool,
+    pub message: String,
+    pub hints: Vec<String>,
+    pub detail: String
+}
+
+impl Error {
+    /// A stable identifier the frontend can match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Reason(_) => "unknown",
+            Error::JagexApi { .. } => "jagex-api",
+            Error::UnexpectedResponse { .. } => "unexpected-response",
+            Error::LoginStateMismatch => "login-state-mismatch",
+            Error::InteractionRequired => "interaction-required",
+            Error::Cancelled(_) => "cancelled",
+            Error::Timeout(_) => "timeout",
+            Error::InvalidRedirectUrl(_) => "invalid-redirect",
+            Error::Io(_) => "io",
+            Error::TauriError(_) | Error::TauriPlugin(_) => "tauri",
+            Error::TauriHttp(_) => "network",
+            Error::Auth(_) => "auth-rejected",
+            Error::AuthUrlParse(_) => "invalid-url",
+            Error::SerdeJson(_) => "malformed-response",
+            Error::JWTError(_) => "invalid-token",
+            Error::Store(_) => "store",
+            Error::Opener(_) => "opener",
+            Error::IdTokenVerification(_) => "id-token-verification",
+            Error::NonceMismatch => "nonce-mismatch",
+            Error::SessionStateMismatch => "session-state-mismatch",
+            Error::SessionReplayed => "session-replayed",
+            Error::AccountNotFound(_) => "account-not-found",
+            Error::Vault(_) => "vault",
+            Error::SessionExpired(_) => "session-expired",
+            Error::CharacterNotFound(_) => "character-not-found",
+            Error::ClientNotConfigured => "client-not-configured",
+            Error::ClientAlreadyRunning(_) => "client-already-running",
+            Error::LogSessionNotFound(_) => "log-session-not-found",
+            Error::DownloadFailed { .. } => "download-failed",
+            Error::InvalidManifest(_) => "invalid-manifest",
+            Error::ChecksumMismatch { .. } => "checksum-mismatch",
+            Error::InstallInProgress => "install-in-progress",
+            Error::InvalidPackage(_) => "invalid-package",
+            Error::UnpackToolMissing { .. } => "unpack-tool-missing",
+            Error::ClientNotInstalled(_) => "client-not-installed",
+            Error::JavaNotFound(_) => "java-not-found",
+            Error::JavaSourceNotConfigured => "java-source-not-configured",
+            Error::InvalidLaunchProfile(_) => "invalid-launch-profile",
+            Error::InvalidClientTemplate { .. } => "invalid-client-template",
+            Error::ClientTemplateNotFound(_) => "client-template-not-found",
+            Error::CharacterDataNotFound(_) => "character-data-not-found",
+            Error::LaunchGroupNotFound(_) => "launch-group-not-found",
+            Error::InvalidLaunchGroup(_) => "invalid-launch-group",
+            Error::HiscoresNotFound(_) => "hiscores-not-found",
+            Error::InvalidInput(_) => "invalid-input",
+            Error::LocalState(_) => "local-state",
+            Error::Unsupported(_) => "unsupported"
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::TauriHttp(_) | Error::Timeout(_) | Error::ChecksumMismatch { .. } => ErrorCategory::Network,
+            Error::DownloadFailed { status, .. } if *status >= 500 => ErrorCategory::Server,
+            Error::DownloadFailed { .. } => ErrorCategory::Network,
+            Error::Cancelled(_) => ErrorCategory::Cancelled,
+            Error::JagexApi { status, .. } if *status >= 500 => ErrorCategory::Server,
+            // The hiscores are public, so they cannot reject a login.
+            Error::JagexApi { api: JagexApi::Hiscores, .. } | Error::HiscoresNotFound(_) => ErrorCategory::Server,
+            Error::JagexApi { .. }
+            | Error::Auth(_)
+            | Error::LoginStateMismatch
+            | Error::InteractionRequired
+            | Error::InvalidRedirectUrl(_)
+            | Error::JWTError(_)
+            | Error::IdTokenVerification(_)
+            | Error::NonceMismatch
+            | Error::SessionStateMismatch
+            | Error::SessionReplayed
+            | Error::SessionExpired(_) => ErrorCategory::AuthRejected,
+            Error::Io(_)
+            | Error::TauriError(_)
+            | Error::TauriPlugin(_)
+            | Error::Store(_)
+            | Error::Opener(_)
+            | Error::AccountNotFound(_)
+            | Error::CharacterNotFound(_)
+            | Error::Vault(_)
+            | Error::ClientNotConfigured
+            | Error::ClientAlreadyRunning(_)
+            | Error::LogSessionNotFound(_)
+            | Error::InstallInProgress
+            | Error::UnpackToolMissing { .. }
+            | Error::ClientNotInstalled(_)
+            | Error::JavaNotFound(_)
+            | Error::JavaSourceNotConfigured
+            | Error::InvalidLaunchProfile(_)
+            | Error::InvalidClientTemplate { .. }
+            | Error::ClientTemplateNotFound(_)
+            | Error::CharacterDataNotFound(_)
+            | Error::LaunchGroupNotFound(_)
+            | Error::InvalidLaunchGroup(_)
+            | Error::InvalidInput(_)
+            | Error::LocalState(_)
+            | Error::Unsupported(_) => ErrorCategory::LocalIo,
+            Error::Reason(_)
+            | Error::UnexpectedResponse { .. }
+            | Error::AuthUrlParse(_)
+            | Error::SerdeJson(_)
+            | Error::InvalidManifest(_)
+            | Error::InvalidPackage(_) => ErrorCategory::Server
+        }
+    }
+
+    /// Whether simply trying the same operation again has a reasonable chance of succeeding.
+    pub fn retryable(&self) -> bool {
+        match self {
+            Error::JagexApi { status, .. } | Error::DownloadFailed { status, .. } => *status >= 500 || *status == 429,
+            // Usually a download that was cut short or mangled on the way.
+            Error::ChecksumMismatch { .. } => true,
+            Error::TauriHttp(_) | Error::Timeout(_) | Error::Cancelled(_) | Error::UnexpectedResponse { .. } | Error::SerdeJson(_) => true,
+            // A fresh login generates a new state and nonce.
+            Error::LoginStateMismatch | Error::NonceMismatch | Error::SessionStateMismatch | Error::SessionReplayed => true,
+            _ => false
+        }
+    }
+
+    /// For JSON the launcher stored itself, which is a local problem rather than a bad server response.
+    pub fn local_state(e: serde_json::Error) -> Error {
+        Error::LocalState(e.to_string())
+    }
+
+    /// A short explanation suitable for showing to the user.
+    pub fn message(&self) -> String {
+        match self.category() {
+            ErrorCategory::Network => match self {
+                // Each timeout says what took too long.
+                Error::Timeout(reason) => reason.clone(),
+                Error::ChecksumMismatch { .. } => "A downloaded client file was corrupted.".to_string(),
+                Error::DownloadFailed { .. } => "The client could not be downloaded.".to_string(),
+                _ => "Unable to reach the Jagex servers.".to_string()
+            },
+            ErrorCategory::Cancelled => match self {
+                Error::Cancelled(reason) => reason.clone(),
+                _ => "The operation was cancelled.".to_string()
+            },
+            ErrorCategory::AuthRejected => match self {
+                Error::SessionExpired(_) => "The session of this account has expired.".to_string(),
+                _ => "Jagex rejected the login.".to_string()
+            },
+            ErrorCategory::Server => match self {
+                Error::HiscoresNotFound(_) => "This character is not ranked on the hiscores yet.".to_string(),
+                Error::JagexApi { api: JagexApi::Hiscores, .. } => "The hiscores could not be loaded.".to_string(),
+                Error::InvalidManifest(_) => "The client download server returned an unusable manifest.".to_string(),
+                Error::InvalidPackage(_) => "The downloaded client package could not be unpacked.".to_string(),
+                Error::DownloadFailed { .. } => "The client download server is having problems.".to_string(),
+                _ => "The Jagex servers returned an unexpected response.".to_string()
+            },
+            ErrorCategory::LocalIo => match self {
+                Error::AccountNotFound(_) => "This account is no longer stored in the launcher.".to_string(),
+                Error::CharacterNotFound(_) => "This character is not among the stored characters of the account.".to_string(),
+                Error::Vault(_) => "Unable to read the stored account credentials.".to_string(),
+                Error::ClientNotConfigured => "No game client has been chosen yet.".to_string(),
+                Error::ClientAlreadyRunning(_) => "This character is already running.".to_string(),
+                Error::LogSessionNotFound(_) => "This client log no longer exists.".to_string(),
+                Error::InstallInProgress => "The client is already being installed.".to_string(),
+                Error::UnpackToolMissing { tool, .. } => format!("The {} program needed to unpack the download could not be run.", tool),
+                Error::ClientNotInstalled(_) => "This client is not installed yet.".to_string(),
+                Error::JavaNotFound(_) => "No Java runtime that can start this client was found.".to_string(),
+                Error::JavaSourceNotConfigured => "No download source for Java runtimes has been set up.".to_string(),
+                Error::InvalidLaunchProfile(_) => "The launch profile contains invalid settings.".to_string(),
+                Error::InvalidClientTemplate { .. } => "A client in the client definitions file is invalid.".to_string(),
+                Error::ClientTemplateNotFound(_) => "This client is no longer in the client definitions file.".to_string(),
+                Error::CharacterDataNotFound(_) => "This character has no client data yet.".to_string(),
+                Error::LaunchGroupNotFound(_) => "This launch group no longer exists.".to_string(),
+                Error::InvalidLaunchGroup(_) => "The launch group is not valid.".to_string(),
+                Error::InvalidInput(reason) => reason.clone(),
+                Error::Unsupported(_) => "This is not supported on your operating system.".to_string(),
+                _ => "The launcher could not access its local files.".to_string()
+            }
+        }
+    }
+
+    pub fn hints(&self) -> Vec<String> {
+        let hints: &[&str] = match self {
+            Error::TauriHttp(_) | Error::Timeout(_) => &["Check your internet connection and try again."],
+            Error::JagexApi { status, .. } if *status >= 500 || *status == 429 => &["The Jagex services may be busy or down. Try again in a few minutes."],
+            Error::JagexApi { api: JagexApi::Hiscores, .. } => &["Try again later."],
+            Error::HiscoresNotFound(_) => &["Characters show up on the hiscores once they have trained a skill past the ranking threshold."],
+            Error::JagexApi { .. } | Error::Auth(_) | Error::IdTokenVerification(_) | Error::InteractionRequired | Error::SessionExpired(_) =>
+                &["Log in to the Jagex account again."],
+            Error::CharacterNotFound(_) => &["Log in to the Jagex account again to refresh its characters."],
+            Error::ClientNotConfigured => &["Choose the game client executable to launch."],
+            Error::ClientAlreadyRunning(_) => &["Close the running client first, or stop it from the launcher."],
+            Error::ChecksumMismatch { .. } => &["Try installing again. If it keeps failing, something may be altering downloads."],
+            Error::DownloadFailed { .. } | Error::InvalidManifest(_) => &["Check your internet connection, or try again later."],
+            Error::InstallInProgress => &["Wait for the running install to finish."],
+            Error::UnpackToolMissing { .. } => &["Install it with your system's package manager and try again."],
+            Error::ClientNotInstalled(_) => &["Install the client from the launcher first."],
+            Error::JavaNotFound(_) => &["Install the managed Java runtime, or install Java 11 or newer and pick it for this client."],
+            Error::JavaSourceNotConfigured => &["Set the runtime manifest URL in the Java settings, or install Java 11 or newer yourself."],
+            Error::InvalidLaunchProfile(_) => &["Use absolute paths and leave the JX_ variables to the launcher."],
+            Error::CharacterDataNotFound(_) => &["Launch the character once so its client creates its settings."],
+            Error::InvalidLaunchGroup(_) => &["Give the group a name and add each character to it once."],
+            Error::InvalidClientTemplate { .. } => &["Fix the client definitions file. Placeholders are {session_id}, {character_id}, {display_name}, {account_id} and {data_dir}."],
+            Error::ClientTemplateNotFound(_) => &["Add the client back to clients.toml in the launcher config directory, or choose another client."],
+            Error::LoginStateMismatch | Error::NonceMismatch | Error::SessionStateMismatch | Error::SessionReplayed =>
+                &["Start the login again. If this keeps happening, something may be interfering with the login window."],
+            Error::Vault(_) => &["Remove the account and log in again."],
+            Error::Io(_) | Error::Store(_) => &["Make sure the launcher data directory is writable."],
+            _ => &[]
+        };
+        hints.iter().map(|hint| hint.to_string()).collect()
+    }
+
+    pub fn payload(&self) -> ErrorPayload {
+        ErrorPayload {
+            code: self.code(),
+            category: self.category(),
+            retryable: self.retryable(),
+            message: self.message(),
+            hints: self.hints(),
+            detail: self.to_string()
+        }
+    }
 }
 
 impl Serialize for Error {
@@ -43,6 +443,6 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        self.payload().serialize(serializer)
     }
 }
\ No newline at end of file
@@ -7,12 +7,27 @@ use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri_plugin_log::fern;
 use crate::runner::build_tauri_app;
-use crate::commands::auth_commands;
+use crate::commands::{account_commands, auth_commands, character_data_commands, hiscores_commands, install_commands, java_commands, launch_commands, launch_group_commands, launch_profile_commands, log_commands, playtime_commands};
+use crate::state::client_logs::ClientLogs;
+use crate::state::endpoints::AuthEndpoints;
+use crate::state::hiscores::HiscoresCache;
+use crate::state::installer::ClientInstaller;
+use crate::state::login::{ConsumedSessionStates, LoginState};
+use crate::state::playtime::PlaytimeRecorder;
+use crate::state::relaunch::Relauncher;
+use crate::state::supervisor::Supervisor;
+use crate::state::tokens;
+use crate::state::webview_profiles;
+
+/// Id of the system tray icon, so its tooltip can be updated later.
+pub const TRAY_ID: &str = "main";
 
 mod runner;
 pub mod error;
 pub mod commands;
 pub mod state;
+#[cfg(any(test, feature = "mock-identity"))]
+mod test_support;
 
 #[tokio::main]
 async fn main() { 
@@ -23,6 +38,7 @@ async fn main() {
             handle.plugin(tauri_plugin_opener::init())?;
             handle.plugin(tauri_plugin_fs::init())?;
             handle.plugin(tauri_plugin_http::init())?;
+            handle.plugin(tauri_plugin_store::Builder::default().build())?;
 
             #[cfg(feature = "devtools")]
             {
@@ -57,13 +73,38 @@ async fn main() {
                 }
             }))?;
             handle.plugin(tauri_plugin_dialog::init())?;
+            handle.plugin(tauri_plugin_notification::init())?;
+
+            #[cfg(not(feature = "mock-identity"))]
+            app.manage(AuthEndpoints::load(handle)?);
+            #[cfg(feature = "mock-identity")]
+            {
+                let mock = tauri::async_runtime::block_on(test_support::mock_identity::MockIdentity::start());
+                log::warn!("Logging in against the mock identity provider at {}", mock.base_url);
+                app.manage::<AuthEndpoints>(mock.endpoints());
+            }
+
+            // --- Background token renewal for stored accounts ---
+            app.manage(tokens::TokenLifecycle::default());
+            tokens::start(handle.clone());
+            app.manage(LoginState::default());
+            app.manage(ConsumedSessionStates::default());
+            app.manage(Supervisor::default());
+            app.manage(Relauncher::default());
+            app.manage(PlaytimeRecorder::default());
+            app.manage(ClientLogs::default());
+            app.manage(ClientInstaller::default());
+            app.manage(HiscoresCache::default());
+            if let Err(e) = webview_profiles::remove_stale(handle) {
+                log::error!("Failed to remove leftover login browser profiles: {}", e);
+            }
 
             // --- Initialize System Tray ---
             let show_item = MenuItem::with_id(app, "show", "Open Rune Launcher", true, None::<&str>)?;
             let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let menu = Menu::with_items(app, &[&show_item, &quit_item])?;
 
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .menu(&menu)
                 .show_menu_on_left_click(false)
                 .tooltip("Rune Launcher")
@@ -93,6 +134,53 @@ async fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             auth_commands::login,
+            auth_commands::relogin,
+            auth_commands::cancel_login,
+            account_commands::list_accounts,
+            account_commands::get_account,
+            account_commands::remove_account,
+            account_commands::clear_browser_data,
+            hiscores_commands::lookup_hiscores,
+            hiscores_commands::get_character_levels,
+            character_data_commands::get_character_data_usage,
+            character_data_commands::copy_character_settings,
+            character_data_commands::wipe_character_cache,
+            character_data_commands::write_runelite_credentials,
+            character_data_commands::remove_runelite_credentials,
+            launch_commands::launch_character,
+            launch_commands::dry_run_launch,
+            launch_commands::list_running_clients,
+            launch_commands::kill_client,
+            launch_commands::list_client_templates,
+            launch_commands::get_client_config,
+            launch_commands::set_client_config,
+            launch_commands::pick_client_executable,
+            launch_group_commands::list_launch_groups,
+            launch_group_commands::save_launch_group,
+            launch_group_commands::delete_launch_group,
+            launch_group_commands::launch_group,
+            launch_profile_commands::get_launch_profiles,
+            launch_profile_commands::set_account_launch_profile,
+            launch_profile_commands::set_character_launch_profile,
+            launch_profile_commands::get_effective_launch_profile,
+            log_commands::list_log_sessions,
+            log_commands::tail_client_log,
+            log_commands::stop_client_log_tail,
+            log_commands::open_client_logs_folder,
+            playtime_commands::list_play_sessions,
+            playtime_commands::get_character_playtime,
+            playtime_commands::get_account_playtime,
+            playtime_commands::get_daily_playtime,
+            install_commands::install_runelite,
+            install_commands::get_installed_runelite,
+            install_commands::install_rs3,
+            install_commands::get_installed_rs3,
+            java_commands::list_java_runtimes,
+            java_commands::install_java_runtime,
+            java_commands::get_java_source,
+            java_commands::set_java_source,
+            java_commands::get_java_runtime,
+            java_commands::pin_java_runtime,
         ]);
 
     log::trace!("Trace level");
@@ -103,9 +191,14 @@ async fn main() {
 
     build_tauri_app(builder)
         .expect("Failed to run application")
-        .run(|app_handle, event| {
-            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+        .run(|app_handle, event| match event {
+            tauri::RunEvent::ExitRequested { api, .. } => {
                 log::info!("Exit requested default to allow async tasks to finish if any.");
             }
+            tauri::RunEvent::Exit => {
+                let running = app_handle.state::<Supervisor>().running();
+                app_handle.state::<PlaytimeRecorder>().flush(app_handle, &running);
+            }
+            _ => {}
         });
 }
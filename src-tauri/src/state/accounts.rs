@@ -0,0 +1,217 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use base64::Engine;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+use crate::error;
+use crate::error::Error;
+use crate::state::auth::{Account, AccountInfo, GameSession, OAuthToken};
+
+const ACCOUNTS_STORE: &str = "accounts.json";
+const VAULT_KEY_FILE: &str = "vault.key";
+const NONCE_LEN: usize = 12;
+
+static CIPHER: OnceCell<Aes256Gcm> = OnceCell::new();
+
+/// Everything that allows someone to act as the account. Never written to disk unencrypted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSecrets {
+    pub oauth_token: OAuthToken,
+    pub game_session: Option<GameSession>,
+    /// Unix timestamp (seconds) at which `oauth_token.access_token` stops being valid.
+    #[serde(default)]
+    pub token_expires_at: u64
+}
+
+impl AccountSecrets {
+    pub fn new(oauth_token: OAuthToken, game_session: Option<GameSession>) -> Self {
+        let token_expires_at = unix_now() + oauth_token.expires_in;
+        Self {
+            oauth_token,
+            game_session,
+            token_expires_at
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredAccount {
+    account: Account,
+    info: AccountInfo,
+    /// Base64 of the AES-GCM nonce followed by the encrypted `AccountSecrets` json.
+    secrets: String
+}
+
+pub fn save_account<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account: &Account,
+    info: &AccountInfo,
+    secrets: &AccountSecrets
+) -> error::Result<()> {
+    let stored = StoredAccount {
+        account: account.clone(),
+        info: info.clone(),
+        secrets: encrypt_secrets(app_handle, secrets)?
+    };
+
+    let store = app_handle.store(ACCOUNTS_STORE)?;
+    store.set(account.id.clone(), serde_json::to_value(&stored)?);
+    store.save()?;
+
+    log::info!("Saved account {} to the account vault.", account.id);
+    Ok(())
+}
+
+pub fn list_accounts<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Vec<Account>> {
+    let store = app_handle.store(ACCOUNTS_STORE)?;
+
+    // One unreadable entry should not hide every other account.
+    let mut accounts = Vec::<Account>::new();
+    for (account_id, value) in store.entries() {
+        match serde_json::from_value::<StoredAccount>(value) {
+            Ok(stored) => accounts.push(stored.account),
+            Err(e) => log::error!("Skipping unreadable stored account {}: {}", account_id, e)
+        }
+    }
+    accounts.sort_by(|a, b| a.account_name.cmp(&b.account_name));
+
+    Ok(accounts)
+}
+
+pub fn get_account<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<Account> {
+    Ok(stored_account(app_handle, account_id)?.account)
+}
+
+pub fn account_info<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<AccountInfo> {
+    Ok(stored_account(app_handle, account_id)?.info)
+}
+
+pub fn account_secrets<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<AccountSecrets> {
+    let stored = stored_account(app_handle, account_id)?;
+    decrypt_secrets(app_handle, &stored.secrets)
+}
+
+/// Replaces the secrets of an already stored account, e.g. after a token refresh.
+pub fn update_secrets<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    secrets: &AccountSecrets
+) -> error::Result<()> {
+    let mut stored = stored_account(app_handle, account_id)?;
+    stored.secrets = encrypt_secrets(app_handle, secrets)?;
+
+    let store = app_handle.store(ACCOUNTS_STORE)?;
+    store.set(account_id, serde_json::to_value(&stored)?);
+    store.save()?;
+    Ok(())
+}
+
+pub fn remove_account<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<()> {
+    let store = app_handle.store(ACCOUNTS_STORE)?;
+    if !store.delete(account_id) {
+        return Err(Error::AccountNotFound(account_id.to_string()));
+    }
+    store.save()?;
+
+    log::info!("Removed account {} from the account vault.", account_id);
+    Ok(())
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn stored_account<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<StoredAccount> {
+    let store = app_handle.store(ACCOUNTS_STORE)?;
+    let value = store.get(account_id)
+        .ok_or_else(|| Error::AccountNotFound(account_id.to_string()))?;
+
+    serde_json::from_value::<StoredAccount>(value).map_err(Error::local_state)
+}
+
+fn encrypt_secrets<R: Runtime>(app_handle: &AppHandle<R>, secrets: &AccountSecrets) -> error::Result<String> {
+    let cipher = cipher(app_handle)?;
+    let plaintext = serde_json::to_vec(secrets)?;
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| Error::Vault("Failed to encrypt account secrets.".to_string()))?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(base64::engine::general_purpose::STANDARD.encode(blob))
+}
+
+fn decrypt_secrets<R: Runtime>(app_handle: &AppHandle<R>, encoded: &str) -> error::Result<AccountSecrets> {
+    let cipher = cipher(app_handle)?;
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| Error::Vault(format!("Stored account secrets are not valid base64: {:?}", e)))?;
+
+    if blob.len() <= NONCE_LEN {
+        return Err(Error::Vault("Stored account secrets are truncated.".to_string()));
+    }
+
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| Error::Vault("Failed to decrypt account secrets. The vault key may have changed.".to_string()))?;
+
+    serde_json::from_slice::<AccountSecrets>(&plaintext).map_err(Error::local_state)
+}
+
+/// The vault key lives next to, but outside of, the account store so that a copy of
+/// `accounts.json` on its own does not leak any tokens.
+fn cipher<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<&'static Aes256Gcm> {
+    CIPHER.get_or_try_init(|| {
+        let key_path = vault_key_path(app_handle)?;
+
+        let key_bytes = if key_path.exists() {
+            let key_bytes = fs::read(&key_path)?;
+            if key_bytes.len() != 32 {
+                return Err(Error::Vault(format!("Vault key at {} is corrupt.", key_path.display())));
+            }
+            key_bytes
+        } else {
+            log::info!("No vault key found. Generating a new one at {}", key_path.display());
+            let key = Aes256Gcm::generate_key(OsRng);
+            write_private_file(&key_path, key.as_slice())?;
+            key.to_vec()
+        };
+
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes)))
+    })
+}
+
+fn vault_key_path<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<PathBuf> {
+    let dir = app_handle.path().app_local_data_dir()?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(VAULT_KEY_FILE))
+}
+
+fn write_private_file(path: &PathBuf, contents: &[u8]) -> error::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,24 @@
+use crate::state::auth::GameSession;
+use crate::test_support::mock_app::MockApp;
+use super::*;
+
+fn session() -> GameSession {
+    GameSession {
+        code: "code".to_string(),
+        id_token: "id-token".to_string(),
+        state: "state".to_string(),
+        session_id: "mock-session-id".to_string()
+    }
+}
+
+#[test]
+fn unreadable_accounts_are_skipped() {
+    let app = MockApp::new();
+    let app_handle = app.handle();
+    app.save_account("account-1", Vec::new(), session());
+    app_handle.store(ACCOUNTS_STORE).unwrap().set("account-2", serde_json::json!({ "account": "corrupt" }));
+
+    let listed = list_accounts(app_handle).unwrap();
+    assert_eq!(listed.iter().map(|account| account.id.as_str()).collect::<Vec<_>>(), vec!["account-1"]);
+    assert!(matches!(get_account(app_handle, "account-2"), Err(Error::LocalState(_))));
+}
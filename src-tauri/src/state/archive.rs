@@ -0,0 +1,325 @@
+use std::fs;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use flate2::read::GzDecoder;
+use crate::error;
+use crate::error::Error;
+
+const TAR_BLOCK: usize = 512;
+/// Larger long name or pax header entries are rejected rather than read into memory.
+const MAX_METADATA: u64 = 1024 * 1024;
+
+/// Decompresses `data` according to the extension of `name`, e.g. `data.tar.xz`.
+pub fn decompress(name: &str, data: &[u8]) -> error::Result<Vec<u8>> {
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("tar") => Ok(data.to_vec()),
+        Some("gz") => {
+            let mut tar = Vec::new();
+            GzDecoder::new(data).read_to_end(&mut tar)?;
+            Ok(tar)
+        }
+        Some("xz") => decompress_with("xz", data),
+        Some("zst") => decompress_with("zstd", data),
+        _ => Err(Error::InvalidPackage(format!("Unsupported compression of {}", name)))
+    }
+}
+
+/// Decompresses with the system tool for formats we have no decoder for. Every Linux distribution
+/// ships `xz`, and most `zstd`.
+fn decompress_with(tool: &str, data: &[u8]) -> error::Result<Vec<u8>> {
+    let mut child = Command::new(tool)
+        .args(["-d", "-c"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::UnpackToolMissing {
+            tool: tool.to_string(),
+            package: "the package".to_string(),
+            reason: e.to_string()
+        })?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = data.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        return Err(Error::InvalidPackage(format!("{} failed to unpack the package", tool)));
+    }
+    Ok(output.stdout)
+}
+
+/// Writes the regular files, directories and links of `tar` below `prefix` into `target`, keeping
+/// their permissions. Links that would lead out of `target` are left out. Returns the paths written,
+/// relative to `target`.
+pub fn extract_tar(mut tar: impl Read, prefix: &str, target: &Path) -> error::Result<Vec<PathBuf>> {
+    let prefix = Path::new(prefix.trim_start_matches("./"));
+    let mut extracted = Vec::new();
+    let mut long_name: Option<String> = None;
+    let mut long_link: Option<String> = None;
+    let mut header = [0; TAR_BLOCK];
+
+    while read_block(&mut tar, &mut header)? {
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        let size = octal(&header[124..136])?;
+        let mut data = (&mut tar).take(size);
+
+        let name = match long_name.take() {
+            Some(name) => name,
+            None => {
+                let name = field(&header[..100]);
+                let ustar_prefix = field(&header[345..500]);
+                if &header[257..262] == b"ustar" && !ustar_prefix.is_empty() {
+                    format!("{}/{}", ustar_prefix, name)
+                } else {
+                    name
+                }
+            }
+        };
+        let link = long_link.take().unwrap_or_else(|| field(&header[157..257]));
+
+        match header[156] {
+            // GNU long names and pax headers carry the paths of the entry that follows.
+            b'L' => long_name = Some(field(&read_metadata(&mut data, size)?)),
+            b'K' => long_link = Some(field(&read_metadata(&mut data, size)?)),
+            b'x' => {
+                let records = read_metadata(&mut data, size)?;
+                long_name = pax_record(&records, "path");
+                long_link = pax_record(&records, "linkpath");
+            }
+            kind @ (b'0' | b'\0' | b'1' | b'2' | b'5') => {
+                if let Some(relative) = relative_path(&name, prefix) {
+                    if extract_entry(kind, &header, &mut data, target, &relative, &link, prefix)? {
+                        extracted.push(relative);
+                    }
+                }
+            }
+            _ => {}
+        }
+        skip(&mut data)?;
+
+        // Entries are padded to whole blocks. The end of the archive may come without the padding.
+        let padding = size.next_multiple_of(TAR_BLOCK as u64) - size;
+        io::copy(&mut (&mut tar).take(padding), &mut io::sink())?;
+    }
+    Ok(extracted)
+}
+
+/// Writes the entry of `kind` at `relative` below `target`, returning whether it wrote anything
+/// other than a directory. Hard links must point at a file extracted from below `prefix` before.
+fn extract_entry(
+    kind: u8,
+    header: &[u8; TAR_BLOCK],
+    data: &mut io::Take<impl Read>,
+    target: &Path,
+    relative: &Path,
+    link: &str,
+    prefix: &Path
+) -> error::Result<bool> {
+    let path = target.join(relative);
+    if kind == b'5' {
+        fs::create_dir_all(&path)?;
+        return Ok(false);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    remove_existing(&path)?;
+    match kind {
+        b'1' => {
+            // Hard links name another entry of the archive rather than a path relative to the link.
+            let source = relative_path(link, prefix).map(|source| target.join(source));
+            let Some(source) = source.filter(|source| fs::symlink_metadata(source).is_ok_and(|metadata| metadata.is_file())) else {
+                log::info!("Skipping hard link {} to {}, which is not extracted", relative.display(), link);
+                return Ok(false);
+            };
+            fs::hard_link(source, &path)?;
+            Ok(true)
+        }
+        b'2' => {
+            if !link_stays_inside(target, relative, Path::new(link))? {
+                log::info!("Skipping symbolic link {} to {}, which leads out of the archive", relative.display(), link);
+                return Ok(false);
+            }
+            Ok(symlink(Path::new(link), &path)?)
+        }
+        _ => {
+            let size = data.limit();
+            if io::copy(data, &mut fs::File::create(&path)?)? != size {
+                return Err(truncated());
+            }
+            set_mode(&path, octal(&header[100..108])? as u32)?;
+            Ok(true)
+        }
+    }
+}
+
+/// Fills `block` with the next block of the archive. Returns `false` at the end of the archive.
+fn read_block(tar: &mut impl Read, block: &mut [u8; TAR_BLOCK]) -> error::Result<bool> {
+    let mut filled = 0;
+    while filled < TAR_BLOCK {
+        match tar.read(&mut block[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into())
+        }
+    }
+    match filled {
+        0 => Ok(false),
+        TAR_BLOCK => Ok(true),
+        _ => Err(truncated())
+    }
+}
+
+/// Reads what is left of an entry, checking it was all there.
+fn skip(data: &mut io::Take<impl Read>) -> error::Result<()> {
+    let left = data.limit();
+    if io::copy(data, &mut io::sink())? != left {
+        return Err(truncated());
+    }
+    Ok(())
+}
+
+/// The contents of a long name or pax header entry, which are small unless the archive is malicious.
+fn read_metadata(data: &mut io::Take<impl Read>, size: u64) -> error::Result<Vec<u8>> {
+    if size > MAX_METADATA {
+        return Err(Error::InvalidPackage(format!("Tar header entry of {} bytes", size)));
+    }
+    let mut metadata = Vec::new();
+    data.read_to_end(&mut metadata)?;
+    if metadata.len() as u64 != size {
+        return Err(truncated());
+    }
+    Ok(metadata)
+}
+
+fn truncated() -> Error {
+    Error::InvalidPackage("Truncated tar archive".to_string())
+}
+
+/// Whether the symbolic link at `relative` below `target` resolves to somewhere below `target`.
+/// The directories above the link are resolved as they are on disk, as earlier entries may have
+/// made them links themselves. `..` is only accepted at the start of `link`: after a name it
+/// may climb out of another link.
+fn link_stays_inside(target: &Path, relative: &Path, link: &Path) -> io::Result<bool> {
+    let root = fs::canonicalize(target)?;
+    let mut resolved = fs::canonicalize(target.join(relative).parent().unwrap_or(target))?;
+    let mut climbing = true;
+    for component in link.components() {
+        match component {
+            Component::ParentDir if climbing => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            Component::Normal(name) => {
+                climbing = false;
+                resolved.push(name);
+            }
+            _ => return Ok(false)
+        }
+    }
+    Ok(resolved.starts_with(&root))
+}
+
+/// Makes room for an entry, as tar does, so it replaces what an earlier entry put there instead
+/// of writing through it.
+fn remove_existing(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.is_dir() => fs::remove_file(path),
+        _ => Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn symlink(link: &Path, path: &Path) -> io::Result<bool> {
+    std::os::unix::fs::symlink(link, path)?;
+    Ok(true)
+}
+
+/// Creating symbolic links takes extra privileges on Windows, where runtimes come as zips anyway.
+#[cfg(not(unix))]
+fn symlink(link: &Path, path: &Path) -> io::Result<bool> {
+    log::info!("Skipping symbolic link {} to {}", path.display(), link.display());
+    Ok(false)
+}
+
+fn field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+fn octal(bytes: &[u8]) -> error::Result<u64> {
+    let value = field(bytes);
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(value, 8).map_err(|_| Error::InvalidPackage(format!("Invalid number {} in tar archive", value)))
+}
+
+/// The value of `key` in pax extended header `records`, e.g. `30 path=some/long/path/name\n`.
+fn pax_record(records: &[u8], key: &str) -> Option<String> {
+    String::from_utf8_lossy(records).lines()
+        .filter_map(|record| record.split_once(' ').and_then(|(_, record)| record.split_once('=')))
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value.to_string())
+}
+
+/// The path of `name` below `prefix`, if it is there and stays there.
+fn relative_path(name: &str, prefix: &Path) -> Option<PathBuf> {
+    let relative = Path::new(name.trim_start_matches("./")).strip_prefix(prefix).ok()?;
+    let normal = relative.components().all(|component| matches!(component, Component::Normal(_)));
+    (normal && relative.components().next().is_some()).then(|| relative.to_path_buf())
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o755))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Unpacks a downloaded archive into `target`. Zip files are left to the system `tar`, which reads
+/// them on Windows and macOS, where runtimes are published as zips.
+pub fn unpack(archive: &Path, target: &Path) -> error::Result<Vec<PathBuf>> {
+    let name = archive.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    if name.ends_with(".zip") {
+        let status = Command::new("tar")
+            .arg("-xf").arg(archive)
+            .arg("-C").arg(target)
+            .stdin(Stdio::null())
+            .status()
+            .map_err(|e| Error::UnpackToolMissing {
+                tool: "tar".to_string(),
+                package: name.clone(),
+                reason: e.to_string()
+            })?;
+        if !status.success() {
+            return Err(Error::InvalidPackage(format!("tar failed to unpack {}", name)));
+        }
+        return Ok(Vec::new());
+    }
+
+    // Runtimes are large, so gzipped and plain tarballs are extracted as they are read.
+    let name = name.strip_suffix(".tgz").map(|stem| format!("{}.tar.gz", stem)).unwrap_or(name);
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("gz") => extract_tar(GzDecoder::new(BufReader::new(fs::File::open(archive)?)), "", target),
+        Some("tar") => extract_tar(BufReader::new(fs::File::open(archive)?), "", target),
+        _ => extract_tar(decompress(&name, &fs::read(archive)?)?.as_slice(), "", target)
+    }
+}
+
+#[cfg(test)]
+mod tests;
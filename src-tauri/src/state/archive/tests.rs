@@ -0,0 +1,88 @@
+use crate::test_support::debian_package::{file, gzip, hardlink, symlink, tar};
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+#[test]
+fn long_names_are_followed() {
+    let long_path = format!("usr/share/games/runescape-launcher/{}/asset.bin", "nested".repeat(20));
+    let archive = tar(&[file(&long_path, b"asset", 0o644)]);
+    let target = TempDir::new("archive");
+
+    let extracted = extract_tar(archive.as_slice(), "usr/share/games/runescape-launcher", &target).unwrap();
+    assert_eq!(extracted, vec![PathBuf::from(format!("{}/asset.bin", "nested".repeat(20)))]);
+}
+
+#[test]
+fn entries_cannot_escape_the_target() {
+    let archive = tar(&[
+        file("./usr/share/games/runescape-launcher/../../../../etc/passwd", b"root", 0o644),
+        file("/usr/share/games/runescape-launcher/runescape", b"client", 0o755)
+    ]);
+    let target = TempDir::new("archive");
+
+    assert!(extract_tar(archive.as_slice(), "usr/share/games/runescape-launcher", &target).unwrap().is_empty());
+}
+
+#[test]
+fn tarballs_are_unpacked_whole() {
+    let dir = TempDir::new("archive");
+    let archive = dir.join("jre.tgz");
+    fs::write(&archive, gzip(&tar(&[file("jdk-17/bin/java", b"#!/bin/sh\n", 0o755)]))).unwrap();
+    let target = dir.join("out");
+
+    assert_eq!(unpack(&archive, &target).unwrap(), vec![PathBuf::from("jdk-17/bin/java")]);
+    assert!(target.join("jdk-17/bin/java").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn links_are_recreated_unless_they_lead_out() {
+    let dir = TempDir::new("archive");
+    let archive = dir.join("jre.tar.gz");
+    fs::write(&archive, gzip(&tar(&[
+        file("jdk-17/lib/server/libjvm.so", b"jvm", 0o755),
+        symlink("jdk-17/lib/libjvm.so", "server/libjvm.so"),
+        hardlink("jdk-17/bin/java", "jdk-17/lib/server/libjvm.so"),
+        symlink("jdk-17/legal", "../../outside"),
+        symlink("jdk-17/etc", "/etc"),
+        hardlink("jdk-17/passwd", "../etc/passwd")
+    ]))).unwrap();
+    let target = dir.join("out");
+    fs::create_dir(&target).unwrap();
+
+    let extracted = unpack(&archive, &target).unwrap();
+    assert_eq!(extracted, vec![
+        PathBuf::from("jdk-17/lib/server/libjvm.so"),
+        PathBuf::from("jdk-17/lib/libjvm.so"),
+        PathBuf::from("jdk-17/bin/java")
+    ]);
+    assert_eq!(fs::read_link(target.join("jdk-17/lib/libjvm.so")).unwrap(), PathBuf::from("server/libjvm.so"));
+    assert_eq!(fs::read(target.join("jdk-17/bin/java")).unwrap(), b"jvm");
+    assert!(fs::symlink_metadata(target.join("jdk-17/legal")).is_err());
+    assert!(fs::symlink_metadata(target.join("jdk-17/etc")).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn links_cannot_climb_out_through_other_links() {
+    let archive = tar(&[
+        symlink("here", "."),
+        symlink("here/escape", ".."),
+        file("here/escape/file", b"outside", 0o644)
+    ]);
+    let dir = TempDir::new("archive");
+    let target = dir.join("out");
+    fs::create_dir(&target).unwrap();
+
+    let extracted = extract_tar(archive.as_slice(), "", &target).unwrap();
+    assert!(!extracted.contains(&PathBuf::from("here/escape")));
+    assert!(!dir.join("file").exists());
+}
+
+#[test]
+fn truncated_archives_are_rejected() {
+    let archive = tar(&[file("jdk-17/bin/java", &[0; 4096], 0o755)]);
+    let target = TempDir::new("archive");
+
+    assert!(matches!(extract_tar(&archive[..2048], "", &target), Err(Error::InvalidPackage(_))));
+}
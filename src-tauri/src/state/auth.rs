@@ -1,26 +1,32 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use base64::Engine;
-use openidconnect::core::{CoreAuthPrompt, CoreAuthenticationFlow, CoreClient, CoreErrorResponseType, CoreGenderClaim, CoreIdToken, CoreIdTokenClaims, CoreIdTokenVerifier, CoreProviderMetadata, CoreResponseType};
-use openidconnect::{ClientId, CsrfToken, EmptyAdditionalClaims, EndpointMaybeSet, EndpointNotSet, EndpointSet, IdToken, Nonce, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, Scope, StandardErrorResponse};
+use once_cell::sync::Lazy;
+use openidconnect::core::{CoreAuthPrompt, CoreAuthenticationFlow, CoreClient, CoreErrorResponseType, CoreGenderClaim, CoreIdToken, CoreIdTokenClaims, CoreIdTokenVerifier, CoreJsonWebKeySet, CoreProviderMetadata, CoreResponseType};
+use openidconnect::{ClaimsVerificationError, ClientId, CsrfToken, EmptyAdditionalClaims, EndpointMaybeSet, EndpointNotSet, EndpointSet, IdToken, Nonce, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, Scope, SignatureVerificationError, StandardErrorResponse};
 use reqwest::redirect::Policy;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use serde_json::map::Values;
-use tauri::{AppHandle, Manager, Runtime, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tauri::{AppHandle, Manager, Runtime, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
 use tokio::io::AsyncBufReadExt;
 use url::Url;
 use uuid::Uuid;
 use crate::error;
-use crate::error::Error;
+use crate::error::{Error, JagexApi};
+use crate::state::endpoints::AuthEndpoints;
+use crate::state::hiscores::CharacterLevels;
+use crate::state::login::{AuthWindowGuard, ConsumedSessionStates, REQUEST_TIMEOUT};
+use crate::state::webview_profiles::{WebviewProfile, WebviewProfileExt};
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 
-const OIDC_DISCOVERY_URL: &str = "https://account.jagex.com/.well-known/openid-configuration";
-const AUTH_CODE_CLIENT_ID: &str = "com_jagex_auth_desktop_launcher";
-const AUTH_CODE_REDIRECT_URI: &str = "https://secure.runescape.com/m=weblogin/launcher-redirect";
 const AUTH_CODE_SCOPE: &str = "openid offline gamesso.token.create user.profile.read user.entitlement.read user.game.read user.sku.read user.voucher.redeem";
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Provider signing keys keyed by JWKS url, so that every login does not refetch them.
+static JWKS_CACHE: Lazy<Mutex<HashMap<String, (Instant, CoreJsonWebKeySet)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, Clone)]
 pub struct AuthFlow {
@@ -39,9 +45,18 @@ pub struct AuthFlow {
     pub nonce: Nonce,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// The game-session authorize request, kept so its redirect can be checked against it.
+#[derive(Debug, Clone)]
+pub struct SessionRequest {
+    pub url: Url,
+    pub csrf_token: CsrfToken,
+    pub nonce: Nonce
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthToken {
     pub access_token: String,
+    #[serde(default)]
     pub refresh_token: String,
     pub expires_in: u64,
     pub id_token: String,
@@ -49,34 +64,62 @@ pub struct OAuthToken {
     pub token_type: String
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameSession {
     pub code: String,
     pub id_token: String,
     pub state: String,
+    #[serde(deserialize_with = "unquoted")]
     pub session_id: String
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+/// Session ids stored by earlier versions kept the quotes of the JSON string they came from.
+fn unquoted<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<String, D::Error> {
+    Ok(String::deserialize(deserializer)?.trim_matches('"').to_string())
+}
+
+/// The text of a JSON string, or of any other scalar, and nothing for `null`.
+fn json_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameCharacter {
     pub account_id: String,
     pub display_name: String,
     pub user_hash: String,
-    pub is_members: bool
+    pub is_members: bool,
+    /// Filled in by `get_character_levels`. Unset for unranked or unnamed characters.
+    #[serde(default)]
+    pub levels: Option<CharacterLevels>
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
+    pub id: String,
     pub email: String,
     pub account_name: String,
     pub characters: Vec<GameCharacter>
 }
 
-pub async fn begin_login() -> error::Result<AuthFlow> {
-    let provider_metadata = provider_metadata().await?;
+pub async fn begin_login(endpoints: &AuthEndpoints) -> error::Result<AuthFlow> {
+    begin_authorization(endpoints, None).await
+}
+
+/// Like `begin_login`, but asks the provider to answer without showing any UI (`prompt=none`).
+pub async fn begin_silent_login(endpoints: &AuthEndpoints) -> error::Result<AuthFlow> {
+    begin_authorization(endpoints, Some(CoreAuthPrompt::None)).await
+}
+
+async fn begin_authorization(endpoints: &AuthEndpoints, prompt: Option<CoreAuthPrompt>) -> error::Result<AuthFlow> {
+    let provider_metadata = provider_metadata(endpoints).await?;
 
-    let client_id = ClientId::new(AUTH_CODE_CLIENT_ID.to_string());
-    let redirect_url = RedirectUrl::new(AUTH_CODE_REDIRECT_URI.to_string())?;
+    let client_id = ClientId::new(endpoints.launcher_client_id.clone());
+    let redirect_url = RedirectUrl::new(endpoints.launcher_redirect_uri.clone())?;
     let client = CoreClient::from_provider_metadata(provider_metadata, client_id, None)
         .set_redirect_uri(redirect_url);
 
@@ -86,7 +129,7 @@ pub async fn begin_login() -> error::Result<AuthFlow> {
         .map(|v| Scope::new(v.to_string()))
         .collect::<Vec<Scope>>();
 
-    let (auth_url, csrf_token, nonce) = client
+    let mut request = client
         .authorize_url(
             CoreAuthenticationFlow::Hybrid(vec![
                 CoreResponseType::Code
@@ -95,8 +138,11 @@ pub async fn begin_login() -> error::Result<AuthFlow> {
             Nonce::new_random
         )
         .set_pkce_challenge(pkce_challenge.clone())
-        .add_scopes(scopes)
-        .url();
+        .add_scopes(scopes);
+    if let Some(prompt) = prompt {
+        request = request.add_prompt(prompt);
+    }
+    let (auth_url, csrf_token, nonce) = request.url();
 
     let flow = AuthFlow {
         client,
@@ -117,7 +163,7 @@ pub async fn oauth_token(
     let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().redirect(Policy::none()).build()?;
 
     if flow.csrf_token.into_secret() != state {
-        return Err(Error::Reason("Provided state from OAuth flow does not match the CSRF token.".to_string()));
+        return Err(Error::LoginStateMismatch);
     }
 
     let params = &[
@@ -135,27 +181,162 @@ pub async fn oauth_token(
         .await?;
 
     if !response.status().is_success() {
-        let error_data = response.json::<StandardErrorResponse<CoreErrorResponseType>>().await?;
-        log::error!("Failed to get oauth token response from OAuth. Error: {}", error_data);
-        return Err(Error::Reason("Failed to get oauth token response from OAuth.".to_string()));
+        return Err(api_error(JagexApi::Token, response).await);
     }
 
     let auth_token = response.json::<OAuthToken>().await?;
     Ok(auth_token)
 }
 
-async fn provider_metadata() -> error::Result<CoreProviderMetadata> {
+/// Exchanges a stored refresh token for a new token set using the `refresh_token` grant.
+pub async fn refresh_oauth_token(endpoints: &AuthEndpoints, refresh_token: &str) -> error::Result<OAuthToken> {
+    let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().redirect(Policy::none()).build()?;
+
+    let provider_metadata = provider_metadata(endpoints).await?;
+    let token_endpoint = provider_metadata.token_endpoint()
+        .ok_or_else(|| Error::UnexpectedResponse {
+            api: JagexApi::Token,
+            reason: "Provider metadata does not contain a token endpoint.".to_string()
+        })?;
+
+    let params = &[
+        ("client_id", endpoints.launcher_client_id.clone()),
+        ("grant_type", "refresh_token".to_string()),
+        ("refresh_token", refresh_token.to_string())
+    ];
+
+    let response = http_client
+        .post(token_endpoint.as_str())
+        .form(&params)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        // Only an OAuth error means the refresh token itself was rejected. Gateway pages and the
+        // like are reported as a failing token endpoint.
+        if let Ok(error_data) = serde_json::from_str::<StandardErrorResponse<CoreErrorResponseType>>(&body) {
+            log::error!("Failed to refresh oauth token. Error: {}", error_data);
+            return Err(Error::Auth(error_data));
+        }
+        return Err(jagex_api_error(JagexApi::Token, status, &body));
+    }
+
+    let auth_token = response.json::<OAuthToken>().await?;
+    Ok(auth_token)
+}
+
+/// Turns an unsuccessful Jagex response into an error, keeping the OAuth error body when there is one.
+async fn api_error(api: JagexApi, response: tauri_plugin_http::reqwest::Response) -> Error {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    jagex_api_error(api, status, &body)
+}
+
+fn jagex_api_error(api: JagexApi, status: u16, body: &str) -> Error {
+    let error = match serde_json::from_str::<StandardErrorResponse<CoreErrorResponseType>>(body) {
+        Ok(error_data) => Some(error_data.to_string()),
+        Err(_) => Some(body.trim().to_string()).filter(|body| !body.is_empty())
+    };
+
+    let error = Error::JagexApi { api, status, error };
+    log::error!("{}", error);
+    error
+}
+
+async fn provider_metadata(endpoints: &AuthEndpoints) -> error::Result<CoreProviderMetadata> {
     let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().build()?;
-    let metadata: CoreProviderMetadata = http_client.get(OIDC_DISCOVERY_URL).send().await?.json().await?;
+    let metadata: CoreProviderMetadata = http_client.get(&endpoints.oidc_discovery_url).send().await?.json().await?;
 
     Ok(metadata)
 }
 
-pub async fn authorize<R: Runtime>(app_handle: AppHandle<R>, flow: AuthFlow) -> error::Result<(String, String)> {
+async fn signing_keys(metadata: &CoreProviderMetadata, force_refresh: bool) -> error::Result<CoreJsonWebKeySet> {
+    let jwks_uri = metadata.jwks_uri().url().to_string();
+
+    if !force_refresh {
+        if let Ok(cache) = JWKS_CACHE.lock() {
+            if let Some((fetched_at, jwks)) = cache.get(&jwks_uri) {
+                if fetched_at.elapsed() < JWKS_CACHE_TTL {
+                    return Ok(jwks.clone());
+                }
+            }
+        }
+    }
+
+    log::debug!("Fetching provider signing keys from {}", jwks_uri);
+    let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().build()?;
+    let jwks: CoreJsonWebKeySet = http_client.get(&jwks_uri).send().await?.json().await?;
+
+    if let Ok(mut cache) = JWKS_CACHE.lock() {
+        cache.insert(jwks_uri, (Instant::now(), jwks.clone()));
+    }
+    Ok(jwks)
+}
+
+/// Verifies the RS256 signature, issuer, audience, expiry and nonce of an ID token issued to `client_id`.
+///
+/// An unknown signing key triggers a single JWKS refetch in case the provider rotated its keys.
+async fn verify_id_token(
+    endpoints: &AuthEndpoints,
+    client_id: &ClientId,
+    id_token: &str,
+    nonce: &Nonce
+) -> error::Result<CoreIdTokenClaims> {
+    let metadata = provider_metadata(endpoints).await?;
+    let id_token = CoreIdToken::from_str(id_token)?;
+
+    let verify = |jwks: CoreJsonWebKeySet| {
+        let verifier = CoreIdTokenVerifier::new_public_client(client_id.clone(), metadata.issuer().clone(), jwks);
+        id_token.claims(&verifier, nonce).cloned()
+    };
+
+    let result = match verify(signing_keys(&metadata, false).await?) {
+        Err(ClaimsVerificationError::SignatureVerification(SignatureVerificationError::NoMatchingKey)) => {
+            log::info!("ID token is signed with an unknown key. Refreshing provider signing keys.");
+            verify(signing_keys(&metadata, true).await?)
+        }
+        result => result
+    };
+
+    result.map_err(|e| {
+        log::error!("Failed to verify id token for client {}: {:?}", client_id.as_str(), e);
+        match e {
+            ClaimsVerificationError::InvalidNonce(_) => Error::NonceMismatch,
+            e => Error::IdTokenVerification(e.to_string())
+        }
+    })
+}
+
+/// Sends `Cancelled` through `tx` once the user closes an auth window before it redirected.
+fn cancel_on_close<R: Runtime, T: Send + 'static>(
+    window: &WebviewWindow<R>,
+    tx: Arc<Mutex<Option<tokio::sync::oneshot::Sender<error::Result<T>>>>>,
+    reason: &'static str
+) {
+    window.on_window_event(move |event| {
+        if let WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed = event {
+            if let Ok(mut tx_guard) = tx.lock() {
+                if let Some(sender) = tx_guard.take() {
+                    log::warn!("{}", reason);
+                    let _ = sender.send(Err(Error::Cancelled(reason.to_string())));
+                }
+            }
+        }
+    });
+}
+
+pub async fn authorize<R: Runtime>(
+    app_handle: AppHandle<R>,
+    flow: AuthFlow,
+    profile: &WebviewProfile
+) -> error::Result<(String, String)> {
     log::info!("Starting OAuth authorization. Opening authorization window popup.");
 
-    let (tx, mut rx) = tokio::sync::oneshot::channel::<error::Result<(String, String)>>();
+    let (tx, rx) = tokio::sync::oneshot::channel::<error::Result<(String, String)>>();
     let tx = Arc::new(Mutex::new(Some(tx)));
+    let navigation_tx = tx.clone();
     
     if let Some(auth_window) = &app_handle.get_webview_window("auth") {
         let _ = auth_window.close()?;
@@ -173,6 +354,7 @@ pub async fn authorize<R: Runtime>(app_handle: AppHandle<R>, flow: AuthFlow) ->
         WebviewUrl::External(flow.authorization_request_url.parse()?)
     )
         .title("Login with Jagex Account")
+        .webview_profile(profile, &app_handle)?
         .inner_size(480., 700.)
         .position((main_window_pos.0 as f64 + main_window_size.0 as f64)  + 32f64, main_window_pos.1 as f64)
         .center()
@@ -180,27 +362,10 @@ pub async fn authorize<R: Runtime>(app_handle: AppHandle<R>, flow: AuthFlow) ->
         .on_navigation(move |url| {
             let url_str = url.as_str().to_string();
             if url_str.to_lowercase().starts_with(&flow.client.redirect_uri().unwrap().as_str().to_lowercase()) {
-                let code = url.query_pairs()
-                    .find(|(k, _)| k == "code")
-                    .map(|(_, v)| v.to_string());
-
-                let state = url.query_pairs()
-                    .find(|(k, _)| k == "state")
-                    .map(|(_, v)| v.to_string());
-
-                if let Ok(mut tx_guard) = tx.lock() {
+                if let Ok(mut tx_guard) = navigation_tx.lock() {
                     if let Some(sender) = tx_guard.take() {
-                        return match (code, state) {
-                            (Some(code), Some(state)) => {
-                                let _ = sender.send(Ok((code, state)));
-                                false
-                            }
-                            _ => {
-                                log::error!("Invalid redirect URL: {}", url_str);
-                                let _ = sender.send(Err(Error::InvalidRedirectUrl(url_str)));
-                                false
-                            }
-                        }
+                        let _ = sender.send(authorization_response(url));
+                        return false;
                     }
                 }
             }
@@ -208,47 +373,146 @@ pub async fn authorize<R: Runtime>(app_handle: AppHandle<R>, flow: AuthFlow) ->
             true
         })
         .build()?;
+    let window = AuthWindowGuard(window);
+    cancel_on_close(&window.0, tx, "The login window was closed before the login finished.");
 
     let result = rx.await
-        .map_err(|_| Error::Reason("OAuth flow was cancelled!".to_string()))?;
+        .map_err(|_| Error::Cancelled("OAuth flow was cancelled.".to_string()))?;
     if result.is_ok() {
-        // Close the window.
-        {
-            window.close()?;
-        }
         log::info!("Finished OAuth authorization. Closing authorization window popup.");
-
-        Ok(result?)
     } else {
-        log::warn!("OAuth authorization cancelled.");
-        Err(result.unwrap_err())
+        log::warn!("OAuth authorization failed or was cancelled.");
     }
+    result
 }
 
-pub async fn game_session<R: Runtime>(
+/// Reads the code and state from the launcher redirect, or the error the provider answered with.
+fn authorization_response(url: &Url) -> error::Result<(String, String)> {
+    let param = |key: &str| url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.to_string());
+
+    if let Some(error) = param("error") {
+        log::warn!("Authorization redirect returned error {}: {}", error, param("error_description").unwrap_or_default());
+        return match error.as_str() {
+            "login_required" | "interaction_required" | "consent_required" | "account_selection_required" =>
+                Err(Error::InteractionRequired),
+            _ => Err(Error::Auth(serde_json::from_value(json!({
+                "error": error,
+                "error_description": param("error_description")
+            }))?))
+        };
+    }
+
+    match (param("code"), param("state")) {
+        (Some(code), Some(state)) => Ok((code, state)),
+        _ => {
+            log::error!("Invalid redirect URL: {}", url);
+            Err(Error::InvalidRedirectUrl(url.to_string()))
+        }
+    }
+}
+
+/// Runs `flow` in a hidden window, for flows started with `begin_silent_login`.
+///
+/// Fails with `InteractionRequired` when the provider has no usable session and would need to show a login page.
+pub async fn authorize_silently<R: Runtime>(
     app_handle: AppHandle<R>,
     flow: AuthFlow,
-    oauth_token: OAuthToken
+    profile: &WebviewProfile
+) -> error::Result<(String, String)> {
+    log::info!("Attempting silent OAuth authorization.");
+
+    let (tx, rx) = tokio::sync::oneshot::channel::<error::Result<(String, String)>>();
+    let tx = Arc::new(Mutex::new(Some(tx)));
+    let navigation_tx = tx.clone();
+    let redirect_uri = flow.client.redirect_uri().unwrap().as_str().to_lowercase();
+
+    let window = WebviewWindowBuilder::new(
+        &app_handle,
+        format!("auth_silent_{}", Uuid::new_v4().simple()),
+        WebviewUrl::External(flow.authorization_request_url.parse()?)
+    )
+        .title("Renewing Jagex Login")
+        .webview_profile(profile, &app_handle)?
+        .skip_taskbar(true)
+        .inner_size(480., 700.)
+        .visible(false)
+        .on_navigation(move |url| {
+            if url.as_str().to_lowercase().starts_with(&redirect_uri) {
+                if let Ok(mut tx_guard) = navigation_tx.lock() {
+                    if let Some(sender) = tx_guard.take() {
+                        let _ = sender.send(authorization_response(url));
+                        return false;
+                    }
+                }
+            }
+
+            true
+        })
+        .build()?;
+    let window = AuthWindowGuard(window);
+    cancel_on_close(&window.0, tx, "The silent login window was closed before it redirected.");
+
+    // Without a provider session the hidden page may just sit on a login form instead of redirecting.
+    let result = tokio::time::timeout(REQUEST_TIMEOUT, rx).await
+        .map_err(|_| Error::InteractionRequired)?
+        .map_err(|_| Error::Cancelled("Silent login was cancelled.".to_string()))?;
+    drop(window);
+    result
+}
+
+/// Logs `account_id` in again without any UI by reusing the provider's session cookie.
+///
+/// A provider session belonging to a different Jagex account also counts as needing interaction.
+pub async fn silent_login<R: Runtime>(
+    app_handle: AppHandle<R>,
+    endpoints: &AuthEndpoints,
+    account_id: &str
+) -> error::Result<(AuthFlow, OAuthToken)> {
+    let flow = begin_silent_login(endpoints).await?;
+    let (code, state) = authorize_silently(app_handle, flow.clone(), &WebviewProfile::account(account_id)).await?;
+    let oauth_token = oauth_token(flow.clone(), code, state).await?;
+
+    let claims = verify_id_token(endpoints, flow.client.client_id(), &oauth_token.id_token, &flow.nonce).await?;
+    if claims.subject().as_str() != account_id {
+        log::warn!("Silent login returned account {} instead of {}.", claims.subject().as_str(), account_id);
+        return Err(Error::InteractionRequired);
+    }
+
+    log::info!("Silently re-authenticated account {}.", account_id);
+    Ok((flow, oauth_token))
+}
+
+pub async fn game_session<R: Runtime>(
+    app_handle: AppHandle<R>,
+    endpoints: &AuthEndpoints,
+    oauth_token: OAuthToken,
+    profile: &WebviewProfile
 ) -> error::Result<GameSession> {
-    let session_id_url = get_session_id_request_url(flow, oauth_token.clone()).await?;
+    let request = get_session_id_request_url(endpoints, oauth_token.clone()).await?;
+    let redirect_uri = endpoints.game_session_redirect_uri.clone();
 
-    let (tx, mut rx) = tokio::sync::oneshot::channel::<error::Result<String>>();
+    let (tx, rx) = tokio::sync::oneshot::channel::<error::Result<String>>();
     let tx = Arc::new(Mutex::new(Some(tx)));
+    let navigation_tx = tx.clone();
 
+    // Renewals can run while a login is in progress, so every session window gets its own label.
     let window = WebviewWindowBuilder::new(
         &app_handle,
-        "auth_session_id",
-        WebviewUrl::External(session_id_url)
+        format!("auth_session_id_{}", Uuid::new_v4().simple()),
+        WebviewUrl::External(request.url.clone())
     )
         .title("Fetching Session Id")
+        .webview_profile(profile, &app_handle)?
         .skip_taskbar(true)
         .center()
         .inner_size(500., 500.)
         .visible(false)
         .on_navigation(move |url| {
             let url_str = url.as_str().to_string();
-            if url_str.starts_with("http://localhost") {
-                if let Ok(mut tx_guard) = tx.lock() {
+            if url_str.starts_with(&redirect_uri) {
+                if let Ok(mut tx_guard) = navigation_tx.lock() {
                     if let Some(sender) = tx_guard.take() {
                         let _ = sender.send(Ok(url_str));
                         return false;
@@ -259,11 +523,46 @@ pub async fn game_session<R: Runtime>(
             true
         })
         .build()?;
+    let window = AuthWindowGuard(window);
+    cancel_on_close(&window.0, tx, "The game session window was closed before it redirected.");
 
-    let result_url = rx.await
-        .map_err(|_| Error::Reason("Failed to get session id.".to_string()))??;
+    // The window is hidden, so a provider page that never redirects would otherwise hang forever.
+    let result_url = tokio::time::timeout(REQUEST_TIMEOUT, rx).await
+        .map_err(|_| Error::Timeout("Timed out waiting for the game session redirect.".to_string()))?
+        .map_err(|_| Error::Cancelled("Fetching the game session was cancelled.".to_string()))??;
+    drop(window);
 
-    let mut data = parse_query_params(result_url.as_str());
+    session_from_redirect(endpoints, result_url.as_str(), &request, &app_handle.state::<ConsumedSessionStates>()).await
+}
+
+/// Exchanges the id token from the game-session redirect for a Jagex game session id.
+///
+/// The redirect must echo the `state` of `request` and carry an ID token bound to its nonce,
+/// and each request can only be redeemed once.
+async fn session_from_redirect(
+    endpoints: &AuthEndpoints,
+    redirect_url: &str,
+    request: &SessionRequest,
+    consumed: &ConsumedSessionStates
+) -> error::Result<GameSession> {
+    let mut data = parse_query_params(redirect_url, &endpoints.game_session_redirect_uri);
+    if ["code", "state", "id_token"].iter().any(|key| !data.contains_key(*key)) {
+        log::error!("Invalid game session redirect URL: {}", redirect_url);
+        return Err(Error::InvalidRedirectUrl(redirect_url.to_string()));
+    }
+
+    if data["state"] != *request.csrf_token.secret() {
+        log::error!("Game session redirect state does not match the request.");
+        return Err(Error::SessionStateMismatch);
+    }
+
+    let client_id = ClientId::new(endpoints.game_session_client_id.clone());
+    verify_id_token(endpoints, &client_id, &data["id_token"], &request.nonce).await?;
+
+    if !consumed.consume(&data["state"]) {
+        log::error!("Game session redirect was replayed.");
+        return Err(Error::SessionReplayed);
+    }
 
     // Build the session id request
 
@@ -274,21 +573,25 @@ pub async fn game_session<R: Runtime>(
     });
 
     let response = http_client
-        .post("https://auth.jagex.com/game-session/v1/sessions")
+        .post(endpoints.game_sessions_url())
         .header("content-type", "application/json")
         .json(&params)
         .send().await?;
 
     if !response.status().is_success() {
-        let error_data = response.json::<StandardErrorResponse<CoreErrorResponseType>>().await?;
-        log::error!("Failed to get session id response from Jagex. Error: {}", error_data);
-        return Err(Error::Reason("Failed to get session id response from Jagex.".to_string()));
+        return Err(api_error(JagexApi::GameSession, response).await);
     }
 
     let response_json = response.text().await?.to_string();
     let json = serde_json::from_str::<Value>(response_json.as_str().trim())?;
 
-    data.insert("session_id".to_string(), json.get("sessionId".to_string()).unwrap().to_string());
+    let session_id = json.get("sessionId")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::UnexpectedResponse {
+            api: JagexApi::GameSession,
+            reason: "Response does not contain a sessionId.".to_string()
+        })?;
+    data.insert("session_id".to_string(), session_id.to_string());
 
     let game_session = GameSession {
         code: data["code"].to_string(),
@@ -297,16 +600,12 @@ pub async fn game_session<R: Runtime>(
         session_id: data["session_id"].to_string()
     };
 
-    {
-        let _ = window.close()?;
-    }
-
     Ok(game_session)
 }
 
-fn parse_query_params(query: &str) -> HashMap<String, String> {
+fn parse_query_params(query: &str, redirect_uri: &str) -> HashMap<String, String> {
     query
-        .replace("http://localhost/#", "")
+        .replace(&format!("{}/#", redirect_uri.trim_end_matches('/')), "")
         .split("&")
         .filter_map(|pair| {
             let mut parts = pair.splitn(2, "=");
@@ -321,11 +620,11 @@ fn parse_query_params(query: &str) -> HashMap<String, String> {
         }).collect()
 }
 
-async fn get_session_id_request_url(flow: AuthFlow, oauth_token: OAuthToken) -> error::Result<Url> {
-    let client_id = ClientId::new("1fddee4e-b100-4f4e-b2b0-097f9088f9d2".to_string());
-    let provider_metadata = provider_metadata().await?;
+async fn get_session_id_request_url(endpoints: &AuthEndpoints, oauth_token: OAuthToken) -> error::Result<SessionRequest> {
+    let client_id = ClientId::new(endpoints.game_session_client_id.clone());
+    let provider_metadata = provider_metadata(endpoints).await?;
     let client = CoreClient::from_provider_metadata(provider_metadata, client_id, None)
-        .set_redirect_uri(RedirectUrl::new("http://localhost".to_string())?);
+        .set_redirect_uri(RedirectUrl::new(endpoints.game_session_redirect_uri.clone())?);
 
     let scopes = &[
         "openid",
@@ -333,7 +632,7 @@ async fn get_session_id_request_url(flow: AuthFlow, oauth_token: OAuthToken) ->
     ];
     let scopes = scopes.iter().map(|s| Scope::new(s.to_string())).collect::<Vec<Scope>>();
 
-    let (result_url, csrf_token, nonce) = client.authorize_url(
+    let (url, csrf_token, nonce) = client.authorize_url(
         CoreAuthenticationFlow::Hybrid(vec![
             CoreResponseType::Code,
             CoreResponseType::IdToken
@@ -345,7 +644,7 @@ async fn get_session_id_request_url(flow: AuthFlow, oauth_token: OAuthToken) ->
         .add_extra_param("id_token_hint", oauth_token.id_token.to_string())
         .url();
 
-    Ok(result_url)
+    Ok(SessionRequest { url, csrf_token, nonce })
 }
 
 fn decode_jwt_claims_unverified(token: &str) -> error::Result<serde_json::Value> {
@@ -376,43 +675,49 @@ fn decode_jwt_claims_unverified(token: &str) -> error::Result<serde_json::Value>
 
 
 pub async fn characters(
+    endpoints: &AuthEndpoints,
     session: GameSession
 ) -> error::Result<Vec<GameCharacter>> {
     let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().redirect(Policy::none()).build()?;
 
     let response = http_client
-        .get("https://auth.jagex.com/game-session/v1/accounts")
-        .bearer_auth(session.session_id.replace("\"", ""))
+        .get(endpoints.game_accounts_url())
+        .bearer_auth(&session.session_id)
         .send()
         .await?;
 
     if !response.status().is_success() {
-        let error_data = response.json::<StandardErrorResponse<CoreErrorResponseType>>().await?;
-        log::error!("Failed to get characters response from Jagex. Error: {}", error_data);
-        return Err(Error::Reason("Failed to get characters response from Jagex.".to_string()));
+        return Err(api_error(JagexApi::Characters, response).await);
     }
 
     let json = response.text().await?.to_string();
     let json = serde_json::from_str::<Value>(json.as_str().trim())?;
 
+    let entries = json.as_array()
+        .ok_or_else(|| Error::UnexpectedResponse {
+            api: JagexApi::Characters,
+            reason: "Response is not a list.".to_string()
+        })?;
+
     let mut results = Vec::<GameCharacter>::new();
-    for entry in json.as_array().unwrap().iter() {
-        let account_id = entry["accountId"].to_string().replace("\"", "");
-        let display_name = entry["displayName"].to_string().replace("\"", "");
-        let user_hash = entry["userHash"].to_string().replace("\"", "");
+    for entry in entries.iter() {
+        let account_id = json_string(&entry["accountId"]);
+        let display_name = json_string(&entry["displayName"]);
+        let user_hash = json_string(&entry["userHash"]);
         let is_members = false;
         results.push(GameCharacter {
             account_id,
             display_name,
             user_hash,
-            is_members
+            is_members,
+            levels: None
         });
     }
 
     Ok(results)
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     pub nickname: String,
     pub display_name: String,
@@ -422,43 +727,33 @@ pub struct AccountInfo {
 }
 
 pub async fn account_info(
+    endpoints: &AuthEndpoints,
     flow: AuthFlow,
     oauth_token: OAuthToken
 ) -> error::Result<AccountInfo> {
-    let client = flow.client;
-    let id_token = CoreIdToken::from_str(&oauth_token.id_token.as_str())?;
-    let id_token_verifier = client.id_token_verifier().insecure_disable_signature_check();
-    let claims: &CoreIdTokenClaims = match id_token.claims(&id_token_verifier, &flow.nonce) {
-        Ok(claims) => claims,
-        Err(e) => {
-            log::error!("Failed to get claims from id token: {:?}", e);
-            return Err(Error::Reason("Failed to get claims from id token.".to_string()));
-        }
-    };
+    let claims = verify_id_token(endpoints, flow.client.client_id(), &oauth_token.id_token, &flow.nonce).await?;
     let claims_json = serde_json::to_value(&claims)?;
 
-    let sub = claims_json["sub"].to_string().replace("\"", "");
-    let nickname = claims_json["nickname"].to_string().replace("\"", "");
+    let sub = json_string(&claims_json["sub"]);
+    let nickname = json_string(&claims_json["nickname"]);
 
     log::debug!("AccountID: {} - Sub: {}", nickname, sub);
 
     let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().redirect(Policy::none()).build()?;
-    let response = http_client.get(format!("https://api.jagex.com/v1/users/{}/displayName", sub))
+    let response = http_client.get(endpoints.display_name_url(&sub))
         .bearer_auth(&oauth_token.access_token)
         .send().await?;
 
     if !response.status().is_success() {
-        let error_data = response.json::<StandardErrorResponse<CoreErrorResponseType>>().await?;
-        log::error!("Failed to get account info response from Jagex. Error: {}", error_data);
-        return Err(Error::Reason("Failed to get account info response from Jagex.".to_string()));
+        return Err(api_error(JagexApi::DisplayName, response).await);
     }
 
     let response_json = response.text().await?.to_string();
     let response_json = serde_json::from_str::<Value>(response_json.as_str().trim())?;
 
-    let display_name = response_json["displayName"].to_string().replace("\"", "");
-    let id = response_json["id"].to_string().replace("\"", "");
-    let user_id = response_json["userId"].to_string().replace("\"", "");
+    let display_name = json_string(&response_json["displayName"]);
+    let id = json_string(&response_json["id"]);
+    let user_id = json_string(&response_json["userId"]);
 
     Ok(AccountInfo {
         nickname,
@@ -467,4 +762,7 @@ pub async fn account_info(
         user_id,
         email: None
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
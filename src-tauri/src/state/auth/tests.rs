@@ -0,0 +1,357 @@
+use super::*;
+use serde_json::json;
+use crate::test_support::mock_identity::{MockIdentity, MockRoute, MOCK_DISPLAY_NAME, MOCK_NICKNAME, MOCK_SESSION_ID, MOCK_SUB};
+
+/// Plays the part of the login webview: requests `url` and returns where the provider redirects to.
+async fn follow_authorize(url: &str) -> Url {
+    let http_client = reqwest::ClientBuilder::new().redirect(Policy::none()).build().unwrap();
+    let response = http_client.get(url).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+
+    let location = response.headers()[reqwest::header::LOCATION].to_str().unwrap();
+    Url::parse(location).unwrap()
+}
+
+fn query_param(url: &Url, key: &str) -> String {
+    url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.to_string()).unwrap()
+}
+
+async fn login_token(mock: &MockIdentity) -> (AuthFlow, OAuthToken) {
+    let endpoints = mock.endpoints();
+    let flow = begin_login(&endpoints).await.unwrap();
+
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+    assert!(redirect.as_str().starts_with(&endpoints.launcher_redirect_uri));
+
+    let token = oauth_token(flow.clone(), query_param(&redirect, "code"), query_param(&redirect, "state")).await.unwrap();
+    (flow, token)
+}
+
+async fn session_redirect(mock: &MockIdentity, token: &OAuthToken) -> (String, SessionRequest) {
+    let request = get_session_id_request_url(&mock.endpoints(), token.clone()).await.unwrap();
+    (follow_authorize(request.url.as_str()).await.to_string(), request)
+}
+
+fn unrelated_session_request(mock: &MockIdentity) -> SessionRequest {
+    SessionRequest {
+        url: Url::parse(&mock.base_url).unwrap(),
+        csrf_token: CsrfToken::new_random(),
+        nonce: Nonce::new_random()
+    }
+}
+
+/// Re-signs the launcher ID token of `token` with modified claims.
+fn with_id_token_claims(mock: &MockIdentity, flow: &AuthFlow, token: &OAuthToken, edit: impl FnOnce(&mut Value)) -> OAuthToken {
+    let mut claims = mock.id_token_claims(flow.client.client_id().as_str(), Some(flow.nonce.secret()));
+    edit(&mut claims);
+    OAuthToken {
+        id_token: mock.sign(&claims),
+        ..token.clone()
+    }
+}
+
+#[tokio::test]
+async fn login_pipeline_against_mock_server() {
+    let mock = MockIdentity::start().await;
+    let endpoints = mock.endpoints();
+
+    let (flow, token) = login_token(&mock).await;
+    assert!(token.access_token.starts_with("mock-access-"));
+    assert!(token.refresh_token.starts_with("mock-refresh-"));
+
+    let (redirect, request) = session_redirect(&mock, &token).await;
+    let session = session_from_redirect(&endpoints, &redirect, &request, &ConsumedSessionStates::default()).await.unwrap();
+    assert_eq!(session.session_id, MOCK_SESSION_ID);
+
+    let characters = characters(&endpoints, session).await.unwrap();
+    assert_eq!(characters.len(), 2);
+    assert_eq!(characters[0].account_id, "1001");
+    assert_eq!(characters[0].display_name, "MockHero");
+    // Never named, so Jagex has no display name for it.
+    assert_eq!(characters[1].display_name, "");
+
+    let info = account_info(&endpoints, flow, token).await.unwrap();
+    assert_eq!(info.nickname, MOCK_NICKNAME);
+    assert_eq!(info.display_name, MOCK_DISPLAY_NAME);
+    assert_eq!(info.user_id, MOCK_SUB);
+}
+
+#[tokio::test]
+async fn refresh_token_grant_issues_new_tokens() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+
+    let refreshed = refresh_oauth_token(&mock.endpoints(), &token.refresh_token).await.unwrap();
+    assert_ne!(refreshed.access_token, token.access_token);
+    assert!(refreshed.refresh_token.starts_with("mock-refresh-"));
+}
+
+#[tokio::test]
+async fn rejected_refresh_token_is_auth_error() {
+    let mock = MockIdentity::start().await;
+
+    let result = refresh_oauth_token(&mock.endpoints(), "revoked").await;
+    assert!(matches!(result, Err(Error::Auth(ref e)) if *e.error() == CoreErrorResponseType::InvalidGrant));
+}
+
+#[tokio::test]
+async fn failing_token_endpoint_on_refresh_is_api_error() {
+    let mock = MockIdentity::start().await;
+
+    mock.override_response(MockRoute::Token, 502, "<html>Bad Gateway</html>");
+    let result = refresh_oauth_token(&mock.endpoints(), "mock-refresh-1").await;
+    assert!(matches!(result, Err(Error::JagexApi { api: JagexApi::Token, status: 502, error: Some(ref e) }) if e.contains("Bad Gateway")));
+}
+
+#[tokio::test]
+async fn mismatched_state_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let flow = begin_login(&mock.endpoints()).await.unwrap();
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+
+    let result = oauth_token(flow, query_param(&redirect, "code"), "forged-state".to_string()).await;
+    assert!(matches!(result, Err(Error::LoginStateMismatch)));
+}
+
+#[tokio::test]
+async fn token_endpoint_error_is_reported() {
+    let mock = MockIdentity::start().await;
+    let flow = begin_login(&mock.endpoints()).await.unwrap();
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+
+    mock.override_response(MockRoute::Token, 400, r#"{"error":"invalid_grant","error_description":"Code expired."}"#);
+    let result = oauth_token(flow, query_param(&redirect, "code"), query_param(&redirect, "state")).await;
+    assert!(matches!(result, Err(Error::JagexApi { api: JagexApi::Token, status: 400, error: Some(ref e) }) if e.contains("invalid_grant")));
+}
+
+#[tokio::test]
+async fn invalid_redirect_uri_is_url_parse_error() {
+    let mock = MockIdentity::start().await;
+    let endpoints = AuthEndpoints {
+        launcher_redirect_uri: "not a url".to_string(),
+        ..mock.endpoints()
+    };
+
+    let result = begin_login(&endpoints).await;
+    assert!(matches!(result, Err(Error::AuthUrlParse(_))));
+}
+
+#[tokio::test]
+async fn unreachable_provider_is_http_error() {
+    let endpoints = AuthEndpoints {
+        oidc_discovery_url: "http://127.0.0.1:9/.well-known/openid-configuration".to_string(),
+        ..AuthEndpoints::default()
+    };
+
+    let result = begin_login(&endpoints).await;
+    assert!(matches!(result, Err(Error::TauriHttp(_))));
+}
+
+#[tokio::test]
+async fn session_redirect_without_id_token_is_rejected() {
+    let mock = MockIdentity::start().await;
+
+    let result = session_from_redirect(&mock.endpoints(), "http://localhost/#code=abc&state=xyz", &unrelated_session_request(&mock), &ConsumedSessionStates::default()).await;
+    assert!(matches!(result, Err(Error::InvalidRedirectUrl(_))));
+}
+
+#[tokio::test]
+async fn session_endpoint_error_is_reported() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+    let (redirect, request) = session_redirect(&mock, &token).await;
+
+    mock.override_response(MockRoute::Sessions, 401, r#"{"error":"access_denied"}"#);
+    let result = session_from_redirect(&mock.endpoints(), &redirect, &request, &ConsumedSessionStates::default()).await;
+    assert!(matches!(result, Err(Error::JagexApi { api: JagexApi::GameSession, status: 401, .. })));
+}
+
+#[tokio::test]
+async fn malformed_characters_response_is_json_error() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+    let (redirect, request) = session_redirect(&mock, &token).await;
+    let session = session_from_redirect(&mock.endpoints(), &redirect, &request, &ConsumedSessionStates::default()).await.unwrap();
+
+    mock.override_response(MockRoute::Accounts, 200, "<html>maintenance</html>");
+    let result = characters(&mock.endpoints(), session.clone()).await;
+    assert!(matches!(result, Err(Error::SerdeJson(_))));
+
+    mock.override_response(MockRoute::Accounts, 200, r#"{"accounts":[]}"#);
+    let result = characters(&mock.endpoints(), session).await;
+    assert!(matches!(result, Err(Error::UnexpectedResponse { api: JagexApi::Characters, .. })));
+}
+
+#[tokio::test]
+async fn display_name_error_is_reported() {
+    let mock = MockIdentity::start().await;
+    let (flow, token) = login_token(&mock).await;
+
+    mock.override_response(MockRoute::DisplayName, 403, r#"{"error":"access_denied"}"#);
+    let result = account_info(&mock.endpoints(), flow, token).await;
+    assert!(matches!(result, Err(Error::JagexApi { api: JagexApi::DisplayName, status: 403, .. })));
+}
+
+#[tokio::test]
+async fn tampered_id_token_signature_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (flow, token) = login_token(&mock).await;
+
+    let parts = token.id_token.split('.').collect::<Vec<&str>>();
+    let mut forged_claims = mock.id_token_claims(flow.client.client_id().as_str(), Some(flow.nonce.secret()));
+    forged_claims["sub"] = json!("someone-else");
+    let forged_payload = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(forged_claims.to_string());
+    let forged = OAuthToken {
+        id_token: format!("{}.{}.{}", parts[0], forged_payload, parts[2]),
+        ..token
+    };
+
+    let result = account_info(&mock.endpoints(), flow, forged).await;
+    assert!(matches!(result, Err(Error::IdTokenVerification(_))));
+}
+
+#[tokio::test]
+async fn id_token_for_another_audience_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (flow, token) = login_token(&mock).await;
+
+    let token = with_id_token_claims(&mock, &flow, &token, |claims| claims["aud"] = json!(["someone-else"]));
+    let result = account_info(&mock.endpoints(), flow, token).await;
+    assert!(matches!(result, Err(Error::IdTokenVerification(_))));
+}
+
+#[tokio::test]
+async fn id_token_from_another_issuer_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (flow, token) = login_token(&mock).await;
+
+    let token = with_id_token_claims(&mock, &flow, &token, |claims| claims["iss"] = json!("https://evil.example"));
+    let result = account_info(&mock.endpoints(), flow, token).await;
+    assert!(matches!(result, Err(Error::IdTokenVerification(_))));
+}
+
+#[tokio::test]
+async fn expired_id_token_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (flow, token) = login_token(&mock).await;
+
+    let token = with_id_token_claims(&mock, &flow, &token, |claims| claims["exp"] = json!(1_000_000));
+    let result = account_info(&mock.endpoints(), flow, token).await;
+    assert!(matches!(result, Err(Error::IdTokenVerification(_))));
+}
+
+#[tokio::test]
+async fn id_token_with_wrong_nonce_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (flow, token) = login_token(&mock).await;
+
+    let token = with_id_token_claims(&mock, &flow, &token, |claims| claims["nonce"] = json!("replayed-nonce"));
+    let result = account_info(&mock.endpoints(), flow, token).await;
+    assert!(matches!(result, Err(Error::NonceMismatch)));
+}
+
+#[tokio::test]
+async fn game_session_id_token_with_wrong_nonce_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+    let (redirect, request) = session_redirect(&mock, &token).await;
+
+    let request = SessionRequest { nonce: Nonce::new_random(), ..request };
+    let result = session_from_redirect(&mock.endpoints(), &redirect, &request, &ConsumedSessionStates::default()).await;
+    assert!(matches!(result, Err(Error::NonceMismatch)));
+}
+
+#[tokio::test]
+async fn game_session_redirect_with_wrong_state_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+    let (redirect, request) = session_redirect(&mock, &token).await;
+
+    let request = SessionRequest { csrf_token: CsrfToken::new_random(), ..request };
+    let result = session_from_redirect(&mock.endpoints(), &redirect, &request, &ConsumedSessionStates::default()).await;
+    assert!(matches!(result, Err(Error::SessionStateMismatch)));
+}
+
+#[tokio::test]
+async fn game_session_redirect_from_another_request_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+    let (redirect, _) = session_redirect(&mock, &token).await;
+    let (_, other_request) = session_redirect(&mock, &token).await;
+
+    let result = session_from_redirect(&mock.endpoints(), &redirect, &other_request, &ConsumedSessionStates::default()).await;
+    assert!(matches!(result, Err(Error::SessionStateMismatch)));
+}
+
+#[tokio::test]
+async fn replayed_game_session_redirect_is_rejected() {
+    let mock = MockIdentity::start().await;
+    let (_, token) = login_token(&mock).await;
+    let (redirect, request) = session_redirect(&mock, &token).await;
+
+    let consumed = ConsumedSessionStates::default();
+    session_from_redirect(&mock.endpoints(), &redirect, &request, &consumed).await.unwrap();
+    let result = session_from_redirect(&mock.endpoints(), &redirect, &request, &consumed).await;
+    assert!(matches!(result, Err(Error::SessionReplayed)));
+}
+
+#[tokio::test]
+async fn errors_serialize_with_code_and_category() {
+    let mock = MockIdentity::start().await;
+    let flow = begin_login(&mock.endpoints()).await.unwrap();
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+
+    mock.override_response(MockRoute::Token, 503, "<html>Service Unavailable</html>");
+    let error = oauth_token(flow, query_param(&redirect, "code"), query_param(&redirect, "state")).await.unwrap_err();
+
+    let payload = serde_json::to_value(&error).unwrap();
+    assert_eq!(payload["code"], "jagex-api");
+    assert_eq!(payload["category"], "server");
+    assert_eq!(payload["retryable"], true);
+    assert!(payload["message"].is_string());
+    assert!(!payload["hints"].as_array().unwrap().is_empty());
+    assert!(payload["detail"].as_str().unwrap().contains("Service Unavailable"));
+}
+
+#[tokio::test]
+async fn silent_login_requests_prompt_none() {
+    let mock = MockIdentity::start().await;
+    let flow = begin_silent_login(&mock.endpoints()).await.unwrap();
+
+    let url = Url::parse(&flow.authorization_request_url).unwrap();
+    assert_eq!(query_param(&url, "prompt"), "none");
+
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+    let (code, state) = authorization_response(&redirect).unwrap();
+    let token = oauth_token(flow.clone(), code, state).await.unwrap();
+    let claims = verify_id_token(&mock.endpoints(), flow.client.client_id(), &token.id_token, &flow.nonce).await.unwrap();
+    assert_eq!(claims.subject().as_str(), MOCK_SUB);
+}
+
+#[tokio::test]
+async fn silent_login_without_provider_session_requires_interaction() {
+    let mock = MockIdentity::start().await;
+    mock.end_provider_session();
+
+    let flow = begin_silent_login(&mock.endpoints()).await.unwrap();
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+    assert!(matches!(authorization_response(&redirect), Err(Error::InteractionRequired)));
+
+    // An interactive login is unaffected.
+    let flow = begin_login(&mock.endpoints()).await.unwrap();
+    let redirect = follow_authorize(&flow.authorization_request_url).await;
+    assert!(authorization_response(&redirect).is_ok());
+}
+
+#[test]
+fn authorization_error_redirect_is_auth_error() {
+    let redirect = Url::parse("https://example.com/redirect?error=access_denied&error_description=Denied&state=xyz").unwrap();
+    let result = authorization_response(&redirect);
+    assert!(matches!(result, Err(Error::Auth(ref e)) if e.error().as_ref() == "access_denied"));
+}
+
+#[test]
+fn stored_sessions_lose_their_quotes() {
+    let stored = json!({ "code": "code", "id_token": "id-token", "state": "state", "session_id": "\"mock-session-id\"" });
+    assert_eq!(serde_json::from_value::<GameSession>(stored).unwrap().session_id, MOCK_SESSION_ID);
+}
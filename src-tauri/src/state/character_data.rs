@@ -0,0 +1,212 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime};
+use uuid::Uuid;
+use crate::error;
+use crate::error::Error;
+use crate::state::{credentials, java, paths};
+use crate::state::launch::LaunchPlan;
+
+const CHARACTER_DATA_DIR: &str = "character-data";
+/// Caches below a character's home, relative to it. Clients rebuild these on their next start.
+const CACHE_DIRS: [&str; 5] = [".cache", ".runelite/cache", ".runelite/jagexcache", "jagexcache", ".local/share/Jagex/RuneScape"];
+/// Files tied to one character's login, which must never be copied to another character.
+const SESSION_FILES: [&str; 1] = [credentials::RUNELITE_CREDENTIALS];
+/// Settings of the user's own home that new character homes start out with, relative to it.
+/// Libraries, logs and screenshots stay behind: clients download or write those themselves.
+const SEEDED: [&str; 3] = [".runelite/settings.properties", ".runelite/profiles2", ".runelite/plugins"];
+/// Files the desktop session keeps in the user's home by default, which clients still need to reach
+/// once `HOME` points elsewhere. Forwarded through their variables unless those are already set.
+const SESSION_COOKIES: [(&str, &str); 3] = [
+    ("XAUTHORITY", ".Xauthority"),
+    ("ICEAUTHORITY", ".ICEauthority"),
+    ("PULSE_COOKIE", ".config/pulse/cookie")
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DataUsage {
+    pub character_id: String,
+    pub dir: PathBuf,
+    /// Everything in the directory, caches included.
+    pub total_bytes: u64,
+    pub cache_bytes: u64
+}
+
+/// The isolated home of a character. Clients see it as `$HOME`, so `~/.runelite` and friends end up here.
+pub fn data_dir<R: Runtime>(app_handle: &AppHandle<R>, character_id: &str) -> error::Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?
+        .join(CHARACTER_DATA_DIR)
+        .join(paths::dir_name(character_id)))
+}
+
+/// The data directory of `character_id`, created on first use and then seeded from the user's own
+/// home, so switching to isolated homes keeps existing RuneLite settings and plugins. The home is
+/// put together next to where it belongs and only moved into place once seeded, so a failed seed
+/// is tried again on the next launch.
+pub fn ensure_data_dir<R: Runtime>(app_handle: &AppHandle<R>, character_id: &str) -> error::Result<PathBuf> {
+    let dir = data_dir(app_handle, character_id)?;
+    if dir.exists() {
+        return Ok(dir);
+    }
+
+    let parent = dir.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let staging = parent.join(format!(".seeding-{}", Uuid::new_v4().simple()));
+    fs::create_dir(&staging)?;
+    let seeded = app_handle.path().home_dir().ok()
+        .map_or(Ok(0), |real_home| seed(&staging, &real_home))
+        .and_then(|_| fs::rename(&staging, &dir).map_err(Error::from));
+    if let Err(e) = seeded {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    Ok(dir)
+}
+
+/// Copies the settings in `real_home` that clients keep per user into the new home `dir`, leaving out
+/// caches and login files. Returns the number of files copied.
+pub fn seed(dir: &Path, real_home: &Path) -> error::Result<u64> {
+    let mut copied = 0;
+    for seeded in SEEDED {
+        let (from, to) = (real_home.join(seeded), dir.join(seeded));
+        if from.is_dir() {
+            fs::create_dir_all(&to)?;
+            copied += copy_tree(real_home, dir, Path::new(seeded))?;
+        } else if from.is_file() {
+            fs::create_dir_all(to.parent().unwrap_or(dir))?;
+            fs::copy(&from, &to)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Points `plan` at `home` for everything clients store per user: `HOME`, the XDG base dirs and,
+/// for Java clients, which ignore `HOME`, the `user.home` property. Variables the plan already sets win.
+/// The X and PulseAudio cookies are still taken from `real_home`, the user's own home.
+pub fn isolate(plan: &mut LaunchPlan, home: &Path, real_home: Option<&Path>) {
+    if let Some(real_home) = real_home {
+        forward_session_cookies(plan, real_home, |name| std::env::var_os(name).is_some());
+    }
+
+    let dirs = [
+        ("HOME", home.to_path_buf()),
+        ("USERPROFILE", home.to_path_buf()),
+        ("XDG_CONFIG_HOME", home.join(".config")),
+        ("XDG_CACHE_HOME", home.join(".cache")),
+        ("XDG_DATA_HOME", home.join(".local/share")),
+        ("XDG_STATE_HOME", home.join(".local/state"))
+    ];
+    for (name, dir) in dirs {
+        plan.env.entry(name.to_string()).or_insert_with(|| dir.to_string_lossy().to_string());
+    }
+
+    if plan.args.iter().any(|arg| java::is_entry_flag(arg)) && !plan.args.iter().any(|arg| arg.starts_with("-Duser.home=")) {
+        plan.args.insert(0, format!("-Duser.home={}", home.display()));
+    }
+}
+
+/// Sets the variable of every cookie in `real_home` that neither the plan nor, per `inherited`,
+/// the launcher's own environment sets.
+fn forward_session_cookies(plan: &mut LaunchPlan, real_home: &Path, inherited: impl Fn(&str) -> bool) {
+    for (name, file) in SESSION_COOKIES {
+        let path = real_home.join(file);
+        if !inherited(name) && path.exists() {
+            plan.env.entry(name.to_string()).or_insert_with(|| path.to_string_lossy().to_string());
+        }
+    }
+}
+
+fn is_cache(relative: &Path) -> bool {
+    CACHE_DIRS.iter().any(|dir| relative == Path::new(dir))
+}
+
+fn is_session_file(relative: &Path) -> bool {
+    SESSION_FILES.iter().any(|file| relative == Path::new(file))
+}
+
+/// Bytes used by the files below `path`. Symlinks are counted as themselves, not followed.
+fn size_of(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e)
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += size_of(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+pub fn usage(character_id: &str, dir: &Path) -> error::Result<DataUsage> {
+    let mut cache_bytes = 0;
+    for cache in CACHE_DIRS {
+        cache_bytes += size_of(&dir.join(cache))?;
+    }
+    Ok(DataUsage {
+        character_id: character_id.to_string(),
+        dir: dir.to_path_buf(),
+        total_bytes: size_of(dir)?,
+        cache_bytes
+    })
+}
+
+/// Copies `relative` in the home `from` to the home `to`, skipping caches and login files.
+fn copy_tree(from: &Path, to: &Path, relative: &Path) -> io::Result<u64> {
+    let mut copied = 0;
+    for entry in fs::read_dir(from.join(relative))? {
+        let entry = entry?;
+        let relative = relative.join(entry.file_name());
+        if is_cache(&relative) || is_session_file(&relative) {
+            continue;
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            fs::create_dir_all(to.join(&relative))?;
+            copied += copy_tree(from, to, &relative)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), to.join(&relative))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Copies the settings in `from` over those in `to`, leaving out caches and login files.
+/// Returns the number of files copied.
+pub fn copy_settings(from: &Path, to: &Path) -> error::Result<u64> {
+    if !from.is_dir() {
+        return Err(Error::CharacterDataNotFound(from.display().to_string()));
+    }
+    // Copying a file onto itself truncates it before it is read.
+    if from == to || to.canonicalize().is_ok_and(|to| from.canonicalize().is_ok_and(|from| from == to)) {
+        return Err(Error::InvalidInput("Settings cannot be copied onto the same character".to_string()));
+    }
+    fs::create_dir_all(to)?;
+    Ok(copy_tree(from, to, Path::new(""))?)
+}
+
+/// Deletes the caches in `dir`, returning how many bytes that freed.
+pub fn wipe_caches(dir: &Path) -> error::Result<u64> {
+    let mut freed = 0;
+    for cache in CACHE_DIRS {
+        let path = dir.join(cache);
+        let size = size_of(&path)?;
+        match fs::remove_dir_all(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => freed += size
+        }
+    }
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+fn write(dir: &Path, relative: &str, contents: &[u8]) {
+    let path = dir.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn runelite_home() -> TempDir {
+    let home = TempDir::new("character-data");
+    write(&home, ".runelite/settings.properties", b"runelite.theme=dark");
+    write(&home, ".runelite/profiles2/default.properties", b"xp.orbs=true");
+    write(&home, ".runelite/credentials.properties", b"JX_SESSION_ID=secret");
+    write(&home, ".runelite/jagexcache/oldschool/main_file_cache.dat2", &[0; 4096]);
+    write(&home, ".cache/fontconfig/cache", &[0; 1024]);
+    home
+}
+
+#[test]
+fn clients_are_pointed_at_the_character_home() {
+    let home = PathBuf::from("/data/1001");
+    let mut plan = LaunchPlan {
+        program: PathBuf::from("/usr/bin/java"),
+        args: vec!["-Xmx2g".to_string(), "-jar".to_string(), "RuneLite.jar".to_string()],
+        env: BTreeMap::from([("XDG_CONFIG_HOME".to_string(), "/home/mock/.config".to_string())]),
+        cwd: None
+    };
+
+    isolate(&mut plan, &home, None);
+    assert_eq!(plan.env["HOME"], "/data/1001");
+    assert_eq!(plan.env["XDG_CACHE_HOME"], "/data/1001/.cache");
+    // Set by the profile, so left alone.
+    assert_eq!(plan.env["XDG_CONFIG_HOME"], "/home/mock/.config");
+    assert_eq!(plan.args, vec!["-Duser.home=/data/1001", "-Xmx2g", "-jar", "RuneLite.jar"]);
+
+    let mut native = LaunchPlan { program: PathBuf::from("/opt/rs3/runescape"), ..LaunchPlan::default() };
+    isolate(&mut native, &home, None);
+    assert!(native.args.is_empty());
+}
+
+#[test]
+fn session_cookies_are_taken_from_the_real_home() {
+    let real_home = TempDir::new("character-data");
+    write(&real_home, ".Xauthority", b"cookie");
+    write(&real_home, ".config/pulse/cookie", b"cookie");
+    let mut plan = LaunchPlan {
+        env: BTreeMap::from([("PULSE_COOKIE".to_string(), "/run/pulse/cookie".to_string())]),
+        ..LaunchPlan::default()
+    };
+
+    forward_session_cookies(&mut plan, &real_home, |_| false);
+    assert_eq!(plan.env["XAUTHORITY"], real_home.join(".Xauthority").to_string_lossy());
+    // Set by the profile, so left alone.
+    assert_eq!(plan.env["PULSE_COOKIE"], "/run/pulse/cookie");
+    // Missing in the real home.
+    assert!(!plan.env.contains_key("ICEAUTHORITY"));
+
+    // Inherited from the launcher, so left alone.
+    let mut inherited = LaunchPlan::default();
+    forward_session_cookies(&mut inherited, &real_home, |name| name == "XAUTHORITY");
+    assert!(!inherited.env.contains_key("XAUTHORITY"));
+}
+
+#[test]
+fn new_homes_are_seeded_with_existing_settings() {
+    let real_home = runelite_home();
+    write(&real_home, ".runelite/plugins/mock-plugin.jar", b"plugin");
+    write(&real_home, ".runelite/repository2/client.jar", &[0; 2048]);
+    write(&real_home, ".runelite/logs/client.log", b"log");
+    write(&real_home, ".runelite/screenshots/MockHero/level.png", &[0; 512]);
+    let home = TempDir::new("character-data");
+
+    assert_eq!(seed(&home, &real_home).unwrap(), 3);
+    assert_eq!(fs::read(home.join(".runelite/settings.properties")).unwrap(), b"runelite.theme=dark");
+    assert!(home.join(".runelite/profiles2/default.properties").exists());
+    assert!(home.join(".runelite/plugins/mock-plugin.jar").exists());
+    assert!(!home.join(".runelite/repository2").exists());
+    assert!(!home.join(".runelite/logs").exists());
+    assert!(!home.join(".runelite/screenshots").exists());
+    assert!(!home.join(".runelite/credentials.properties").exists());
+    assert!(!home.join(".runelite/jagexcache").exists());
+    assert!(!home.join(".cache").exists());
+
+    // Nothing to seed from.
+    assert_eq!(seed(&home, &real_home.join("missing")).unwrap(), 0);
+}
+
+#[test]
+fn usage_separates_caches() {
+    let home = runelite_home();
+
+    let usage = usage("1001", &home).unwrap();
+    assert_eq!(usage.cache_bytes, 4096 + 1024);
+    assert!(usage.total_bytes > usage.cache_bytes);
+    assert_eq!(super::usage("2002", &home.join("missing")).unwrap().total_bytes, 0);
+}
+
+#[test]
+fn settings_are_copied_without_caches_or_credentials() {
+    let from = runelite_home();
+    let to = TempDir::new("character-data");
+    write(&to, ".runelite/settings.properties", b"runelite.theme=light");
+
+    assert_eq!(copy_settings(&from, &to).unwrap(), 2);
+    assert_eq!(fs::read(to.join(".runelite/settings.properties")).unwrap(), b"runelite.theme=dark");
+    assert!(to.join(".runelite/profiles2/default.properties").exists());
+    assert!(!to.join(".runelite/credentials.properties").exists());
+    assert!(!to.join(".runelite/jagexcache").exists());
+    assert!(!to.join(".cache").exists());
+
+    assert!(matches!(copy_settings(&from.join("missing"), &to), Err(Error::CharacterDataNotFound(_))));
+
+    // Copying onto itself, directly or through another path, must leave the settings alone.
+    assert!(matches!(copy_settings(&from, &from), Err(Error::InvalidInput(_))));
+    assert!(matches!(copy_settings(&from, &from.join(".runelite/..")), Err(Error::InvalidInput(_))));
+    assert_eq!(fs::read(from.join(".runelite/settings.properties")).unwrap(), b"runelite.theme=dark");
+}
+
+#[test]
+fn wiping_caches_keeps_settings() {
+    let home = runelite_home();
+
+    assert_eq!(wipe_caches(&home).unwrap(), 4096 + 1024);
+    assert!(home.join(".runelite/settings.properties").exists());
+    assert!(!home.join(".runelite/jagexcache").exists());
+    assert_eq!(wipe_caches(&home).unwrap(), 0);
+}
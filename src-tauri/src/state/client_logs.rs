@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdout};
+use tokio::sync::{broadcast, oneshot};
+use crate::error;
+use crate::error::Error;
+use crate::state::supervisor::RunningClient;
+use crate::state::paths::dir_name;
+
+const LOGS_DIR: &str = "client-logs";
+const LOG_EXTENSION: &str = "log";
+/// A session log is rotated once it grows past this size.
+const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024;
+/// Rotated files kept per session, next to the current one as `<session>.log.1`, `.2`, ...
+const ROTATED_FILES: usize = 2;
+/// Sessions kept per character; older ones are deleted when a new one starts.
+const SESSIONS_KEPT: usize = 20;
+/// Lines of an existing log sent before a tail starts following it.
+const TAIL_LINES: usize = 200;
+const STDERR_PREFIX: &str = "[stderr] ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogStream {
+    Stdout,
+    Stderr
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub text: String
+}
+
+impl LogLine {
+    fn encode(&self) -> String {
+        match self.stream {
+            LogStream::Stdout => format!("{}\n", self.text),
+            LogStream::Stderr => format!("{}{}\n", STDERR_PREFIX, self.text)
+        }
+    }
+
+    fn decode(line: &str) -> Self {
+        match line.strip_prefix(STDERR_PREFIX) {
+            Some(text) => LogLine { stream: LogStream::Stderr, text: text.to_string() },
+            None => LogLine { stream: LogStream::Stdout, text: line.to_string() }
+        }
+    }
+}
+
+/// One run of a client as it shows up in the logs folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSession {
+    pub character_id: String,
+    pub session_id: String,
+    pub started_at: u64,
+    /// Size of the current log file in bytes, rotated files not included.
+    pub size: u64,
+    pub live: bool
+}
+
+/// The file a session is written to, rotated when it gets too large.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64
+}
+
+impl LogFile {
+    fn create(path: PathBuf) -> io::Result<Self> {
+        let file = File::create(&path)?;
+        Ok(LogFile { path, file, size: 0 })
+    }
+
+    fn append(&mut self, line: &LogLine) -> io::Result<()> {
+        let encoded = line.encode();
+        if self.size > 0 && self.size + encoded.len() as u64 > MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        self.file.write_all(encoded.as_bytes())?;
+        self.size += encoded.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+/// The log of a client that is still running, which tails can follow.
+pub struct SessionLog {
+    file: Mutex<LogFile>,
+    lines: broadcast::Sender<LogLine>
+}
+
+impl SessionLog {
+    fn append(&self, line: LogLine) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Err(e) = file.append(&line) {
+                log::error!("Failed to write client log {}: {}", file.path.display(), e);
+            }
+            // Sent while holding the file, so a tail never misses or repeats a line.
+            let _ = self.lines.send(line);
+        }
+    }
+
+    /// The last lines written so far, and a receiver for everything written after them.
+    fn follow(&self) -> error::Result<(Vec<LogLine>, broadcast::Receiver<LogLine>)> {
+        let file = self.file.lock()
+            .map_err(|_| Error::LocalState("Client log is poisoned.".to_string()))?;
+        Ok((read_tail(&file.path, TAIL_LINES)?, self.lines.subscribe()))
+    }
+}
+
+/// The logs of running clients and the tails following them.
+#[derive(Default)]
+pub struct ClientLogs {
+    live: Mutex<HashMap<String, Arc<SessionLog>>>,
+    tails: Mutex<HashMap<u32, oneshot::Sender<()>>>
+}
+
+impl ClientLogs {
+    /// Stops treating a session as live once its client exited. Output still in flight is written.
+    pub fn finish(&self, session_id: &str) {
+        if let Ok(mut live) = self.live.lock() {
+            live.remove(session_id);
+        }
+    }
+
+    fn live_session(&self, session_id: &str) -> Option<Arc<SessionLog>> {
+        self.live.lock().ok().and_then(|live| live.get(session_id).cloned())
+    }
+
+    fn is_live(&self, session_id: &str) -> bool {
+        self.live.lock().is_ok_and(|live| live.contains_key(session_id))
+    }
+
+    /// Stops the tail streaming to `channel_id`. Returns false if there was none.
+    pub fn stop_tail(&self, channel_id: u32) -> bool {
+        match self.tails.lock().ok().and_then(|mut tails| tails.remove(&channel_id)) {
+            Some(stop) => stop.send(()).is_ok(),
+            None => false
+        }
+    }
+}
+
+/// Where the logs of `character_id` are kept, or the root of all client logs.
+pub fn logs_dir<R: Runtime>(app_handle: &AppHandle<R>, character_id: Option<&str>) -> error::Result<PathBuf> {
+    let root = app_handle.path().app_local_data_dir()?.join(LOGS_DIR);
+    Ok(match character_id {
+        Some(character_id) => root.join(dir_name(character_id)),
+        None => root
+    })
+}
+
+fn session_id(client: &RunningClient) -> String {
+    format!("{}-{}", client.started_at, client.pid)
+}
+
+fn session_path(dir: &Path, session_id: &str) -> PathBuf {
+    dir.join(dir_name(session_id)).with_extension(LOG_EXTENSION)
+}
+
+fn started_at(session_id: &str) -> u64 {
+    session_id.split('-').next()
+        .and_then(|started_at| started_at.parse().ok())
+        .unwrap_or_default()
+}
+
+/// The session ids logged in `dir`, oldest first.
+fn session_ids(dir: &Path) -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(dir) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        entries => entries?
+    };
+
+    let mut ids = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == LOG_EXTENSION))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+        .collect::<Vec<String>>();
+    ids.sort_by_key(|id| (started_at(id), id.clone()));
+    Ok(ids)
+}
+
+/// Deletes the oldest sessions in `dir` so that at most `keep` remain.
+fn prune_sessions(dir: &Path, keep: usize) -> io::Result<()> {
+    let ids = session_ids(dir)?;
+    for id in ids.iter().take(ids.len().saturating_sub(keep)) {
+        let path = session_path(dir, id);
+        fs::remove_file(&path)?;
+        for index in 1..=ROTATED_FILES {
+            let _ = fs::remove_file(rotated_path(&path, index));
+        }
+    }
+    Ok(())
+}
+
+fn read_tail(path: &Path, lines: usize) -> io::Result<Vec<LogLine>> {
+    let contents = String::from_utf8_lossy(&fs::read(path)?).to_string();
+    let all = contents.lines().collect::<Vec<&str>>();
+    Ok(all[all.len().saturating_sub(lines)..].iter().map(|line| LogLine::decode(line)).collect())
+}
+
+/// Streams the output of a freshly spawned client into a new session log. Returns the session id.
+/// The output is read even if the log cannot be created, since a client whose pipes fill up blocks.
+pub fn capture<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    client: &RunningClient,
+    child: &mut Child
+) -> error::Result<String> {
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    let session_id = session_id(client);
+    let session = logs_dir(app_handle, Some(&client.character_id))
+        .and_then(|dir| open_session(&dir, &session_id));
+    drain(stdout, stderr, session.as_ref().ok().cloned());
+
+    if let Ok(mut live) = app_handle.state::<ClientLogs>().live.lock() {
+        live.insert(session_id.clone(), session?);
+    }
+    Ok(session_id)
+}
+
+fn open_session(dir: &Path, session_id: &str) -> error::Result<Arc<SessionLog>> {
+    fs::create_dir_all(dir)?;
+    if let Err(e) = prune_sessions(dir, SESSIONS_KEPT - 1) {
+        log::error!("Failed to delete old client logs in {}: {}", dir.display(), e);
+    }
+
+    let (lines, _) = broadcast::channel(256);
+    Ok(Arc::new(SessionLog {
+        file: Mutex::new(LogFile::create(session_path(dir, session_id))?),
+        lines
+    }))
+}
+
+/// Reads the output of a client into `session`, or discards it when there is no session log.
+fn drain(stdout: Option<ChildStdout>, stderr: Option<ChildStderr>, session: Option<Arc<SessionLog>>) {
+    if let Some(stdout) = stdout {
+        tauri::async_runtime::spawn(pipe(stdout, LogStream::Stdout, session.clone()));
+    }
+    if let Some(stderr) = stderr {
+        tauri::async_runtime::spawn(pipe(stderr, LogStream::Stderr, session));
+    }
+}
+
+async fn pipe(output: impl AsyncRead + Unpin, stream: LogStream, session: Option<Arc<SessionLog>>) {
+    let mut reader = BufReader::new(output);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer).await {
+            Ok(0) => break,
+            Ok(_) => if let Some(session) = &session {
+                let text = String::from_utf8_lossy(&buffer).trim_end_matches(['\r', '\n']).to_string();
+                session.append(LogLine { stream, text });
+            },
+            Err(e) => {
+                log::error!("Failed to read client output: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Past and running sessions of `character_id`, newest first.
+pub fn sessions<R: Runtime>(app_handle: &AppHandle<R>, character_id: &str) -> error::Result<Vec<LogSession>> {
+    let dir = logs_dir(app_handle, Some(character_id))?;
+    let logs = app_handle.state::<ClientLogs>();
+
+    let mut sessions = Vec::new();
+    for session_id in session_ids(&dir)?.into_iter().rev() {
+        let size = fs::metadata(session_path(&dir, &session_id)).map(|metadata| metadata.len()).unwrap_or_default();
+        sessions.push(LogSession {
+            character_id: character_id.to_string(),
+            started_at: started_at(&session_id),
+            size,
+            live: logs.is_live(&session_id),
+            session_id
+        });
+    }
+    Ok(sessions)
+}
+
+/// Sends the end of a session log to `channel` and, while its client runs, every line after it.
+///
+/// Returns once the client is gone and its output drained, or the tail is stopped.
+pub async fn tail<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    character_id: &str,
+    session_id: &str,
+    channel: Channel<LogLine>
+) -> error::Result<()> {
+    let logs = app_handle.state::<ClientLogs>();
+    let Some(session) = logs.live_session(session_id) else {
+        let path = session_path(&logs_dir(app_handle, Some(character_id))?, session_id);
+        if !path.exists() {
+            return Err(Error::LogSessionNotFound(session_id.to_string()));
+        }
+        for line in read_tail(&path, TAIL_LINES)? {
+            channel.send(line)?;
+        }
+        return Ok(());
+    };
+
+    let (backlog, mut lines) = session.follow()?;
+    // Holding on to the session would keep the stream open after the client and its pipes are gone.
+    drop(session);
+    for line in backlog {
+        channel.send(line)?;
+    }
+
+    let (stop_tx, mut stop_rx) = oneshot::channel();
+    if let Ok(mut tails) = logs.tails.lock() {
+        tails.insert(channel.id(), stop_tx);
+    }
+
+    let result = loop {
+        tokio::select! {
+            line = lines.recv() => match line {
+                Ok(line) => if let Err(e) = channel.send(line) {
+                    break Err(e.into());
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::info!("Tail of client log {} skipped {} lines.", session_id, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break Ok(())
+            },
+            _ = &mut stop_rx => break Ok(())
+        }
+    };
+
+    if let Ok(mut tails) = logs.tails.lock() {
+        tails.remove(&channel.id());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,86 @@
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+#[test]
+fn stderr_lines_survive_the_log_file() {
+    let stdout = LogLine { stream: LogStream::Stdout, text: "Loaded 12 plugins".to_string() };
+    let stderr = LogLine { stream: LogStream::Stderr, text: "java.lang.OutOfMemoryError".to_string() };
+
+    assert_eq!(LogLine::decode(stdout.encode().trim_end()), stdout);
+    assert_eq!(LogLine::decode(stderr.encode().trim_end()), stderr);
+}
+
+#[test]
+fn large_logs_are_rotated() {
+    let dir = TempDir::new("logs");
+    let path = session_path(&dir, "1700000000-42");
+    let mut file = LogFile::create(path.clone()).unwrap();
+
+    let line = LogLine { stream: LogStream::Stdout, text: "x".repeat(1024 * 1024) };
+    for _ in 0..(4 * ROTATED_FILES + 6) {
+        file.append(&line).unwrap();
+    }
+
+    assert!(fs::metadata(&path).unwrap().len() <= MAX_LOG_BYTES);
+    for index in 1..=ROTATED_FILES {
+        assert!(rotated_path(&path, index).exists());
+    }
+    assert!(!rotated_path(&path, ROTATED_FILES + 1).exists());
+    // Rotated files are not mistaken for sessions of their own.
+    assert_eq!(session_ids(&dir).unwrap(), vec!["1700000000-42"]);
+}
+
+#[test]
+fn oldest_sessions_are_pruned() {
+    let dir = TempDir::new("logs");
+    for session_id in ["1700000300-3", "1700000100-1", "1700000200-2"] {
+        fs::write(session_path(&dir, session_id), "").unwrap();
+    }
+    fs::write(rotated_path(&session_path(&dir, "1700000100-1"), 1), "").unwrap();
+
+    prune_sessions(&dir, 2).unwrap();
+
+    assert_eq!(session_ids(&dir).unwrap(), vec!["1700000200-2", "1700000300-3"]);
+    assert!(!rotated_path(&session_path(&dir, "1700000100-1"), 1).exists());
+}
+
+#[test]
+fn tail_returns_the_last_lines() {
+    let dir = TempDir::new("logs");
+    let path = session_path(&dir, "1700000000-42");
+    let contents = (0..10).map(|i| format!("line {}\n", i)).collect::<String>();
+    fs::write(&path, contents).unwrap();
+
+    let tail = read_tail(&path, 3).unwrap();
+    assert_eq!(tail.iter().map(|line| line.text.as_str()).collect::<Vec<&str>>(), vec!["line 7", "line 8", "line 9"]);
+}
+
+#[test]
+fn session_ids_cannot_escape_the_logs_directory() {
+    let dir = PathBuf::from("/logs/1001");
+    let path = session_path(&dir, "../../secrets");
+    assert_eq!(path.parent(), Some(dir.as_path()));
+    assert_eq!(path.extension().unwrap(), LOG_EXTENSION);
+    assert!(path.file_name().unwrap().to_string_lossy().starts_with("______secrets-"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn output_is_drained_when_the_log_cannot_be_created() {
+    // A file where the logs directory should be makes creating the session log fail.
+    let dir = TempDir::new("logs");
+    fs::write(dir.join("client-logs"), "").unwrap();
+    assert!(open_session(&dir.join("client-logs"), "1-1").is_err());
+
+    // Far more output than a pipe buffer holds: the client only exits if it is read.
+    let mut child = tokio::process::Command::new("sh")
+        .args(["-c", "head -c 1000000 /dev/zero; head -c 1000000 /dev/zero >&2"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    drain(child.stdout.take(), child.stderr.take(), None);
+
+    let status = tokio::time::timeout(std::time::Duration::from_secs(10), child.wait()).await;
+    assert!(status.expect("client blocked on a full pipe").unwrap().success());
+}
@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::error;
+use crate::error::Error;
+use crate::state::launch::ClientConfig;
+
+/// Replaces the path of the client definitions file, e.g. with one kept next to a team's scripts.
+pub const CLIENT_TEMPLATES_ENV: &str = "RUNELAUNCHER_CLIENT_TEMPLATES";
+/// Looked up in the app config dir in this order.
+const CLIENT_TEMPLATES_FILES: [&str; 2] = ["clients.toml", "clients.json"];
+/// The placeholders templates may use. Each is filled in per launch from the session and character.
+pub const PLACEHOLDERS: [&str; 5] = ["session_id", "character_id", "display_name", "account_id", "data_dir"];
+
+/// A client declared by the user, e.g. Bolt, HDOS or a RuneLite fork.
+///
+/// `args`, `env` values and `working_dir` may contain `{placeholder}`s; `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientTemplate {
+    pub name: String,
+    /// The program to start. Exactly one of `executable` and `jar` is set.
+    #[serde(default)]
+    pub executable: Option<PathBuf>,
+    /// A jar started with the Java runtime pinned for this client.
+    #[serde(default)]
+    pub jar: Option<PathBuf>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub working_dir: Option<String>
+}
+
+/// The contents of the definitions file: `[[clients]]` tables in TOML, or a `clients` array in JSON.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ClientDefinitions {
+    #[serde(default)]
+    pub clients: Vec<ClientTemplate>
+}
+
+/// What the placeholders of a template expand to for one launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateValues {
+    pub session_id: String,
+    pub character_id: String,
+    pub display_name: String,
+    pub account_id: String,
+    pub data_dir: PathBuf
+}
+
+impl TemplateValues {
+    fn get(&self, placeholder: &str) -> Option<String> {
+        match placeholder {
+            "session_id" => Some(self.session_id.clone()),
+            "character_id" => Some(self.character_id.clone()),
+            "display_name" => Some(self.display_name.clone()),
+            "account_id" => Some(self.account_id.clone()),
+            "data_dir" => Some(self.data_dir.to_string_lossy().to_string()),
+            _ => None
+        }
+    }
+}
+
+impl ClientTemplate {
+    /// Checks the template can be launched, naming the first problem found.
+    pub fn validate(&self) -> error::Result<()> {
+        let invalid = |reason: String| Error::InvalidClientTemplate { name: self.name.clone(), reason };
+
+        if self.name.trim().is_empty() {
+            return Err(invalid("The client has no name".to_string()));
+        }
+        if self.executable.is_some() == self.jar.is_some() {
+            return Err(invalid("Set either an executable or a jar".to_string()));
+        }
+        if let Some(name) = self.env.keys().find(|name| name.is_empty() || name.contains(['=', '\0']) || name.starts_with("JX_")) {
+            return Err(invalid(format!("{:?} cannot be set as an environment variable", name)));
+        }
+
+        let templates = self.args.iter()
+            .chain(self.env.values())
+            .chain(self.working_dir.iter());
+        for template in templates {
+            for placeholder in placeholders(template).map_err(invalid)? {
+                if !PLACEHOLDERS.contains(&placeholder) {
+                    return Err(invalid(format!("Unknown placeholder {{{}}} in {:?}", placeholder, template)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The client config of this template with its placeholders filled in.
+    pub fn config(&self, values: &TemplateValues) -> error::Result<ClientConfig> {
+        let expand = |template: &str| expand(template, values)
+            .map_err(|reason| Error::InvalidClientTemplate { name: self.name.clone(), reason });
+
+        let mut env = BTreeMap::new();
+        for (name, value) in &self.env {
+            env.insert(name.clone(), expand(value)?);
+        }
+        Ok(ClientConfig {
+            program: self.executable.clone().or_else(|| self.jar.clone()).unwrap_or_default(),
+            args: self.args.iter().map(|arg| expand(arg)).collect::<error::Result<_>>()?,
+            env,
+            working_dir: self.working_dir.as_deref().map(expand).transpose()?.map(PathBuf::from)
+        })
+    }
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Placeholder(&'a str)
+}
+
+fn tokens(template: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    while let Some(index) = rest.find(['{', '}']) {
+        tokens.push(Token::Text(&rest[..index]));
+        let after = &rest[index + 1..];
+        match (&rest[index..index + 1], after.chars().next()) {
+            ("{", Some('{')) => {
+                tokens.push(Token::Text("{"));
+                rest = &after[1..];
+            }
+            ("}", Some('}')) => {
+                tokens.push(Token::Text("}"));
+                rest = &after[1..];
+            }
+            ("{", _) => {
+                let end = after.find('}').ok_or_else(|| format!("Unclosed placeholder in {:?}", template))?;
+                tokens.push(Token::Placeholder(&after[..end]));
+                rest = &after[end + 1..];
+            }
+            _ => return Err(format!("Unmatched }} in {:?}; write }}}} for a literal brace", template))
+        }
+    }
+    tokens.push(Token::Text(rest));
+    Ok(tokens)
+}
+
+/// The placeholders `template` uses.
+pub fn placeholders(template: &str) -> Result<Vec<&str>, String> {
+    Ok(tokens(template)?.into_iter()
+        .filter_map(|token| match token {
+            Token::Placeholder(name) => Some(name),
+            Token::Text(_) => None
+        })
+        .collect())
+}
+
+/// `template` with its placeholders replaced by `values`.
+pub fn expand(template: &str, values: &TemplateValues) -> Result<String, String> {
+    let mut expanded = String::new();
+    for token in tokens(template)? {
+        match token {
+            Token::Text(text) => expanded.push_str(text),
+            Token::Placeholder(name) => expanded.push_str(&values.get(name)
+                .ok_or_else(|| format!("Unknown placeholder {{{}}}", name))?)
+        }
+    }
+    Ok(expanded)
+}
+
+/// Parses and validates a definitions file. TOML unless the file name ends in `.json`.
+pub fn from_file(path: &Path) -> error::Result<Vec<ClientTemplate>> {
+    let contents = fs::read_to_string(path)?;
+    let definitions = if path.extension().is_some_and(|extension| extension == "json") {
+        serde_json::from_str::<ClientDefinitions>(&contents).map_err(|e| Error::InvalidClientTemplate {
+            name: path.display().to_string(),
+            reason: e.to_string()
+        })?
+    } else {
+        toml::from_str::<ClientDefinitions>(&contents).map_err(|e| Error::InvalidClientTemplate {
+            name: path.display().to_string(),
+            reason: e.message().to_string()
+        })?
+    };
+
+    let mut names = HashSet::new();
+    for template in &definitions.clients {
+        template.validate()?;
+        if !names.insert(template.name.as_str()) {
+            return Err(Error::InvalidClientTemplate {
+                name: template.name.clone(),
+                reason: "Another client has the same name".to_string()
+            });
+        }
+    }
+    Ok(definitions.clients)
+}
+
+/// The definitions file from `$RUNELAUNCHER_CLIENT_TEMPLATES` or the app config dir, if there is one.
+pub fn templates_path<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Option<PathBuf>> {
+    if let Some(path) = std::env::var_os(CLIENT_TEMPLATES_ENV) {
+        return Ok(Some(PathBuf::from(path)));
+    }
+    let dir = app_handle.path().app_config_dir()?;
+    Ok(CLIENT_TEMPLATES_FILES.iter()
+        .map(|file| dir.join(file))
+        .find(|path| path.exists()))
+}
+
+/// The user's client templates. The file is read on every call so edits apply to the next launch.
+pub fn load<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Vec<ClientTemplate>> {
+    match templates_path(app_handle)? {
+        Some(path) => from_file(&path),
+        None => Ok(Vec::new())
+    }
+}
+
+pub fn find<R: Runtime>(app_handle: &AppHandle<R>, name: &str) -> error::Result<ClientTemplate> {
+    load(app_handle)?.into_iter()
+        .find(|template| template.name == name)
+        .ok_or_else(|| Error::ClientTemplateNotFound(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests;
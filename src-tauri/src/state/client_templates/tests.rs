@@ -0,0 +1,120 @@
+use crate::test_support::mock_app::MockApp;
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+const DEFINITIONS: &str = r#"
+[[clients]]
+name = "HDOS"
+jar = "/opt/hdos/hdos-launcher.jar"
+args = ["--data", "{data_dir}/hdos"]
+env = { HDOS_USER = "{display_name}" }
+
+[[clients]]
+name = "Bolt"
+executable = "/usr/bin/bolt"
+args = ["--session={session_id}", "--character={character_id}", "--json={{\"account\":\"{account_id}\"}}"]
+working_dir = "{data_dir}"
+"#;
+
+fn values() -> TemplateValues {
+    TemplateValues {
+        session_id: "mock-session-id".to_string(),
+        character_id: "1001".to_string(),
+        display_name: "MockHero".to_string(),
+        account_id: "account-1".to_string(),
+        data_dir: PathBuf::from("/data/1001")
+    }
+}
+
+/// Writes a definitions file to a new directory, which is removed when the returned guard is dropped.
+fn write_definitions(file_name: &str, contents: &str) -> (TempDir, PathBuf) {
+    let dir = TempDir::new("templates");
+    let path = dir.join(file_name);
+    fs::write(&path, contents).unwrap();
+    (dir, path)
+}
+
+fn template(args: &[&str]) -> ClientTemplate {
+    ClientTemplate {
+        name: "Custom".to_string(),
+        executable: Some(PathBuf::from("/usr/bin/custom")),
+        jar: None,
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        env: BTreeMap::new(),
+        working_dir: None
+    }
+}
+
+#[test]
+fn definitions_are_loaded_and_expanded() {
+    let (_dir, path) = write_definitions("clients.toml", DEFINITIONS);
+    let templates = from_file(&path).unwrap();
+    assert_eq!(templates.len(), 2);
+
+    let hdos = templates[0].config(&values()).unwrap();
+    assert_eq!(hdos.program, PathBuf::from("/opt/hdos/hdos-launcher.jar"));
+    assert_eq!(hdos.args, vec!["--data", "/data/1001/hdos"]);
+    assert_eq!(hdos.env["HDOS_USER"], "MockHero");
+
+    let bolt = templates[1].config(&values()).unwrap();
+    assert_eq!(bolt.args, vec!["--session=mock-session-id", "--character=1001", "--json={\"account\":\"account-1\"}"]);
+    assert_eq!(bolt.working_dir, Some(PathBuf::from("/data/1001")));
+}
+
+#[test]
+fn json_definitions_are_supported() {
+    let (_dir, path) = write_definitions("clients.json", r#"{"clients": [{"name": "Fork", "jar": "/opt/fork.jar"}]}"#);
+    assert_eq!(from_file(&path).unwrap()[0].jar, Some(PathBuf::from("/opt/fork.jar")));
+}
+
+#[test]
+fn syntax_errors_name_the_definitions_file() {
+    for (file, contents) in [("clients.json", r#"{"clients": [{"name": "Fork",}]}"#), ("clients.toml", "[[clients]\nname = \"Fork\"\n")] {
+        let (_dir, path) = write_definitions(file, contents);
+        match from_file(&path) {
+            Err(Error::InvalidClientTemplate { name, .. }) => assert_eq!(name, path.display().to_string()),
+            other => panic!("Expected an invalid template, got {:?}", other)
+        }
+    }
+}
+
+#[test]
+fn unknown_placeholders_are_rejected_on_load() {
+    let (_dir, path) = write_definitions("clients.toml", "[[clients]]\nname = \"Typo\"\nexecutable = \"/bin/true\"\nargs = [\"{sesion_id}\"]\n");
+    match from_file(&path) {
+        Err(Error::InvalidClientTemplate { name, reason }) => {
+            assert_eq!(name, "Typo");
+            assert!(reason.contains("{sesion_id}"), "{}", reason);
+        }
+        other => panic!("Expected an invalid template, got {:?}", other)
+    }
+}
+
+#[test]
+fn malformed_templates_are_rejected() {
+    assert!(template(&["{session_id"]).validate().is_err());
+    assert!(template(&["session_id}"]).validate().is_err());
+    assert!(template(&["{{literal}}"]).validate().is_ok());
+
+    let both = ClientTemplate { jar: Some(PathBuf::from("/opt/client.jar")), ..template(&[]) };
+    assert!(both.validate().is_err());
+    let neither = ClientTemplate { executable: None, ..template(&[]) };
+    assert!(neither.validate().is_err());
+    let session = ClientTemplate { env: BTreeMap::from([("JX_SESSION_ID".to_string(), "x".to_string())]), ..template(&[]) };
+    assert!(session.validate().is_err());
+}
+
+#[test]
+fn duplicate_names_are_rejected() {
+    let (_dir, path) = write_definitions("clients.json", r#"{"clients": [
+        {"name": "Fork", "jar": "/opt/a.jar"},
+        {"name": "Fork", "jar": "/opt/b.jar"}
+    ]}"#);
+    assert!(matches!(from_file(&path), Err(Error::InvalidClientTemplate { .. })));
+}
+
+#[test]
+fn unknown_templates_are_not_found() {
+    let app = MockApp::new();
+    assert!(matches!(find(app.handle(), "Unknown"), Err(Error::ClientTemplateNotFound(ref name)) if name == "Unknown"));
+}
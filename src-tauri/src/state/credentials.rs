@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+use crate::error;
+use crate::state::{accounts, character_data, launch};
+use crate::state::auth::GameSession;
+
+/// Where RuneLite looks for Jagex account credentials, relative to its home.
+pub const RUNELITE_CREDENTIALS: &str = ".runelite/credentials.properties";
+
+/// The credentials file of `character_id`, inside its isolated home.
+pub fn credentials_path<R: Runtime>(app_handle: &AppHandle<R>, character_id: &str) -> error::Result<PathBuf> {
+    Ok(character_data::data_dir(app_handle, character_id)?.join(RUNELITE_CREDENTIALS))
+}
+
+/// `values` in Java properties format, escaped so `Properties.load` reads them back unchanged.
+pub fn properties(values: &BTreeMap<String, String>) -> String {
+    fn escape(value: &str, key: bool) -> String {
+        let mut escaped = String::new();
+        for (index, c) in value.chars().enumerate() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                '=' | ':' | '#' | '!' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                ' ' if key || index == 0 => escaped.push_str("\\ "),
+                c if !c.is_ascii() || c.is_ascii_control() => {
+                    let mut units = [0u16; 2];
+                    for unit in c.encode_utf16(&mut units) {
+                        escaped.push_str(&format!("\\u{:04X}", unit));
+                    }
+                }
+                c => escaped.push(c)
+            }
+        }
+        escaped
+    }
+
+    let mut contents = "# Written by RuneLauncher. Removed again when the game session ends.\n".to_string();
+    for (key, value) in values {
+        contents.push_str(&format!("{}={}\n", escape(key, true), escape(value, false)));
+    }
+    contents
+}
+
+/// Replaces `path` with `contents`, readable by the current user only. The file is written next to
+/// `path` and renamed over it, so it never exists with wider permissions or half written.
+pub fn write_private(path: &Path, contents: &[u8]) -> error::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let staging = dir.join(format!(".credentials-{}", Uuid::new_v4().simple()));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let result = options.open(&staging)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&staging, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    Ok(result?)
+}
+
+/// Writes the `JX_*` session variables of a character where RuneLite picks them up.
+pub fn write_credentials<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    character_id: &str,
+    session_env: &BTreeMap<String, String>
+) -> error::Result<PathBuf> {
+    let path = credentials_path(app_handle, character_id)?;
+    write_private(&path, properties(session_env).as_bytes())?;
+    log::info!("Wrote RuneLite credentials of character {} to {}.", character_id, path.display());
+    Ok(path)
+}
+
+/// Deletes the credentials file of a character. Returns false if there was none.
+pub fn remove_credentials<R: Runtime>(app_handle: &AppHandle<R>, character_id: &str) -> error::Result<bool> {
+    match fs::remove_file(credentials_path(app_handle, character_id)?) {
+        Ok(()) => {
+            log::info!("Removed RuneLite credentials of character {}.", character_id);
+            Ok(true)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// Deletes the credentials files of every character of an account, once its game session is no longer valid.
+pub fn revoke_account<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) {
+    let characters = match accounts::get_account(app_handle, account_id) {
+        Ok(account) => account.characters,
+        Err(e) => {
+            log::error!("Unable to list characters of account {} to remove their credentials: {}", account_id, e);
+            return;
+        }
+    };
+    for character in characters {
+        if let Err(e) = remove_credentials(app_handle, &character.account_id) {
+            log::error!("Failed to remove RuneLite credentials of character {}: {}", character.account_id, e);
+        }
+    }
+}
+
+/// Replaces the credentials files of the characters of an account that have one with its new game
+/// session, so none of them keeps a session that was just replaced.
+pub fn renew_account<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, session: &GameSession) {
+    let characters = match accounts::get_account(app_handle, account_id) {
+        Ok(account) => account.characters,
+        Err(e) => {
+            log::error!("Unable to list characters of account {} to renew their credentials: {}", account_id, e);
+            return;
+        }
+    };
+    for character in characters {
+        let exists = credentials_path(app_handle, &character.account_id).is_ok_and(|path| path.exists());
+        if !exists {
+            continue;
+        }
+        if let Err(e) = write_credentials(app_handle, &character.account_id, &launch::session_env(session, &character)) {
+            log::error!("Failed to renew RuneLite credentials of character {}: {}", character.account_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
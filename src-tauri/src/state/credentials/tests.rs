@@ -0,0 +1,78 @@
+use crate::state::auth::GameCharacter;
+use crate::test_support::mock_app::MockApp;
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+fn session_env() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("JX_CHARACTER_ID".to_string(), "1001".to_string()),
+        ("JX_SESSION_ID".to_string(), "mock-session-id".to_string()),
+        ("JX_DISPLAY_NAME".to_string(), "Mock Hero".to_string())
+    ])
+}
+
+#[test]
+fn credentials_are_written_as_properties() {
+    let contents = properties(&session_env());
+    let lines = contents.lines().filter(|line| !line.starts_with('#')).collect::<Vec<_>>();
+    assert_eq!(lines, vec!["JX_CHARACTER_ID=1001", "JX_DISPLAY_NAME=Mock Hero", "JX_SESSION_ID=mock-session-id"]);
+}
+
+#[test]
+fn special_characters_are_escaped() {
+    let values = BTreeMap::from([("key with=sep".to_string(), " lead:\\\nñ".to_string())]);
+    assert_eq!(properties(&values).lines().last().unwrap(), "key\\ with\\=sep=\\ lead\\:\\\\\\n\\u00F1");
+}
+
+#[test]
+fn file_is_private_and_replaced_whole() {
+    let dir = TempDir::new("credentials");
+    let path = dir.join(RUNELITE_CREDENTIALS);
+
+    write_private(&path, b"first").unwrap();
+    write_private(&path, b"second").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"second");
+    // Only the file itself is left, no staging files.
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
+
+fn game_session(session_id: &str) -> GameSession {
+    GameSession {
+        code: "code".to_string(),
+        id_token: "id-token".to_string(),
+        state: "state".to_string(),
+        session_id: session_id.to_string()
+    }
+}
+
+fn character(account_id: &str) -> GameCharacter {
+    GameCharacter {
+        account_id: account_id.to_string(),
+        display_name: format!("Hero {}", account_id),
+        user_hash: format!("hash-{}", account_id),
+        is_members: false,
+        levels: None
+    }
+}
+
+#[test]
+fn renewing_an_account_replaces_the_session_of_written_credentials() {
+    let app = MockApp::new();
+    let app_handle = app.handle();
+    app.save_account("account-1", vec![character("1001"), character("1002")], game_session("old-session"));
+    let old = launch::session_env(&game_session("old-session"), &character("1001"));
+    write_credentials(app_handle, "1001", &old).unwrap();
+
+    renew_account(app_handle, "account-1", &game_session("new-session"));
+
+    let contents = fs::read_to_string(credentials_path(app_handle, "1001").unwrap()).unwrap();
+    assert!(contents.lines().any(|line| line == "JX_SESSION_ID=new-session"));
+    assert!(!contents.contains("old-session"));
+    // Characters without credentials do not get any.
+    assert!(!credentials_path(app_handle, "1002").unwrap().exists());
+}
@@ -0,0 +1,118 @@
+use std::cmp::Ordering;
+use crate::error;
+use crate::error::Error;
+use crate::state::archive;
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER_LEN: usize = 60;
+
+/// One stanza of an apt `Packages` index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageEntry {
+    pub package: String,
+    pub version: String,
+    /// Path of the .deb relative to the repository root.
+    pub filename: String,
+    pub size: Option<u64>,
+    pub sha256: String
+}
+
+/// Parses a `Packages` index, skipping stanzas that lack what we need to download and verify a package.
+pub fn parse_packages(index: &str) -> Vec<PackageEntry> {
+    index.split("\n\n")
+        .filter_map(|stanza| {
+            let field = |name: &str| stanza.lines()
+                .filter(|line| !line.starts_with([' ', '\t']))
+                .find_map(|line| line.split_once(':').filter(|(key, _)| key.eq_ignore_ascii_case(name)))
+                .map(|(_, value)| value.trim().to_string());
+
+            Some(PackageEntry {
+                package: field("Package")?,
+                version: field("Version")?,
+                filename: field("Filename")?,
+                size: field("Size").and_then(|size| size.parse().ok()),
+                sha256: field("SHA256")?
+            })
+        })
+        .collect()
+}
+
+/// The newest version of `package` listed in `entries`.
+pub fn newest<'a>(entries: &'a [PackageEntry], package: &str) -> Option<&'a PackageEntry> {
+    entries.iter()
+        .filter(|entry| entry.package == package)
+        .max_by(|a, b| compare_versions(&a.version, &b.version))
+}
+
+/// Orders Debian versions by comparing their numeric and non-numeric runs in turn.
+///
+/// Close enough to dpkg for the plain `1.2.3-4` style versions Jagex publishes; epochs and `~` are not handled.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn runs(version: &str) -> Vec<&str> {
+        let bytes = version.as_bytes();
+        let mut runs = Vec::new();
+        let mut start = 0;
+        for index in 1..bytes.len() {
+            if bytes[index].is_ascii_digit() != bytes[index - 1].is_ascii_digit() {
+                runs.push(&version[start..index]);
+                start = index;
+            }
+        }
+        if !version.is_empty() {
+            runs.push(&version[start..]);
+        }
+        runs
+    }
+
+    let (a, b) = (runs(a), runs(b));
+    let numeric = |run: &str| run.starts_with(|c: char| c.is_ascii_digit());
+    for (a_run, b_run) in a.iter().zip(b.iter()) {
+        let ordering = if numeric(a_run) && numeric(b_run) {
+            a_run.trim_start_matches('0').len().cmp(&b_run.trim_start_matches('0').len())
+                .then_with(|| a_run.trim_start_matches('0').cmp(b_run.trim_start_matches('0')))
+        } else {
+            a_run.cmp(b_run)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// The members of an `ar` archive, which is what a .deb is.
+pub fn ar_members(archive: &[u8]) -> error::Result<Vec<(String, &[u8])>> {
+    let mut rest = archive.strip_prefix(AR_MAGIC)
+        .ok_or_else(|| Error::InvalidPackage("Not an ar archive".to_string()))?;
+
+    let mut members = Vec::new();
+    while rest.len() >= AR_HEADER_LEN {
+        let header = &rest[..AR_HEADER_LEN];
+        if &header[58..60] != b"`\n" {
+            return Err(Error::InvalidPackage("Corrupt ar member header".to_string()));
+        }
+        let name = String::from_utf8_lossy(&header[..16]).trim_end().trim_end_matches('/').to_string();
+        let size = String::from_utf8_lossy(&header[48..58]).trim().parse::<usize>()
+            .map_err(|_| Error::InvalidPackage(format!("Invalid size of ar member {}", name)))?;
+
+        let data = rest.get(AR_HEADER_LEN..AR_HEADER_LEN + size)
+            .ok_or_else(|| Error::InvalidPackage(format!("Truncated ar member {}", name)))?;
+        members.push((name, data));
+
+        // Members are padded to an even length.
+        let next = AR_HEADER_LEN + size + size % 2;
+        rest = rest.get(next..).unwrap_or_default();
+    }
+    Ok(members)
+}
+
+/// The uncompressed `data.tar` of a .deb.
+pub fn data_tar(deb: &[u8]) -> error::Result<Vec<u8>> {
+    let (name, data) = ar_members(deb)?.into_iter()
+        .find(|(name, _)| name.starts_with("data.tar"))
+        .ok_or_else(|| Error::InvalidPackage("Package has no data archive".to_string()))?;
+    archive::decompress(&name, data)
+}
+
+#[cfg(test)]
+mod tests;
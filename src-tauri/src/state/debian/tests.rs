@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+use crate::state::archive::extract_tar;
+use crate::test_support::debian_package::{ar, deb, dir, file};
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+const PACKAGES: &str = "\
+Package: runescape-launcher
+Version: 2.2.9
+Architecture: amd64
+Filename: pool/non-free/r/runescape-launcher/runescape-launcher_2.2.9_amd64.deb
+Size: 1234
+SHA256: aaaa
+Description: RuneScape Game Client
+ Multi-line description, with a
+ Filename: that is not a field
+
+Package: runescape-launcher
+Version: 2.2.11
+Architecture: amd64
+Filename: pool/non-free/r/runescape-launcher/runescape-launcher_2.2.11_amd64.deb
+Size: 5678
+SHA256: bbbb
+
+Package: broken-entry
+Version: 1.0
+";
+
+#[test]
+fn packages_index_is_parsed() {
+    let entries = parse_packages(PACKAGES);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0], PackageEntry {
+        package: "runescape-launcher".to_string(),
+        version: "2.2.9".to_string(),
+        filename: "pool/non-free/r/runescape-launcher/runescape-launcher_2.2.9_amd64.deb".to_string(),
+        size: Some(1234),
+        sha256: "aaaa".to_string()
+    });
+
+    let newest = newest(&entries, "runescape-launcher").unwrap();
+    assert_eq!(newest.version, "2.2.11");
+    assert_eq!(newest.sha256, "bbbb");
+}
+
+#[test]
+fn versions_compare_numerically() {
+    assert_eq!(compare_versions("2.2.11", "2.2.9"), Ordering::Greater);
+    assert_eq!(compare_versions("2.2.9", "2.2.9"), Ordering::Equal);
+    assert_eq!(compare_versions("2.2.9-1", "2.2.9"), Ordering::Greater);
+    assert_eq!(compare_versions("2.10", "2.009"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0a", "1.0b"), Ordering::Less);
+}
+
+#[test]
+fn client_is_extracted_from_package() {
+    let package = deb(&[
+        dir("./usr/"),
+        file("./usr/bin/runescape-launcher", b"#!/bin/sh\n", 0o755),
+        dir("./usr/share/games/runescape-launcher/"),
+        file("./usr/share/games/runescape-launcher/runescape", b"\x7fELF client", 0o755),
+        file("./usr/share/games/runescape-launcher/lib/libclient.so", b"\x7fELF lib", 0o644)
+    ]);
+    let target = TempDir::new("deb");
+
+    let data = data_tar(&package).unwrap();
+    let mut extracted = extract_tar(data.as_slice(), "./usr/share/games/runescape-launcher", &target).unwrap();
+    extracted.sort();
+
+    assert_eq!(extracted, vec![PathBuf::from("lib/libclient.so"), PathBuf::from("runescape")]);
+    assert_eq!(fs::read(target.join("runescape")).unwrap(), b"\x7fELF client");
+    assert!(!target.join("runescape-launcher").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(target.join("runescape")).unwrap().permissions().mode() & 0o777, 0o755);
+        assert_eq!(fs::metadata(target.join("lib/libclient.so")).unwrap().permissions().mode() & 0o777, 0o644);
+    }
+}
+
+#[test]
+fn malformed_packages_are_rejected() {
+    assert!(matches!(data_tar(b"not a deb"), Err(Error::InvalidPackage(_))));
+    assert!(matches!(data_tar(&ar(&[("debian-binary", b"2.0\n")])), Err(Error::InvalidPackage(_))));
+    assert!(matches!(data_tar(&ar(&[("data.tar.lz4", b"????")])), Err(Error::InvalidPackage(_))));
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::error;
+use crate::error::Error;
+
+/// Points at a JSON file that replaces the default endpoints, e.g. a local stand-in identity server.
+pub const AUTH_ENDPOINTS_ENV: &str = "RUNELAUNCHER_AUTH_ENDPOINTS";
+const AUTH_ENDPOINTS_FILE: &str = "auth_endpoints.json";
+
+/// Every URL and client id used while talking to the Jagex identity provider and game APIs.
+///
+/// Missing fields in a config file fall back to the production Jagex values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthEndpoints {
+    pub oidc_discovery_url: String,
+    pub launcher_client_id: String,
+    pub launcher_redirect_uri: String,
+    pub game_session_client_id: String,
+    pub game_session_redirect_uri: String,
+    pub auth_api_url: String,
+    pub api_url: String,
+    /// Serves the OSRS and RS3 hiscores below `/m=hiscore_oldschool` and `/m=hiscore`.
+    pub hiscores_url: String
+}
+
+impl Default for AuthEndpoints {
+    fn default() -> Self {
+        Self {
+            oidc_discovery_url: "https://account.jagex.com/.well-known/openid-configuration".to_string(),
+            launcher_client_id: "com_jagex_auth_desktop_launcher".to_string(),
+            launcher_redirect_uri: "https://secure.runescape.com/m=weblogin/launcher-redirect".to_string(),
+            game_session_client_id: "1fddee4e-b100-4f4e-b2b0-097f9088f9d2".to_string(),
+            game_session_redirect_uri: "http://localhost".to_string(),
+            auth_api_url: "https://auth.jagex.com".to_string(),
+            api_url: "https://api.jagex.com".to_string(),
+            hiscores_url: "https://secure.runescape.com".to_string()
+        }
+    }
+}
+
+impl AuthEndpoints {
+    /// Loads the endpoints from `$RUNELAUNCHER_AUTH_ENDPOINTS`, then `<app config dir>/auth_endpoints.json`,
+    /// and falls back to the production defaults when neither exists.
+    pub fn load<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Self> {
+        let path = match std::env::var_os(AUTH_ENDPOINTS_ENV) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => {
+                let path = app_handle.path().app_config_dir()?.join(AUTH_ENDPOINTS_FILE);
+                path.exists().then_some(path)
+            }
+        };
+
+        match path {
+            Some(path) => {
+                log::info!("Loading auth endpoints from {}", path.display());
+                Self::from_file(&path)
+            }
+            None => Ok(Self::default())
+        }
+    }
+
+    pub fn from_file(path: &PathBuf) -> error::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str::<Self>(&contents).map_err(Error::local_state)
+    }
+
+    pub fn game_sessions_url(&self) -> String {
+        format!("{}/game-session/v1/sessions", self.auth_api_url.trim_end_matches('/'))
+    }
+
+    pub fn game_accounts_url(&self) -> String {
+        format!("{}/game-session/v1/accounts", self.auth_api_url.trim_end_matches('/'))
+    }
+
+    pub fn display_name_url(&self, sub: &str) -> String {
+        format!("{}/v1/users/{}/displayName", self.api_url.trim_end_matches('/'), sub)
+    }
+
+    /// The JSON lite hiscores of OSRS. The player goes in the `player` query parameter.
+    pub fn osrs_hiscores_url(&self) -> String {
+        format!("{}/m=hiscore_oldschool/index_lite.json", self.hiscores_url.trim_end_matches('/'))
+    }
+
+    /// The CSV lite hiscores of RS3, which have no JSON variant.
+    pub fn rs3_hiscores_url(&self) -> String {
+        format!("{}/m=hiscore/index_lite.ws", self.hiscores_url.trim_end_matches('/'))
+    }
+}
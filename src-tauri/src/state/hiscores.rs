@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri::async_runtime::JoinHandle;
+use crate::error;
+use crate::error::{Error, JagexApi};
+use crate::state::launch_profiles;
+use crate::state::auth::GameCharacter;
+use crate::state::endpoints::AuthEndpoints;
+use crate::state::launch::ClientType;
+
+/// How long a lookup is answered from the cache. The hiscores only update as players log out.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Skills in the order the CSV lite endpoints list them.
+const OSRS_SKILLS: [&str; 25] = [
+    "Overall", "Attack", "Defence", "Strength", "Hitpoints", "Ranged", "Prayer", "Magic", "Cooking",
+    "Woodcutting", "Fletching", "Fishing", "Firemaking", "Crafting", "Smithing", "Mining", "Herblore",
+    "Agility", "Thieving", "Slayer", "Farming", "Runecraft", "Hunter", "Construction", "Sailing"
+];
+const RS3_SKILLS: [&str; 30] = [
+    "Overall", "Attack", "Defence", "Strength", "Constitution", "Ranged", "Prayer", "Magic", "Cooking",
+    "Woodcutting", "Fletching", "Fishing", "Firemaking", "Crafting", "Smithing", "Mining", "Herblore",
+    "Agility", "Thieving", "Slayer", "Farming", "Runecrafting", "Hunter", "Construction", "Summoning",
+    "Dungeoneering", "Divination", "Invention", "Archaeology", "Necromancy"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Game {
+    Osrs,
+    Rs3
+}
+
+impl Game {
+    fn skill_names(&self) -> &'static [&'static str] {
+        match self {
+            Game::Osrs => &OSRS_SKILLS,
+            Game::Rs3 => &RS3_SKILLS
+        }
+    }
+
+    /// Hitpoints and Constitution start at 10 rather than 1.
+    fn base_level(&self, skill: &str) -> u32 {
+        match skill {
+            "Hitpoints" | "Constitution" => 10,
+            _ => 1
+        }
+    }
+}
+
+/// A skill of a player. `rank` and `xp` are `None` when the player is not ranked in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Skill {
+    pub id: usize,
+    pub name: String,
+    pub rank: Option<u64>,
+    pub level: u32,
+    pub xp: Option<u64>
+}
+
+/// A minigame, boss or clue score. The CSV endpoints do not name activities.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Activity {
+    pub id: usize,
+    pub name: Option<String>,
+    pub rank: Option<u64>,
+    pub score: Option<u64>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hiscore {
+    pub game: Game,
+    pub player: String,
+    pub skills: Vec<Skill>,
+    pub activities: Vec<Activity>
+}
+
+/// What the account list shows of a character's hiscores.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CharacterLevels {
+    pub game: Game,
+    pub total_level: u32,
+    pub combat_level: u32
+}
+
+/// The hiscores use -1 for "not ranked".
+fn ranked(value: i64) -> Option<u64> {
+    u64::try_from(value).ok()
+}
+
+impl Hiscore {
+    /// The level of `name`, or the level every player starts with if the player is not ranked in it.
+    pub fn level(&self, name: &str) -> u32 {
+        self.skills.iter()
+            .find(|skill| skill.name == name)
+            .map(|skill| skill.level)
+            .filter(|level| *level > 0)
+            .unwrap_or_else(|| self.game.base_level(name))
+    }
+
+    /// The Overall level, or the sum of the skills when the player is not ranked overall.
+    pub fn total_level(&self) -> u32 {
+        match self.skills.iter().find(|skill| skill.name == "Overall") {
+            Some(overall) if overall.rank.is_some() && overall.level > 0 => overall.level,
+            _ => self.game.skill_names()[1..].iter().map(|name| self.level(name)).sum()
+        }
+    }
+
+    pub fn combat_level(&self) -> u32 {
+        match self.game {
+            Game::Osrs => {
+                let base = (self.level("Defence") + self.level("Hitpoints") + self.level("Prayer") / 2) as f64 / 4.0;
+                let melee = (self.level("Attack") + self.level("Strength")) as f64 * 0.325;
+                let ranged = (self.level("Ranged") * 3 / 2) as f64 * 0.325;
+                let magic = (self.level("Magic") * 3 / 2) as f64 * 0.325;
+                (base + melee.max(ranged).max(magic)).floor() as u32
+            }
+            Game::Rs3 => {
+                let offence = [
+                    self.level("Attack") + self.level("Strength"),
+                    2 * self.level("Magic"),
+                    2 * self.level("Ranged"),
+                    2 * self.level("Necromancy")
+                ].into_iter().max().unwrap_or_default();
+                let defence = self.level("Defence") + self.level("Constitution")
+                    + self.level("Prayer") / 2 + self.level("Summoning") / 2;
+                ((offence as f64 * 1.3 + defence as f64) / 4.0).floor() as u32
+            }
+        }
+    }
+
+    pub fn levels(&self) -> CharacterLevels {
+        CharacterLevels {
+            game: self.game,
+            total_level: self.total_level(),
+            combat_level: self.combat_level()
+        }
+    }
+}
+
+fn unexpected(reason: String) -> Error {
+    Error::UnexpectedResponse { api: JagexApi::Hiscores, reason }
+}
+
+/// Parses an `index_lite.ws` response: a `rank,level,xp` line per skill followed by a
+/// `rank,score` line per activity.
+pub fn parse_csv(game: Game, player: &str, body: &str) -> error::Result<Hiscore> {
+    let mut skills = Vec::new();
+    let mut activities = Vec::new();
+    for (line_number, line) in body.lines().map(str::trim).filter(|line| !line.is_empty()).enumerate() {
+        let fields = line.split(',')
+            .map(|field| field.trim().parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| unexpected(format!("Line {} is not a list of numbers: {}", line_number + 1, line)))?;
+
+        match fields.as_slice() {
+            [rank, level, xp] if activities.is_empty() => {
+                let id = skills.len();
+                skills.push(Skill {
+                    id,
+                    name: game.skill_names().get(id).map(|name| name.to_string()).unwrap_or_else(|| format!("Skill {}", id)),
+                    rank: ranked(*rank),
+                    level: u32::try_from(*level).unwrap_or_default(),
+                    xp: ranked(*xp)
+                });
+            }
+            [rank, score] => activities.push(Activity {
+                id: activities.len(),
+                name: None,
+                rank: ranked(*rank),
+                score: ranked(*score)
+            }),
+            _ => return Err(unexpected(format!("Line {} has {} fields", line_number + 1, fields.len())))
+        }
+    }
+
+    if skills.is_empty() {
+        return Err(unexpected("No skills in the response".to_string()));
+    }
+    Ok(Hiscore { game, player: player.to_string(), skills, activities })
+}
+
+#[derive(Deserialize)]
+struct JsonHiscore {
+    skills: Vec<JsonSkill>,
+    #[serde(default)]
+    activities: Vec<JsonActivity>
+}
+
+#[derive(Deserialize)]
+struct JsonSkill {
+    id: usize,
+    name: String,
+    rank: i64,
+    level: i64,
+    xp: i64
+}
+
+#[derive(Deserialize)]
+struct JsonActivity {
+    id: usize,
+    name: String,
+    rank: i64,
+    score: i64
+}
+
+/// Parses an `index_lite.json` response, which names its skills and activities.
+pub fn parse_json(game: Game, player: &str, body: &str) -> error::Result<Hiscore> {
+    let json = serde_json::from_str::<JsonHiscore>(body)
+        .map_err(|e| unexpected(format!("Invalid hiscores JSON: {}", e)))?;
+    Ok(Hiscore {
+        game,
+        player: player.to_string(),
+        skills: json.skills.into_iter().map(|skill| Skill {
+            id: skill.id,
+            name: skill.name,
+            rank: ranked(skill.rank),
+            level: u32::try_from(skill.level).unwrap_or_default(),
+            xp: ranked(skill.xp)
+        }).collect(),
+        activities: json.activities.into_iter().map(|activity| Activity {
+            id: activity.id,
+            name: Some(activity.name),
+            rank: ranked(activity.rank),
+            score: ranked(activity.score)
+        }).collect()
+    })
+}
+
+/// Looks `player` up on the hiscores of `game`, uncached.
+pub async fn fetch(endpoints: &AuthEndpoints, game: Game, player: &str) -> error::Result<Hiscore> {
+    let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().timeout(REQUEST_TIMEOUT).build()?;
+    let url = match game {
+        Game::Osrs => endpoints.osrs_hiscores_url(),
+        Game::Rs3 => endpoints.rs3_hiscores_url()
+    };
+
+    let response = http_client.get(url).query(&[("player", player)]).send().await?;
+    let status = response.status().as_u16();
+    if status == 404 {
+        return Err(Error::HiscoresNotFound(player.to_string()));
+    }
+    if !response.status().is_success() {
+        let error = Error::JagexApi { api: JagexApi::Hiscores, status, error: None };
+        log::error!("{}", error);
+        return Err(error);
+    }
+
+    let body = response.text().await?;
+    match game {
+        Game::Osrs => parse_json(game, player, &body),
+        Game::Rs3 => parse_csv(game, player, &body)
+    }
+}
+
+/// Recent lookups, unranked players included, so opening the account list does not query the
+/// hiscores every time.
+#[derive(Default)]
+pub struct HiscoresCache {
+    entries: Mutex<HashMap<(Game, String), CacheEntry>>
+}
+
+/// When a player was looked up, and what was found.
+type CacheEntry = (Instant, Option<Hiscore>);
+
+impl HiscoresCache {
+    /// `Some(None)` for a player that was recently found not to be ranked.
+    pub fn get(&self, game: Game, player: &str, now: Instant) -> Option<Option<Hiscore>> {
+        let entries = self.entries.lock().ok()?;
+        entries.get(&(game, player.to_lowercase()))
+            .filter(|(fetched_at, _)| now.duration_since(*fetched_at) < CACHE_TTL)
+            .map(|(_, hiscore)| hiscore.clone())
+    }
+
+    pub fn insert(&self, game: Game, player: &str, hiscore: Option<Hiscore>, now: Instant) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, (fetched_at, _)| now.duration_since(*fetched_at) < CACHE_TTL);
+            entries.insert((game, player.to_lowercase()), (now, hiscore));
+        }
+    }
+}
+
+/// Looks `player` up, answering from the cache while it is fresh.
+pub async fn lookup<R: Runtime>(app_handle: &AppHandle<R>, game: Game, player: &str) -> error::Result<Hiscore> {
+    let cache = app_handle.state::<HiscoresCache>();
+    if let Some(hiscore) = cache.get(game, player, Instant::now()) {
+        return hiscore.ok_or_else(|| Error::HiscoresNotFound(player.to_string()));
+    }
+
+    let endpoints = app_handle.state::<AuthEndpoints>();
+    match fetch(&endpoints, game, player).await {
+        Ok(hiscore) => {
+            cache.insert(game, player, Some(hiscore.clone()), Instant::now());
+            Ok(hiscore)
+        }
+        Err(Error::HiscoresNotFound(player)) => {
+            cache.insert(game, &player, None, Instant::now());
+            Err(Error::HiscoresNotFound(player))
+        }
+        Err(e) => Err(e)
+    }
+}
+
+/// The game whose hiscores a character is looked up on: RS3 if it launches the RS3 client,
+/// otherwise OSRS.
+fn game_of<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, character_id: &str) -> Game {
+    match launch_profiles::effective_profile(app_handle, account_id, character_id) {
+        Ok(profile) if profile.client == Some(ClientType::Rs3) => Game::Rs3,
+        _ => Game::Osrs
+    }
+}
+
+/// Fills in the levels of every character that has a display name. Characters that cannot be
+/// looked up keep `levels` unset; the account list is shown either way.
+pub async fn attach_levels<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, characters: &mut [GameCharacter]) {
+    let lookups = characters.iter()
+        .map(|character| {
+            (!character.display_name.is_empty()).then(|| {
+                let app_handle = app_handle.clone();
+                let game = game_of(&app_handle, account_id, &character.account_id);
+                let player = character.display_name.clone();
+                tauri::async_runtime::spawn(async move { lookup(&app_handle, game, &player).await })
+            })
+        })
+        .collect::<Vec<Option<JoinHandle<error::Result<Hiscore>>>>>();
+
+    for (character, lookup) in characters.iter_mut().zip(lookups) {
+        let Some(lookup) = lookup else {
+            continue;
+        };
+        character.levels = match lookup.await {
+            Ok(Ok(hiscore)) => Some(hiscore.levels()),
+            Ok(Err(Error::HiscoresNotFound(_))) => None,
+            Ok(Err(e)) => {
+                log::error!("Failed to look up {} on the hiscores: {}", character.display_name, e);
+                None
+            }
+            Err(e) => {
+                log::error!("Hiscores lookup of {} failed: {}", character.display_name, e);
+                None
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,112 @@
+use super::*;
+use crate::test_support::mock_downloads::MockDownloads;
+
+/// A maxed OSRS account in `index_lite.ws` format, with two activities.
+fn maxed_osrs_csv() -> String {
+    let mut lines = vec!["1000,2277,4600000000".to_string()];
+    lines.extend((1..OSRS_SKILLS.len()).map(|_| "5000,99,13034431".to_string()));
+    lines.extend(["-1,-1".to_string(), "250,1200".to_string()]);
+    lines.join("\n")
+}
+
+fn skill_levels(game: Game, levels: &[(&str, u32)]) -> Hiscore {
+    Hiscore {
+        game,
+        player: "Zezima".to_string(),
+        skills: levels.iter().enumerate().map(|(id, (name, level))| Skill {
+            id,
+            name: name.to_string(),
+            rank: Some(1),
+            level: *level,
+            xp: Some(0)
+        }).collect(),
+        activities: Vec::new()
+    }
+}
+
+#[test]
+fn parses_csv_skills_and_activities() {
+    let hiscore = parse_csv(Game::Osrs, "Zezima", &maxed_osrs_csv()).unwrap();
+
+    assert_eq!(hiscore.skills.len(), OSRS_SKILLS.len());
+    assert_eq!(hiscore.skills[4], Skill { id: 4, name: "Hitpoints".to_string(), rank: Some(5000), level: 99, xp: Some(13_034_431) });
+    assert_eq!(hiscore.activities, [
+        Activity { id: 0, name: None, rank: None, score: None },
+        Activity { id: 1, name: None, rank: Some(250), score: Some(1200) }
+    ]);
+    assert_eq!(hiscore.levels(), CharacterLevels { game: Game::Osrs, total_level: 2277, combat_level: 126 });
+}
+
+#[test]
+fn rejects_malformed_csv() {
+    assert!(matches!(parse_csv(Game::Rs3, "Zezima", "<html>"), Err(Error::UnexpectedResponse { api: JagexApi::Hiscores, .. })));
+    assert!(matches!(parse_csv(Game::Rs3, "Zezima", "1,2,3,4"), Err(Error::UnexpectedResponse { .. })));
+    assert!(matches!(parse_csv(Game::Rs3, "Zezima", ""), Err(Error::UnexpectedResponse { .. })));
+}
+
+#[test]
+fn parses_json_with_names() {
+    let body = r#"{
+        "skills": [
+            {"id": 0, "name": "Overall", "rank": -1, "level": 33, "xp": -1},
+            {"id": 1, "name": "Attack", "rank": 1200000, "level": 10, "xp": 1154}
+        ],
+        "activities": [{"id": 0, "name": "Clue Scrolls (all)", "rank": 9001, "score": 3}]
+    }"#;
+    let hiscore = parse_json(Game::Osrs, "Zezima", body).unwrap();
+
+    assert_eq!(hiscore.skills[0].rank, None);
+    assert_eq!(hiscore.skills[0].xp, None);
+    assert_eq!(hiscore.skills[1].level, 10);
+    assert_eq!(hiscore.activities[0].name.as_deref(), Some("Clue Scrolls (all)"));
+}
+
+#[test]
+fn unranked_skills_count_at_their_starting_level() {
+    // Nothing but Attack is ranked: the rest are at 1, Hitpoints at 10.
+    let hiscore = skill_levels(Game::Osrs, &[("Attack", 40)]);
+
+    assert_eq!(hiscore.level("Hitpoints"), 10);
+    assert_eq!(hiscore.total_level(), 40 + 10 + (OSRS_SKILLS.len() as u32 - 3));
+    assert_eq!(hiscore.combat_level(), 3 + 13);
+}
+
+#[test]
+fn combat_levels_follow_each_game() {
+    assert_eq!(skill_levels(Game::Osrs, &[]).combat_level(), 3);
+    assert_eq!(skill_levels(Game::Osrs, &[("Magic", 99), ("Defence", 1), ("Hitpoints", 10)]).combat_level(), 50);
+
+    assert_eq!(skill_levels(Game::Rs3, &[]).combat_level(), 3);
+    let maxed = RS3_SKILLS.iter().map(|name| (*name, 99)).collect::<Vec<_>>();
+    assert_eq!(skill_levels(Game::Rs3, &maxed).combat_level(), 138);
+}
+
+#[test]
+fn cache_expires_after_ttl() {
+    let cache = HiscoresCache::default();
+    let now = Instant::now();
+    let hiscore = skill_levels(Game::Osrs, &[("Attack", 40)]);
+    cache.insert(Game::Osrs, "Zezima", Some(hiscore.clone()), now);
+    cache.insert(Game::Rs3, "Zezima", None, now);
+
+    // Names are looked up case-insensitively, like the hiscores do.
+    assert_eq!(cache.get(Game::Osrs, "zezima", now), Some(Some(hiscore)));
+    assert_eq!(cache.get(Game::Rs3, "Zezima", now), Some(None));
+    assert_eq!(cache.get(Game::Osrs, "Zezima", now + CACHE_TTL), None);
+}
+
+#[tokio::test]
+async fn fetches_from_configured_base_url() {
+    let server = MockDownloads::start().await;
+    let endpoints = AuthEndpoints {
+        hiscores_url: server.base_url.clone(),
+        ..AuthEndpoints::default()
+    };
+    server.serve("m=hiscore/index_lite.ws", maxed_osrs_csv().as_bytes());
+
+    let hiscore = fetch(&endpoints, Game::Rs3, "Zezima").await.unwrap();
+    assert_eq!(hiscore.skills[4].name, "Constitution");
+
+    // Players that are not ranked at all get a 404.
+    assert!(matches!(fetch(&endpoints, Game::Osrs, "Zezima").await, Err(Error::HiscoresNotFound(_))));
+}
@@ -0,0 +1,560 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+use uuid::Uuid;
+use crate::error;
+use crate::error::Error;
+use crate::state::{accounts, archive, debian};
+use crate::state::launch::{ClientConfig, SETTINGS_STORE};
+
+/// Replaces the manifest URL of the RuneLite source, e.g. with a local stand-in server.
+pub const RUNELITE_MANIFEST_ENV: &str = "RUNELAUNCHER_RUNELITE_MANIFEST";
+const RUNELITE_SOURCE_KEY: &str = "runelite";
+const CLIENTS_DIR: &str = "clients";
+const RUNELITE_CLIENT: &str = "runelite";
+/// What the RuneLite launcher starts once it has put the client and its libraries on the classpath.
+pub const RUNELITE_MAIN_CLASS: &str = "net.runelite.client.RuneLite";
+const INSTALL_RECORD: &str = "installed.json";
+/// Replaces the repository URL of the RS3 source, e.g. with a local stand-in server.
+pub const RS3_REPOSITORY_ENV: &str = "RUNELAUNCHER_RS3_REPOSITORY";
+const RS3_SOURCE_KEY: &str = "rs3";
+const RS3_CLIENT: &str = "rs3";
+/// Where the client lives inside the `runescape-launcher` package.
+const RS3_PACKAGE_DIR: &str = "usr/share/games/runescape-launcher";
+const RS3_BINARY: &str = "runescape";
+/// What the package's `/usr/bin/runescape-launcher` script starts the client with.
+const RS3_CONFIG_URI: &str = "https://www.runescape.com/k=5/l=0/jav_config.ws";
+/// Progress is reported at most once per this many downloaded bytes.
+const PROGRESS_STEP: u64 = 256 * 1024;
+
+/// Where a client is installed from: a bootstrap manifest and, optionally, the one artifact of it to install.
+/// Without one, every artifact the manifest lists for this platform is installed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientSource {
+    pub manifest_url: String,
+    /// Name of the artifact to install. Every artifact of the manifest is installed when unset.
+    pub artifact: Option<String>
+}
+
+impl ClientSource {
+    pub fn runelite() -> Self {
+        ClientSource {
+            manifest_url: "https://static.runelite.net/bootstrap.json".to_string(),
+            artifact: None
+        }
+    }
+}
+
+impl Default for ClientSource {
+    fn default() -> Self {
+        Self::runelite()
+    }
+}
+
+/// A package in an apt repository, e.g. the one Jagex publishes the native RS3 Linux client in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AptSource {
+    pub repository_url: String,
+    pub distribution: String,
+    pub component: String,
+    pub architecture: String,
+    pub package: String
+}
+
+impl AptSource {
+    pub fn rs3() -> Self {
+        AptSource {
+            repository_url: "https://content.runescape.com/downloads/ubuntu".to_string(),
+            distribution: "trusty".to_string(),
+            component: "non-free".to_string(),
+            architecture: "amd64".to_string(),
+            package: "runescape-launcher".to_string()
+        }
+    }
+
+    pub fn packages_url(&self) -> String {
+        format!("{}/dists/{}/{}/binary-{}/Packages",
+            self.repository_url.trim_end_matches('/'), self.distribution, self.component, self.architecture)
+    }
+}
+
+impl Default for AptSource {
+    fn default() -> Self {
+        Self::rs3()
+    }
+}
+
+/// A bootstrap manifest in the format RuneLite publishes: the client jar and the libraries it needs.
+/// Fields we do not use are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub artifacts: Vec<Artifact>,
+    #[serde(default)]
+    pub client: Option<ManifestClient>
+}
+
+/// The Maven coordinates of the client jar among the artifacts.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestClient {
+    #[serde(default)]
+    pub version: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Artifact {
+    pub name: String,
+    /// Download URL of the artifact.
+    pub path: String,
+    /// Hex encoded SHA-256 of the artifact.
+    pub hash: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// The platforms the artifact is for, e.g. native libraries. Needed everywhere when unset.
+    #[serde(default)]
+    pub platform: Option<Vec<Platform>>
+}
+
+/// A platform as the RuneLite launcher names it: `os.name` and `os.arch` of the JVM.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Platform {
+    pub name: String,
+    #[serde(default)]
+    pub arch: Option<String>
+}
+
+impl Artifact {
+    /// Whether the artifact is needed on `os` and `arch`, named as in `std::env::consts`.
+    pub fn is_for(&self, (os, arch): (&str, &str)) -> bool {
+        let Some(platforms) = &self.platform else {
+            return true;
+        };
+        // The JVM calls x86_64 `amd64` everywhere but on macOS.
+        let java_arch: &[&str] = match arch {
+            "x86_64" => &["amd64", "x86_64"],
+            "x86" => &["x86", "i386"],
+            arch => &[arch]
+        };
+        platforms.iter().any(|platform| {
+            let name = platform.name.to_lowercase();
+            let platform_os = if name.starts_with("win") {
+                "windows"
+            } else if name.starts_with("mac") || name == "darwin" {
+                "macos"
+            } else {
+                name.as_str()
+            };
+            platform_os == os && platform.arch.as_deref().is_none_or(|platform_arch| java_arch.contains(&platform_arch))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledArtifact {
+    pub name: String,
+    pub hash: String,
+    pub size: u64
+}
+
+/// What is installed in a client directory, recorded next to the artifacts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledClient {
+    pub client: String,
+    pub dir: PathBuf,
+    /// The manifest or package index the client was installed from.
+    pub manifest_url: String,
+    /// Version of the package, for clients installed from a package repository.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Path of the program to start, relative to `dir`, when it is not an artifact itself.
+    #[serde(default)]
+    pub entry_point: Option<String>,
+    pub artifacts: Vec<InstalledArtifact>,
+    pub installed_at: u64
+}
+
+impl InstalledClient {
+    pub fn artifact_path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallProgress {
+    pub client: String,
+    pub artifact: String,
+    /// Index of the artifact being downloaded, out of `artifacts`.
+    pub index: usize,
+    pub artifacts: usize,
+    pub downloaded: u64,
+    pub total: Option<u64>
+}
+
+/// Makes sure only one install runs at a time, as installs replace the client directory.
+#[derive(Default)]
+pub struct ClientInstaller {
+    busy: tokio::sync::Mutex<()>
+}
+
+impl ClientInstaller {
+    /// Claims the installer until the returned guard is dropped.
+    pub fn begin(&self) -> error::Result<tokio::sync::MutexGuard<'_, ()>> {
+        self.busy.try_lock().map_err(|_| Error::InstallInProgress)
+    }
+}
+
+/// The RuneLite source from `$RUNELAUNCHER_RUNELITE_MANIFEST`, the settings store, or the defaults.
+pub fn runelite_source<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<ClientSource> {
+    let mut source = match app_handle.store(SETTINGS_STORE)?.get(RUNELITE_SOURCE_KEY) {
+        Some(value) => serde_json::from_value::<ClientSource>(value).map_err(Error::local_state)?,
+        None => ClientSource::runelite()
+    };
+    if let Ok(manifest_url) = std::env::var(RUNELITE_MANIFEST_ENV) {
+        source.manifest_url = manifest_url;
+    }
+    Ok(source)
+}
+
+/// The RS3 source from `$RUNELAUNCHER_RS3_REPOSITORY`, the settings store, or the defaults.
+pub fn rs3_source<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<AptSource> {
+    let mut source = match app_handle.store(SETTINGS_STORE)?.get(RS3_SOURCE_KEY) {
+        Some(value) => serde_json::from_value::<AptSource>(value).map_err(Error::local_state)?,
+        None => AptSource::rs3()
+    };
+    if let Ok(repository_url) = std::env::var(RS3_REPOSITORY_ENV) {
+        source.repository_url = repository_url;
+    }
+    Ok(source)
+}
+
+pub fn clients_dir<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?.join(CLIENTS_DIR))
+}
+
+/// The RuneLite install, if there is a complete one.
+pub fn installed_runelite<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Option<InstalledClient>> {
+    installed_client(&clients_dir(app_handle)?.join(RUNELITE_CLIENT))
+}
+
+pub fn installed_rs3<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Option<InstalledClient>> {
+    installed_client(&clients_dir(app_handle)?.join(RS3_CLIENT))
+}
+
+/// The jars of the installed RuneLite, in the order the manifest lists them.
+pub fn runelite_classpath(installed: &InstalledClient) -> Vec<PathBuf> {
+    installed.artifacts.iter()
+        .filter(|artifact| artifact.name.ends_with(".jar"))
+        .map(|artifact| installed.artifact_path(&artifact.name))
+        .collect()
+}
+
+/// How an installed RuneLite that is a single runnable jar, such as the launcher, is started before
+/// a Java runtime is picked for it. The client and libraries of a bootstrap manifest are instead
+/// started from `runelite_classpath` with `RUNELITE_MAIN_CLASS`.
+pub fn runelite_client_config(installed: &InstalledClient) -> Option<ClientConfig> {
+    match runelite_classpath(installed).as_slice() {
+        [jar] => Some(ClientConfig {
+            program: jar.clone(),
+            args: Vec::new(),
+            working_dir: Some(installed.dir.clone()),
+            env: BTreeMap::new()
+        }),
+        _ => None
+    }
+}
+
+/// How the installed native RS3 client is started.
+pub fn rs3_client_config(installed: &InstalledClient) -> ClientConfig {
+    ClientConfig {
+        program: installed.dir.join(RS3_BINARY),
+        args: vec!["--configURI".to_string(), RS3_CONFIG_URI.to_string()],
+        working_dir: Some(installed.dir.clone()),
+        env: BTreeMap::new()
+    }
+}
+
+pub fn installed_client(dir: &Path) -> error::Result<Option<InstalledClient>> {
+    match fs::read_to_string(dir.join(INSTALL_RECORD)) {
+        Ok(contents) => Ok(Some(serde_json::from_str::<InstalledClient>(&contents).map_err(Error::local_state)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into())
+    }
+}
+
+/// Installs RuneLite from its configured source, emitting `client-install-progress` while downloading.
+pub async fn install_runelite<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<InstalledClient> {
+    let installer = app_handle.state::<ClientInstaller>();
+    let _busy = installer.begin()?;
+
+    let source = runelite_source(app_handle)?;
+    log::info!("Installing RuneLite from {}", source.manifest_url);
+    let installed = install(&clients_dir(app_handle)?, RUNELITE_CLIENT, &source, |progress| {
+        let _ = app_handle.emit("client-install-progress", progress);
+    }).await?;
+
+    log::info!("Installed RuneLite into {}", installed.dir.display());
+    Ok(installed)
+}
+
+/// Installs the native RS3 client from the Jagex apt repository, emitting `client-install-progress` while downloading.
+pub async fn install_rs3<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<InstalledClient> {
+    let installer = app_handle.state::<ClientInstaller>();
+    let _busy = installer.begin()?;
+
+    let source = rs3_source(app_handle)?;
+    log::info!("Installing {} from {}", source.package, source.repository_url);
+    let installed = install_deb(&clients_dir(app_handle)?, RS3_CLIENT, &source, RS3_PACKAGE_DIR, |progress| {
+        let _ = app_handle.emit("client-install-progress", progress);
+    }).await?;
+
+    log::info!("Installed RS3 {} into {}", installed.version.as_deref().unwrap_or("client"), installed.dir.display());
+    Ok(installed)
+}
+
+pub async fn fetch_manifest(http_client: &tauri_plugin_http::reqwest::Client, url: &str) -> error::Result<Manifest> {
+    let response = http_client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(Error::DownloadFailed { url: url.to_string(), status: response.status().as_u16() });
+    }
+    Ok(response.json::<Manifest>().await?)
+}
+
+/// Downloads the artifacts of `source` and swaps them in as `<root>/<client>` once all of them are verified.
+///
+/// A failed install leaves the previous one untouched.
+pub async fn install(
+    root: &Path,
+    client: &str,
+    source: &ClientSource,
+    mut on_progress: impl FnMut(&InstallProgress)
+) -> error::Result<InstalledClient> {
+    let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().build()?;
+    let manifest = fetch_manifest(&http_client, &source.manifest_url).await?;
+    let version = manifest.client.as_ref().and_then(|client| client.version.clone());
+    let artifacts = select_artifacts(manifest, source.artifact.as_deref(), (std::env::consts::OS, std::env::consts::ARCH))?;
+
+    let staging = Staging::new(root, client)?;
+    let mut installed = Vec::new();
+    for (index, artifact) in artifacts.iter().enumerate() {
+        let mut progress = InstallProgress {
+            client: client.to_string(),
+            artifact: artifact.name.clone(),
+            index,
+            artifacts: artifacts.len(),
+            downloaded: 0,
+            total: artifact.size
+        };
+        installed.push(download(&http_client, artifact, &staging.dir.join(&artifact.name), &mut progress, &mut on_progress).await?);
+    }
+
+    let record = InstalledClient {
+        client: client.to_string(),
+        dir: root.join(client),
+        manifest_url: source.manifest_url.clone(),
+        version,
+        entry_point: None,
+        artifacts: installed,
+        installed_at: accounts::unix_now()
+    };
+    staging.commit(&record)?;
+    Ok(record)
+}
+
+/// Installs the newest `source.package` from an apt repository, keeping only the files the package
+/// has below `package_dir`. Nothing is run from the package, so neither root nor dpkg is needed.
+pub async fn install_deb(
+    root: &Path,
+    client: &str,
+    source: &AptSource,
+    package_dir: &str,
+    mut on_progress: impl FnMut(&InstallProgress)
+) -> error::Result<InstalledClient> {
+    let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().build()?;
+    let packages_url = source.packages_url();
+    let response = http_client.get(&packages_url).send().await?;
+    if !response.status().is_success() {
+        return Err(Error::DownloadFailed { url: packages_url, status: response.status().as_u16() });
+    }
+    let entries = debian::parse_packages(&response.text().await?);
+    let entry = debian::newest(&entries, &source.package)
+        .ok_or_else(|| Error::InvalidManifest(format!("{} is not listed in {}", source.package, packages_url)))?;
+
+    let artifact = Artifact {
+        name: entry.filename.rsplit('/').next().unwrap_or_default().to_string(),
+        path: format!("{}/{}", source.repository_url.trim_end_matches('/'), entry.filename.trim_start_matches('/')),
+        hash: entry.sha256.clone(),
+        size: entry.size,
+        platform: None
+    };
+    if !is_plain_file_name(&artifact.name) {
+        return Err(Error::InvalidManifest(format!("Package file name {} is not a plain file name", entry.filename)));
+    }
+
+    let staging = Staging::new(root, client)?;
+    let mut progress = InstallProgress {
+        client: client.to_string(),
+        artifact: artifact.name.clone(),
+        index: 0,
+        artifacts: 1,
+        downloaded: 0,
+        total: artifact.size
+    };
+    let deb_path = staging.dir.join(&artifact.name);
+    let package = download(&http_client, &artifact, &deb_path, &mut progress, &mut on_progress).await?;
+
+    let (target, prefix) = (staging.dir.clone(), package_dir.to_string());
+    let extracted = tauri::async_runtime::spawn_blocking(move || -> error::Result<Vec<PathBuf>> {
+        let tar = debian::data_tar(&fs::read(&deb_path)?)?;
+        fs::remove_file(&deb_path)?;
+        archive::extract_tar(tar.as_slice(), &prefix, &target)
+    }).await??;
+    if extracted.is_empty() {
+        return Err(Error::InvalidPackage(format!("{} has no files in {}", artifact.name, package_dir)));
+    }
+    log::info!("Extracted {} files from {}", extracted.len(), artifact.name);
+
+    let record = InstalledClient {
+        client: client.to_string(),
+        dir: root.join(client),
+        manifest_url: packages_url,
+        version: Some(entry.version.clone()),
+        entry_point: None,
+        artifacts: vec![package],
+        installed_at: accounts::unix_now()
+    };
+    staging.commit(&record)?;
+    Ok(record)
+}
+
+/// The artifact called `name`, or every artifact needed on `platform`.
+fn select_artifacts(manifest: Manifest, name: Option<&str>, platform: (&str, &str)) -> error::Result<Vec<Artifact>> {
+    let artifacts = match name {
+        Some(name) => vec![manifest.artifacts.into_iter()
+            .find(|artifact| artifact.name == name)
+            .ok_or_else(|| Error::InvalidManifest(format!("No artifact named {}", name)))?],
+        None => manifest.artifacts.into_iter().filter(|artifact| artifact.is_for(platform)).collect()
+    };
+
+    if artifacts.is_empty() {
+        return Err(Error::InvalidManifest("The manifest lists no artifacts".to_string()));
+    }
+    // Artifact names become file names, so they must not point anywhere else.
+    if let Some(artifact) = artifacts.iter().find(|artifact| !is_plain_file_name(&artifact.name)) {
+        return Err(Error::InvalidManifest(format!("Artifact name {} is not a plain file name", artifact.name)));
+    }
+    Ok(artifacts)
+}
+
+pub(crate) fn is_plain_file_name(name: &str) -> bool {
+    !name.starts_with('.') && Path::new(name).file_name().is_some_and(|file_name| file_name == name)
+}
+
+pub(crate) async fn download(
+    http_client: &tauri_plugin_http::reqwest::Client,
+    artifact: &Artifact,
+    target: &Path,
+    progress: &mut InstallProgress,
+    on_progress: &mut impl FnMut(&InstallProgress)
+) -> error::Result<InstalledArtifact> {
+    let mut response = http_client.get(&artifact.path).send().await?;
+    if !response.status().is_success() {
+        return Err(Error::DownloadFailed { url: artifact.path.clone(), status: response.status().as_u16() });
+    }
+    if progress.total.is_none() {
+        progress.total = response.content_length();
+    }
+    on_progress(progress);
+
+    let mut file = fs::File::create(target)?;
+    let mut hasher = Sha256::new();
+    let mut reported = 0;
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        file.write_all(&chunk)?;
+        progress.downloaded += chunk.len() as u64;
+        if progress.downloaded - reported >= PROGRESS_STEP {
+            reported = progress.downloaded;
+            on_progress(progress);
+        }
+    }
+    file.sync_all()?;
+    if reported != progress.downloaded {
+        on_progress(progress);
+    }
+
+    let hash = hex::encode(hasher.finalize());
+    if !hash.eq_ignore_ascii_case(&artifact.hash) {
+        return Err(Error::ChecksumMismatch {
+            artifact: artifact.name.clone(),
+            expected: artifact.hash.to_lowercase(),
+            actual: hash
+        });
+    }
+
+    Ok(InstalledArtifact {
+        name: artifact.name.clone(),
+        hash,
+        size: progress.downloaded
+    })
+}
+
+/// The directory a new install is put together in, next to the one it replaces. Removed unless committed.
+pub(crate) struct Staging {
+    pub(crate) dir: PathBuf,
+    committed: bool
+}
+
+impl Staging {
+    pub(crate) fn new(root: &Path, client: &str) -> io::Result<Self> {
+        fs::create_dir_all(root)?;
+        let dir = root.join(format!(".{}-{}", client, Uuid::new_v4().simple()));
+        fs::create_dir(&dir)?;
+        Ok(Staging { dir, committed: false })
+    }
+
+    /// Records what was installed and swaps the staged files in as `record.dir`.
+    pub(crate) fn commit(mut self, record: &InstalledClient) -> error::Result<()> {
+        fs::write(self.dir.join(INSTALL_RECORD), serde_json::to_vec_pretty(record)?)?;
+        swap_in(&self.dir, &record.dir)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// Replaces `target` with `staging` using renames, so the client directory is never half written.
+fn swap_in(staging: &Path, target: &Path) -> io::Result<()> {
+    if !target.exists() {
+        return fs::rename(staging, target);
+    }
+
+    let parent = target.parent().unwrap_or(Path::new("."));
+    let previous = parent.join(format!(".previous-{}", Uuid::new_v4().simple()));
+    fs::rename(target, &previous)?;
+    if let Err(e) = fs::rename(staging, target) {
+        fs::rename(&previous, target)?;
+        return Err(e);
+    }
+
+    if let Err(e) = fs::remove_dir_all(&previous) {
+        log::error!("Failed to remove previous client install {}: {}", previous.display(), e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,230 @@
+use serde_json::json;
+use crate::test_support::debian_package::{deb, file};
+use crate::error::ErrorCategory;
+use crate::test_support::mock_downloads::MockDownloads;
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+const LAUNCHER_JAR: &[u8] = b"PK\x03\x04 not really a jar";
+const EXTRA_JAR: &[u8] = b"PK\x03\x04 another one";
+/// Trimmed down from https://static.runelite.net/bootstrap.json, keeping its shape.
+const RUNELITE_BOOTSTRAP: &str = include_str!("../../test_support/runelite_bootstrap.json");
+
+/// Publishes a bootstrap manifest listing `artifacts` with the given hashes, like the RuneLite static server.
+fn publish(bootstrap: &MockDownloads, artifacts: &[(&str, &str)]) -> ClientSource {
+    let artifacts = artifacts.iter()
+        .map(|(name, hash)| json!({
+            "name": name,
+            "path": bootstrap.url(name),
+            "hash": hash,
+            "size": bootstrap.size(name)
+        }))
+        .collect::<Vec<_>>();
+    bootstrap.serve("bootstrap.json", json!({ "artifacts": artifacts, "client": {} }).to_string().as_bytes());
+
+    ClientSource {
+        manifest_url: bootstrap.url("bootstrap.json"),
+        artifact: None
+    }
+}
+
+fn sha256(contents: &[u8]) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+fn leftovers(root: &Path) -> Vec<String> {
+    fs::read_dir(root).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with('.'))
+        .collect()
+}
+
+#[tokio::test]
+async fn installs_verified_artifact_and_reports_progress() {
+    let bootstrap = MockDownloads::start().await;
+    bootstrap.serve("RuneLite.jar", LAUNCHER_JAR);
+    bootstrap.serve("extra.jar", EXTRA_JAR);
+    let mut source = publish(&bootstrap, &[("RuneLite.jar", &sha256(LAUNCHER_JAR)), ("extra.jar", &sha256(EXTRA_JAR))]);
+    source.artifact = Some("RuneLite.jar".to_string());
+    let root = TempDir::new("clients");
+
+    let mut progress = Vec::new();
+    let installed = install(&root, "runelite", &source, |update| progress.push(update.clone())).await.unwrap();
+
+    assert_eq!(installed.dir, root.join("runelite"));
+    assert_eq!(installed.artifacts, vec![InstalledArtifact {
+        name: "RuneLite.jar".to_string(),
+        hash: sha256(LAUNCHER_JAR),
+        size: LAUNCHER_JAR.len() as u64
+    }]);
+    assert_eq!(fs::read(installed.artifact_path("RuneLite.jar")).unwrap(), LAUNCHER_JAR);
+    assert!(!installed.artifact_path("extra.jar").exists());
+    assert_eq!(installed_client(&installed.dir).unwrap().unwrap().artifacts, installed.artifacts);
+
+    let last = progress.last().unwrap();
+    assert_eq!(last.downloaded, LAUNCHER_JAR.len() as u64);
+    assert_eq!(last.total, Some(LAUNCHER_JAR.len() as u64));
+    assert!(leftovers(&root).is_empty());
+}
+
+#[tokio::test]
+async fn checksum_mismatch_keeps_previous_install() {
+    let bootstrap = MockDownloads::start().await;
+    bootstrap.serve("RuneLite.jar", LAUNCHER_JAR);
+    let source = publish(&bootstrap, &[("RuneLite.jar", &sha256(LAUNCHER_JAR))]);
+    let root = TempDir::new("clients");
+    let previous = install(&root, "runelite", &source, |_| {}).await.unwrap();
+
+    // The server now hands out a tampered file under the old hash.
+    bootstrap.serve("RuneLite.jar", EXTRA_JAR);
+    match install(&root, "runelite", &source, |_| {}).await {
+        Err(Error::ChecksumMismatch { artifact, expected, actual }) => {
+            assert_eq!(artifact, "RuneLite.jar");
+            assert_eq!(expected, sha256(LAUNCHER_JAR));
+            assert_eq!(actual, sha256(EXTRA_JAR));
+        }
+        other => panic!("Expected a checksum mismatch, got {:?}", other)
+    }
+
+    assert_eq!(fs::read(previous.artifact_path("RuneLite.jar")).unwrap(), LAUNCHER_JAR);
+    assert!(leftovers(&root).is_empty());
+}
+
+#[tokio::test]
+async fn reinstall_replaces_previous_install() {
+    let bootstrap = MockDownloads::start().await;
+    bootstrap.serve("RuneLite.jar", LAUNCHER_JAR);
+    bootstrap.serve("extra.jar", EXTRA_JAR);
+    let root = TempDir::new("clients");
+
+    let both = publish(&bootstrap, &[("RuneLite.jar", &sha256(LAUNCHER_JAR)), ("extra.jar", &sha256(EXTRA_JAR))]);
+    install(&root, "runelite", &both, |_| {}).await.unwrap();
+    let only_launcher = publish(&bootstrap, &[("RuneLite.jar", &sha256(LAUNCHER_JAR))]);
+    let installed = install(&root, "runelite", &only_launcher, |_| {}).await.unwrap();
+
+    assert!(installed.artifact_path("RuneLite.jar").exists());
+    assert!(!installed.artifact_path("extra.jar").exists());
+    assert!(leftovers(&root).is_empty());
+}
+
+#[tokio::test]
+async fn missing_artifact_and_manifest_are_reported() {
+    let bootstrap = MockDownloads::start().await;
+    let mut source = publish(&bootstrap, &[]);
+    let root = TempDir::new("clients");
+
+    assert!(matches!(install(&root, "runelite", &source, |_| {}).await, Err(Error::InvalidManifest(_))));
+    source.artifact = Some("RuneLite.jar".to_string());
+    assert!(matches!(install(&root, "runelite", &source, |_| {}).await, Err(Error::InvalidManifest(_))));
+    source.manifest_url = format!("{}/missing.json", bootstrap.base_url);
+    assert!(matches!(install(&root, "runelite", &source, |_| {}).await, Err(Error::DownloadFailed { status: 404, .. })));
+}
+
+fn names(artifacts: &[Artifact]) -> Vec<&str> {
+    artifacts.iter().map(|artifact| artifact.name.as_str()).collect()
+}
+
+#[test]
+fn runelite_bootstrap_lists_the_client_and_libraries_per_platform() {
+    assert_eq!(ClientSource::runelite().artifact, None);
+    let manifest = serde_json::from_str::<Manifest>(RUNELITE_BOOTSTRAP).unwrap();
+    assert_eq!(manifest.client.as_ref().and_then(|client| client.version.as_deref()), Some("1.11.9"));
+
+    let linux = select_artifacts(manifest.clone(), None, ("linux", "x86_64")).unwrap();
+    assert_eq!(names(&linux), ["client-1.11.9.jar", "runelite-api-1.11.9.jar", "guava-33.2.1-jre.jar", "gluegen-rt-2.5.0-natives-linux-amd64.jar"]);
+    let arm = select_artifacts(manifest.clone(), None, ("linux", "aarch64")).unwrap();
+    assert_eq!(names(&arm)[3..], ["gluegen-rt-2.5.0-natives-linux-aarch64.jar"]);
+    let windows = select_artifacts(manifest.clone(), None, ("windows", "x86_64")).unwrap();
+    assert_eq!(names(&windows)[3..], ["gluegen-rt-2.5.0-natives-windows-amd64.jar"]);
+    let macos = select_artifacts(manifest, None, ("macos", "aarch64")).unwrap();
+    assert_eq!(names(&macos)[3..], ["gluegen-rt-2.5.0-natives-macosx-universal.jar"]);
+}
+
+#[tokio::test]
+async fn runelite_is_installed_from_its_bootstrap_manifest() {
+    let bootstrap = MockDownloads::start().await;
+    let mut manifest = serde_json::from_str::<serde_json::Value>(RUNELITE_BOOTSTRAP).unwrap();
+    for artifact in manifest["artifacts"].as_array_mut().unwrap() {
+        let name = artifact["name"].as_str().unwrap().to_string();
+        bootstrap.serve(&name, name.as_bytes());
+        artifact["path"] = json!(bootstrap.url(&name));
+        artifact["hash"] = json!(sha256(name.as_bytes()));
+        artifact["size"] = json!(name.len());
+    }
+    bootstrap.serve("bootstrap.json", manifest.to_string().as_bytes());
+    let source = ClientSource { manifest_url: bootstrap.url("bootstrap.json"), ..ClientSource::runelite() };
+    let root = TempDir::new("clients");
+
+    let installed = install(&root, "runelite", &source, |_| {}).await.unwrap();
+    assert_eq!(installed.version.as_deref(), Some("1.11.9"));
+    // Several jars, so not started with -jar but from the classpath.
+    assert!(runelite_client_config(&installed).is_none());
+    let classpath = runelite_classpath(&installed);
+    assert_eq!(classpath[0], installed.artifact_path("client-1.11.9.jar"));
+    assert!(classpath.contains(&installed.artifact_path("guava-33.2.1-jre.jar")));
+    assert!(classpath.len() <= 4);
+}
+
+#[test]
+fn artifact_names_must_be_plain_file_names() {
+    assert!(is_plain_file_name("RuneLite.jar"));
+    assert!(!is_plain_file_name("../RuneLite.jar"));
+    assert!(!is_plain_file_name("lib/RuneLite.jar"));
+    assert!(!is_plain_file_name(".installed.json"));
+    assert!(!is_plain_file_name(""));
+}
+
+#[test]
+fn damaged_install_record_is_a_local_error() {
+    let dir = TempDir::new("clients");
+    assert!(installed_client(&dir).unwrap().is_none());
+
+    fs::write(dir.join(INSTALL_RECORD), b"{ not json").unwrap();
+    let error = installed_client(&dir).unwrap_err();
+    assert!(matches!(error, Error::LocalState(_)));
+    assert_eq!(error.category(), ErrorCategory::LocalIo);
+}
+
+#[tokio::test]
+async fn rs3_client_is_installed_from_apt_repository() {
+    let bootstrap = MockDownloads::start().await;
+    let package = deb(&[
+        file("./usr/bin/runescape-launcher", b"#!/bin/sh\n", 0o755),
+        file("./usr/share/games/runescape-launcher/runescape", b"\x7fELF client", 0o755)
+    ]);
+    let filename = "pool/non-free/r/runescape-launcher/runescape-launcher_2.2.11_amd64.deb";
+    bootstrap.serve(filename, &package);
+    bootstrap.serve("dists/trusty/non-free/binary-amd64/Packages", format!(
+        "Package: runescape-launcher\nVersion: 2.2.11\nFilename: {}\nSize: {}\nSHA256: {}\n",
+        filename, package.len(), sha256(&package)
+    ).as_bytes());
+    let source = AptSource { repository_url: bootstrap.base_url.clone(), ..AptSource::rs3() };
+    let root = TempDir::new("clients");
+
+    let installed = install_deb(&root, "rs3", &source, RS3_PACKAGE_DIR, |_| {}).await.unwrap();
+
+    assert_eq!(installed.version.as_deref(), Some("2.2.11"));
+    assert_eq!(installed.artifacts[0].hash, sha256(&package));
+    let config = rs3_client_config(&installed);
+    assert_eq!(fs::read(&config.program).unwrap(), b"\x7fELF client");
+    assert_eq!(config.working_dir.as_deref(), Some(installed.dir.as_path()));
+    // Only the client is kept, not the downloaded package or the rest of its files.
+    assert!(!installed.dir.join("runescape-launcher_2.2.11_amd64.deb").exists());
+    assert!(!installed.dir.join("runescape-launcher").exists());
+    assert!(leftovers(&root).is_empty());
+}
+
+#[tokio::test]
+async fn rs3_package_with_wrong_hash_is_rejected() {
+    let bootstrap = MockDownloads::start().await;
+    let package = deb(&[file("./usr/share/games/runescape-launcher/runescape", b"\x7fELF client", 0o755)]);
+    bootstrap.serve("runescape-launcher_2.2.11_amd64.deb", &package);
+    bootstrap.serve("dists/trusty/non-free/binary-amd64/Packages",
+        b"Package: runescape-launcher\nVersion: 2.2.11\nFilename: runescape-launcher_2.2.11_amd64.deb\nSHA256: 00\n");
+    let source = AptSource { repository_url: bootstrap.base_url.clone(), ..AptSource::rs3() };
+    let root = TempDir::new("clients");
+
+    assert!(matches!(install_deb(&root, "rs3", &source, RS3_PACKAGE_DIR, |_| {}).await, Err(Error::ChecksumMismatch { .. })));
+    assert!(installed_client(&root.join("rs3")).unwrap().is_none());
+    assert!(leftovers(&root).is_empty());
+}
@@ -0,0 +1,473 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+use tokio::process::Command;
+use crate::error;
+use crate::error::Error;
+use crate::state::{accounts, archive, installer};
+use crate::state::installer::{Artifact, ClientInstaller, InstallProgress, InstalledClient, Staging};
+use crate::state::launch::{ClientConfig, ClientType, SETTINGS_STORE};
+
+/// Replaces the manifest URL managed runtimes are downloaded from, e.g. with a local stand-in server.
+pub const JAVA_MANIFEST_ENV: &str = "RUNELAUNCHER_JAVA_MANIFEST";
+const JAVA_SOURCE_KEY: &str = "java";
+const JAVA_PINS_KEY: &str = "java_pins";
+const RUNTIMES_DIR: &str = "runtimes";
+/// RuneLite and HDOS both refuse to start on anything older.
+pub const MIN_JAVA_MAJOR: u32 = 11;
+/// A `java -version` that takes longer than this is not a runtime we want to launch with.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[cfg(windows)]
+const JAVA_BINARY: &str = "java.exe";
+#[cfg(not(windows))]
+const JAVA_BINARY: &str = "java";
+
+/// Where managed runtimes come from and which of them is installed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JavaSource {
+    pub manifest_url: Option<String>,
+    /// Id of the pinned runtime in the manifest.
+    pub runtime: String
+}
+
+impl Default for JavaSource {
+    fn default() -> Self {
+        JavaSource {
+            manifest_url: None,
+            runtime: "temurin-17".to_string()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuntimeManifest {
+    pub runtimes: Vec<RuntimeArtifact>
+}
+
+/// One downloadable runtime archive, published per operating system and architecture.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuntimeArtifact {
+    pub id: String,
+    pub version: String,
+    /// As in `std::env::consts::OS`, e.g. `linux` or `windows`.
+    pub os: String,
+    /// As in `std::env::consts::ARCH`, e.g. `x86_64` or `aarch64`.
+    pub arch: String,
+    pub url: String,
+    pub sha256: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Path of the java binary inside the archive.
+    pub java_path: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeSource {
+    System,
+    Managed
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JavaRuntime {
+    pub id: String,
+    pub java: PathBuf,
+    pub version: String,
+    pub major: u32,
+    pub source: RuntimeSource
+}
+
+/// The runtime a client type is pinned to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum JavaSelection {
+    Managed { id: String },
+    System { java: PathBuf }
+}
+
+/// The Java source from `$RUNELAUNCHER_JAVA_MANIFEST`, the settings store, or the defaults.
+pub fn java_source<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<JavaSource> {
+    let mut source = match app_handle.store(SETTINGS_STORE)?.get(JAVA_SOURCE_KEY) {
+        Some(value) => serde_json::from_value::<JavaSource>(value).map_err(Error::local_state)?,
+        None => JavaSource::default()
+    };
+    if let Ok(manifest_url) = std::env::var(JAVA_MANIFEST_ENV) {
+        source.manifest_url = Some(manifest_url);
+    }
+    Ok(source)
+}
+
+/// Stores where managed runtimes are downloaded from. `$RUNELAUNCHER_JAVA_MANIFEST` still takes precedence.
+pub fn set_java_source<R: Runtime>(app_handle: &AppHandle<R>, source: &JavaSource) -> error::Result<()> {
+    if let Some(manifest_url) = &source.manifest_url {
+        let url = url::Url::parse(manifest_url)
+            .map_err(|e| Error::InvalidInput(format!("{} is not a valid manifest URL: {}", manifest_url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(Error::InvalidInput(format!("{} is not an http or https URL", manifest_url)));
+        }
+    }
+    if !installer::is_plain_file_name(&source.runtime) {
+        return Err(Error::InvalidInput(format!("{:?} is not a valid runtime id", source.runtime)));
+    }
+
+    let store = app_handle.store(SETTINGS_STORE)?;
+    store.set(JAVA_SOURCE_KEY, serde_json::to_value(source)?);
+    store.save()?;
+
+    log::info!("Java runtimes are now installed from {}", source.manifest_url.as_deref().unwrap_or("nowhere"));
+    Ok(())
+}
+
+pub fn runtimes_dir<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?.join(RUNTIMES_DIR))
+}
+
+/// The major version of a Java version string, e.g. 8 for `1.8.0_292` and 17 for `17.0.8+7`.
+pub fn major_version(version: &str) -> Option<u32> {
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    match parts.next()?.parse::<u32>().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major)
+    }
+}
+
+/// Reads the version out of `java -version` output, e.g. `openjdk version "17.0.8" 2023-07-18`.
+pub fn parse_version(output: &str) -> Option<(String, u32)> {
+    let version = output.lines()
+        .find_map(|line| line.split_once(" version \""))
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(version, _)| version.to_string())?;
+    let major = major_version(&version)?;
+    Some((version, major))
+}
+
+/// Runs `java -version` and returns the version it reports, or `None` if it does not run.
+pub async fn probe(java: &Path) -> Option<(String, u32)> {
+    let output = Command::new(java)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(PROBE_TIMEOUT, output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            log::info!("Unable to run {}: {}", java.display(), e);
+            return None;
+        }
+        Err(_) => {
+            log::info!("{} -version timed out", java.display());
+            return None;
+        }
+    };
+
+    // Java prints its version to stderr, but some wrappers use stdout.
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stderr), String::from_utf8_lossy(&output.stdout));
+    parse_version(&text)
+}
+
+/// Places Java is commonly installed: `$JAVA_HOME`, `$PATH` and the usual JDK directories.
+fn candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(java_home) = std::env::var_os("JAVA_HOME") {
+        candidates.push(PathBuf::from(java_home).join("bin").join(JAVA_BINARY));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&path).map(|dir| dir.join(JAVA_BINARY)));
+    }
+
+    let roots: &[&str] = if cfg!(windows) {
+        &["C:\\Program Files\\Java", "C:\\Program Files\\Eclipse Adoptium", "C:\\Program Files\\Zulu"]
+    } else {
+        &["/usr/lib/jvm", "/usr/java", "/opt/java", "/Library/Java/JavaVirtualMachines"]
+    };
+    for root in roots {
+        let Ok(entries) = fs::read_dir(root) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            candidates.push(entry.path().join("bin").join(JAVA_BINARY));
+            candidates.push(entry.path().join("Contents/Home/bin").join(JAVA_BINARY));
+        }
+    }
+    candidates
+}
+
+/// Every Java installation found on the system that runs, newest first.
+pub async fn system_runtimes() -> Vec<JavaRuntime> {
+    let mut seen = Vec::new();
+    let mut runtimes = Vec::new();
+    for candidate in candidates() {
+        // `/usr/bin/java` and friends are usually symlinks into one of the JDK directories.
+        let Ok(java) = fs::canonicalize(&candidate) else {
+            continue;
+        };
+        if seen.contains(&java) {
+            continue;
+        }
+        seen.push(java.clone());
+
+        if let Some((version, major)) = probe(&java).await {
+            runtimes.push(JavaRuntime {
+                id: format!("system:{}", java.display()),
+                java,
+                version,
+                major,
+                source: RuntimeSource::System
+            });
+        }
+    }
+    runtimes.sort_by_key(|runtime| std::cmp::Reverse(runtime.major));
+    runtimes
+}
+
+fn managed_runtime(installed: &InstalledClient) -> Option<JavaRuntime> {
+    let version = installed.version.clone()?;
+    Some(JavaRuntime {
+        id: installed.client.clone(),
+        java: installed.dir.join(installed.entry_point.as_ref()?),
+        major: major_version(&version)?,
+        version,
+        source: RuntimeSource::Managed
+    })
+}
+
+/// The runtimes installed by the launcher.
+pub fn managed_runtimes<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Vec<JavaRuntime>> {
+    let entries = match fs::read_dir(runtimes_dir(app_handle)?) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new())
+    };
+
+    let mut runtimes = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if let Some(runtime) = installer::installed_client(&entry.path())?.as_ref().and_then(managed_runtime) {
+            runtimes.push(runtime);
+        }
+    }
+    Ok(runtimes)
+}
+
+/// Managed runtimes followed by the ones found on the system.
+pub async fn runtimes<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Vec<JavaRuntime>> {
+    let mut runtimes = managed_runtimes(app_handle)?;
+    runtimes.extend(system_runtimes().await);
+    Ok(runtimes)
+}
+
+/// The runtime to use when a client type is not pinned: a managed one if there is any, as those
+/// are known to work, otherwise the newest recent enough system Java.
+pub fn best_runtime(runtimes: &[JavaRuntime]) -> Option<&JavaRuntime> {
+    runtimes.iter()
+        .filter(|runtime| runtime.major >= MIN_JAVA_MAJOR)
+        .max_by_key(|runtime| (runtime.source == RuntimeSource::Managed, runtime.major))
+}
+
+fn pins<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<HashMap<ClientType, JavaSelection>> {
+    match app_handle.store(SETTINGS_STORE)?.get(JAVA_PINS_KEY) {
+        Some(value) => serde_json::from_value(value).map_err(Error::local_state),
+        None => Ok(HashMap::new())
+    }
+}
+
+pub fn pinned_runtime<R: Runtime>(app_handle: &AppHandle<R>, client_type: ClientType) -> error::Result<Option<JavaSelection>> {
+    Ok(pins(app_handle)?.remove(&client_type))
+}
+
+/// Pins `client_type` to a runtime, or unpins it when `selection` is `None`.
+pub fn pin_runtime<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    client_type: ClientType,
+    selection: Option<JavaSelection>
+) -> error::Result<()> {
+    let mut pins = pins(app_handle)?;
+    match selection {
+        Some(selection) => pins.insert(client_type, selection),
+        None => pins.remove(&client_type)
+    };
+
+    let store = app_handle.store(SETTINGS_STORE)?;
+    store.set(JAVA_PINS_KEY, serde_json::to_value(&pins)?);
+    store.save()?;
+    Ok(())
+}
+
+/// The runtime a client of `client_type` launches with: `selection` from its launch profile,
+/// the runtime the client type is pinned to, or the best one available.
+pub async fn runtime_for<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    client_type: ClientType,
+    selection: Option<JavaSelection>
+) -> error::Result<JavaRuntime> {
+    let selection = match selection {
+        Some(selection) => Some(selection),
+        None => pinned_runtime(app_handle, client_type)?
+    };
+    match selection {
+        Some(JavaSelection::Managed { id }) => managed_runtimes(app_handle)?.into_iter()
+            .find(|runtime| runtime.id == id)
+            .ok_or(Error::JavaNotFound(format!("The pinned runtime {} is not installed", id))),
+        Some(JavaSelection::System { java }) => match probe(&java).await {
+            Some((version, major)) => Ok(JavaRuntime {
+                id: format!("system:{}", java.display()),
+                java,
+                version,
+                major,
+                source: RuntimeSource::System
+            }),
+            None => Err(Error::JavaNotFound(format!("The pinned runtime {} does not run", java.display())))
+        },
+        None => best_runtime(&runtimes(app_handle).await?).cloned()
+            .ok_or(Error::JavaNotFound(format!("No Java {} or newer was found", MIN_JAVA_MAJOR)))
+    }
+}
+
+/// Whether `arg` is where the JVM options of a Java client end and it is told what to run, as
+/// `jar_config` and `classpath_config` start clients.
+pub fn is_entry_flag(arg: &str) -> bool {
+    arg == "-jar" || arg == "-cp"
+}
+
+/// Starts `main_class` with `runtime`, with the jars of `classpath` on its classpath.
+pub fn classpath_config(runtime: &JavaRuntime, classpath: &[PathBuf], main_class: &str, working_dir: Option<PathBuf>) -> ClientConfig {
+    let classpath = std::env::join_paths(classpath)
+        .map(|classpath| classpath.to_string_lossy().to_string())
+        .unwrap_or_default();
+    ClientConfig {
+        program: runtime.java.clone(),
+        args: vec!["-cp".to_string(), classpath, main_class.to_string()],
+        working_dir,
+        env: BTreeMap::new()
+    }
+}
+
+/// Starts the jar `config.program` with `runtime`, passing the client arguments after the jar.
+pub fn jar_config(runtime: &JavaRuntime, config: ClientConfig) -> ClientConfig {
+    let mut args = vec!["-jar".to_string(), config.program.to_string_lossy().to_string()];
+    args.extend(config.args);
+    ClientConfig {
+        program: runtime.java.clone(),
+        args,
+        working_dir: config.working_dir,
+        env: config.env
+    }
+}
+
+/// Installs the pinned runtime of the configured manifest, emitting `client-install-progress` while downloading.
+pub async fn install_runtime<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<JavaRuntime> {
+    let installer = app_handle.state::<ClientInstaller>();
+    let _busy = installer.begin()?;
+
+    let source = java_source(app_handle)?;
+    let manifest_url = source.manifest_url
+        .ok_or(Error::JavaSourceNotConfigured)?;
+    log::info!("Installing Java runtime {} from {}", source.runtime, manifest_url);
+
+    let runtime = install(
+        &runtimes_dir(app_handle)?,
+        &manifest_url,
+        &source.runtime,
+        (std::env::consts::OS, std::env::consts::ARCH),
+        |progress| {
+            let _ = app_handle.emit("client-install-progress", progress);
+        }
+    ).await?;
+
+    log::info!("Installed Java {} into {}", runtime.version, runtime.java.display());
+    Ok(runtime)
+}
+
+/// Downloads runtime `id` for `platform` from the manifest at `manifest_url`, checks it is what the
+/// manifest says it is, and installs it as `<root>/<id>`.
+pub async fn install(
+    root: &Path,
+    manifest_url: &str,
+    id: &str,
+    (os, arch): (&str, &str),
+    mut on_progress: impl FnMut(&InstallProgress)
+) -> error::Result<JavaRuntime> {
+    let http_client = tauri_plugin_http::reqwest::ClientBuilder::new().build()?;
+    let response = http_client.get(manifest_url).send().await?;
+    if !response.status().is_success() {
+        return Err(Error::DownloadFailed { url: manifest_url.to_string(), status: response.status().as_u16() });
+    }
+    let manifest = response.json::<RuntimeManifest>().await?;
+
+    let runtime = manifest.runtimes.into_iter()
+        .find(|runtime| runtime.id == id && runtime.os == os && runtime.arch == arch)
+        // The id was chosen locally, so a missing entry is a wrong choice rather than a broken manifest.
+        .ok_or_else(|| Error::JavaNotFound(format!("The runtime manifest offers no {} for {} {}", id, os, arch)))?;
+    let expected_major = major_version(&runtime.version)
+        .ok_or_else(|| Error::InvalidManifest(format!("Invalid runtime version {}", runtime.version)))?;
+    let java_path = Path::new(&runtime.java_path);
+    if !installer::is_plain_file_name(id) || !java_path.components().all(|component| matches!(component, Component::Normal(_))) {
+        return Err(Error::InvalidManifest(format!("Runtime {} has an invalid id or java path", id)));
+    }
+
+    let archive_name = runtime.url.split(['?', '#']).next().unwrap_or_default()
+        .rsplit('/').next().unwrap_or_default().to_string();
+    if !installer::is_plain_file_name(&archive_name) {
+        return Err(Error::InvalidManifest(format!("Runtime URL {} does not name an archive", runtime.url)));
+    }
+
+    let staging = Staging::new(root, id)?;
+    let archive_path = staging.dir.join(&archive_name);
+    let artifact = Artifact {
+        name: archive_name.clone(),
+        path: runtime.url.clone(),
+        hash: runtime.sha256.clone(),
+        size: runtime.size,
+        platform: None
+    };
+    let mut progress = InstallProgress {
+        client: id.to_string(),
+        artifact: archive_name,
+        index: 0,
+        artifacts: 1,
+        downloaded: 0,
+        total: runtime.size
+    };
+    let downloaded = installer::download(&http_client, &artifact, &archive_path, &mut progress, &mut on_progress).await?;
+
+    let target = staging.dir.clone();
+    tauri::async_runtime::spawn_blocking(move || -> error::Result<()> {
+        archive::unpack(&archive_path, &target)?;
+        fs::remove_file(&archive_path)?;
+        Ok(())
+    }).await??;
+
+    // A runtime that does not start, or is not the version we asked for, is no better than a bad download.
+    let (version, major) = probe(&staging.dir.join(java_path)).await
+        .ok_or_else(|| Error::InvalidPackage(format!("The Java runtime {} does not start", id)))?;
+    if major != expected_major {
+        return Err(Error::InvalidPackage(format!("Runtime {} is Java {}, expected Java {}", id, major, expected_major)));
+    }
+
+    let record = InstalledClient {
+        client: id.to_string(),
+        dir: root.join(id),
+        manifest_url: manifest_url.to_string(),
+        version: Some(version.clone()),
+        entry_point: Some(runtime.java_path.clone()),
+        artifacts: vec![downloaded],
+        installed_at: accounts::unix_now()
+    };
+    staging.commit(&record)?;
+
+    Ok(JavaRuntime {
+        id: id.to_string(),
+        java: record.dir.join(java_path),
+        version,
+        major,
+        source: RuntimeSource::Managed
+    })
+}
+
+#[cfg(test)]
+mod tests;
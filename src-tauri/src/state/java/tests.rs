@@ -0,0 +1,146 @@
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use crate::test_support::debian_package::{file, gzip, tar};
+use crate::test_support::mock_app::MockApp;
+use crate::test_support::mock_downloads::MockDownloads;
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+const JAVA_17: &[u8] = b"#!/bin/sh\necho 'openjdk version \"17.0.8\" 2023-07-18' >&2\n";
+const PLATFORM: (&str, &str) = ("linux", "x86_64");
+
+fn runtime(id: &str, major: u32, source: RuntimeSource) -> JavaRuntime {
+    JavaRuntime {
+        id: id.to_string(),
+        java: PathBuf::from(format!("/jvm/{}/bin/java", id)),
+        version: format!("{}.0.1", major),
+        major,
+        source
+    }
+}
+
+/// Publishes a runtime manifest with one runtime archive for `PLATFORM`, like the Adoptium mirror would.
+fn publish(server: &MockDownloads, archive: &[u8], sha256: &str) -> String {
+    server.serve("temurin-17.tar.gz", archive);
+    server.serve("runtimes.json", json!({
+        "runtimes": [{
+            "id": "temurin-17",
+            "version": "17.0.8+7",
+            "os": PLATFORM.0,
+            "arch": PLATFORM.1,
+            "url": server.url("temurin-17.tar.gz"),
+            "sha256": sha256,
+            "size": archive.len(),
+            "java_path": "jdk-17.0.8+7-jre/bin/java"
+        }]
+    }).to_string().as_bytes());
+    server.url("runtimes.json")
+}
+
+#[test]
+fn versions_are_parsed_from_java_output() {
+    let openjdk = "openjdk version \"17.0.8\" 2023-07-18\nOpenJDK Runtime Environment Temurin-17.0.8+7 (build 17.0.8+7)\n";
+    assert_eq!(parse_version(openjdk), Some(("17.0.8".to_string(), 17)));
+    assert_eq!(parse_version("java version \"1.8.0_292\"\nJava(TM) SE Runtime Environment"), Some(("1.8.0_292".to_string(), 8)));
+    assert_eq!(parse_version("openjdk version \"21\" 2023-09-19"), Some(("21".to_string(), 21)));
+    assert_eq!(parse_version("Error: could not find libjava.so"), None);
+
+    assert_eq!(major_version("11.0.20+8"), Some(11));
+    assert_eq!(major_version("1.7.0"), Some(7));
+    assert_eq!(major_version("jdk"), None);
+}
+
+#[test]
+fn managed_runtime_is_preferred_over_newer_system_java() {
+    let runtimes = vec![
+        runtime("system-21", 21, RuntimeSource::System),
+        runtime("temurin-17", 17, RuntimeSource::Managed),
+        runtime("system-8", 8, RuntimeSource::System)
+    ];
+    assert_eq!(best_runtime(&runtimes).unwrap().id, "temurin-17");
+    assert_eq!(best_runtime(&runtimes[..1]).unwrap().id, "system-21");
+    // Too old to start any current client.
+    assert_eq!(best_runtime(&runtimes[2..]), None);
+}
+
+#[test]
+fn pins_round_trip_through_json() {
+    let pins = HashMap::from([
+        (ClientType::RuneLite, JavaSelection::Managed { id: "temurin-17".to_string() }),
+        (ClientType::Configured, JavaSelection::System { java: PathBuf::from("/usr/bin/java") })
+    ]);
+    let value = serde_json::to_value(&pins).unwrap();
+    assert_eq!(value["rune-lite"], json!({ "kind": "managed", "id": "temurin-17" }));
+    assert_eq!(serde_json::from_value::<HashMap<ClientType, JavaSelection>>(value).unwrap(), pins);
+}
+
+#[tokio::test]
+async fn java_source_must_be_configured_before_installing() {
+    let app = MockApp::new();
+    app.manage(ClientInstaller::default());
+    assert!(matches!(install_runtime(app.handle()).await, Err(Error::JavaSourceNotConfigured)));
+
+    let source = JavaSource { manifest_url: Some("https://java.example.invalid/runtimes.json".to_string()), ..JavaSource::default() };
+    set_java_source(app.handle(), &source).unwrap();
+    assert_eq!(java_source(app.handle()).unwrap().manifest_url, source.manifest_url);
+
+    let file = JavaSource { manifest_url: Some("file:///etc/runtimes.json".to_string()), ..JavaSource::default() };
+    assert!(matches!(set_java_source(app.handle(), &file), Err(Error::InvalidInput(_))));
+    let escaping = JavaSource { runtime: "../temurin-17".to_string(), ..source };
+    assert!(matches!(set_java_source(app.handle(), &escaping), Err(Error::InvalidInput(_))));
+}
+
+#[tokio::test]
+async fn launch_profile_selection_takes_precedence_over_pins() {
+    let app = MockApp::new();
+    let pinned = JavaSelection::Managed { id: "pinned".to_string() };
+    pin_runtime(app.handle(), ClientType::RuneLite, Some(pinned)).unwrap();
+
+    let selected = JavaSelection::Managed { id: "selected".to_string() };
+    let error = runtime_for(app.handle(), ClientType::RuneLite, Some(selected)).await.unwrap_err();
+    assert!(matches!(error, Error::JavaNotFound(message) if message.contains("selected")));
+    let error = runtime_for(app.handle(), ClientType::RuneLite, None).await.unwrap_err();
+    assert!(matches!(error, Error::JavaNotFound(message) if message.contains("pinned")));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn runtime_is_downloaded_verified_and_probed() {
+    let server = MockDownloads::start().await;
+    let archive = gzip(&tar(&[file("jdk-17.0.8+7-jre/bin/java", JAVA_17, 0o755)]));
+    let manifest_url = publish(&server, &archive, &hex::encode(Sha256::digest(&archive)));
+    let root = TempDir::new("runtimes");
+
+    let mut progress = Vec::new();
+    let runtime = install(&root, &manifest_url, "temurin-17", PLATFORM, |update| progress.push(update.clone())).await.unwrap();
+
+    assert_eq!(runtime.java, root.join("temurin-17/jdk-17.0.8+7-jre/bin/java"));
+    assert_eq!((runtime.version.as_str(), runtime.major), ("17.0.8", 17));
+    assert_eq!(progress.last().unwrap().downloaded, archive.len() as u64);
+    // The archive itself is not kept around once unpacked.
+    assert!(!root.join("temurin-17/temurin-17.tar.gz").exists());
+
+    let installed = installer::installed_client(&root.join("temurin-17")).unwrap().unwrap();
+    assert_eq!(managed_runtime(&installed), Some(runtime));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn broken_runtimes_are_rejected() {
+    let server = MockDownloads::start().await;
+    let archive = gzip(&tar(&[file("jdk-17.0.8+7-jre/bin/java", JAVA_17, 0o755)]));
+    let root = TempDir::new("runtimes");
+
+    let manifest_url = publish(&server, &archive, "00");
+    assert!(matches!(install(&root, &manifest_url, "temurin-17", PLATFORM, |_| {}).await, Err(Error::ChecksumMismatch { .. })));
+    assert!(matches!(install(&root, &manifest_url, "temurin-17", ("windows", "x86_64"), |_| {}).await, Err(Error::JavaNotFound(_))));
+
+    // A runtime that claims to be Java 17 but reports something else.
+    let java_8 = b"#!/bin/sh\necho 'java version \"1.8.0_292\"' >&2\n";
+    let archive = gzip(&tar(&[file("jdk-17.0.8+7-jre/bin/java", java_8, 0o755)]));
+    let manifest_url = publish(&server, &archive, &hex::encode(Sha256::digest(&archive)));
+    assert!(matches!(install(&root, &manifest_url, "temurin-17", PLATFORM, |_| {}).await, Err(Error::InvalidPackage(_))));
+
+    assert!(installer::installed_client(&root.join("temurin-17")).unwrap().is_none());
+    assert_eq!(fs::read_dir(&root).unwrap().count(), 0);
+}
@@ -0,0 +1,415 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+use tokio::process::{Child, Command};
+use crate::error;
+use crate::error::Error;
+use crate::state::{accounts, auth, character_data, client_templates, credentials, installer, java, launch_profiles, wrappers};
+use crate::state::auth::{GameCharacter, GameSession};
+use crate::state::client_templates::TemplateValues;
+use crate::state::endpoints::AuthEndpoints;
+use crate::state::java::JavaSelection;
+use crate::state::launch_profiles::LaunchProfile;
+use crate::state::relaunch::Relauncher;
+use crate::state::supervisor;
+use crate::state::supervisor::{RunningClient, Supervisor};
+use crate::state::wrappers::DryRun;
+
+pub(crate) const SETTINGS_STORE: &str = "settings.json";
+const CLIENT_CONFIG_KEY: &str = "client";
+const TEMPLATE_PREFIX: &str = "template:";
+
+/// The game client the launcher starts, chosen by the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientConfig {
+    pub program: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>
+}
+
+/// Which client a character is launched with.
+///
+/// Stored and sent as a string, `configured`, `rune-lite`, `rs3` or `template:<name>`, so it can key maps.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum ClientType {
+    /// The executable the user chose.
+    #[default]
+    Configured,
+    /// RuneLite as installed by the launcher.
+    RuneLite,
+    /// The native RS3 Linux client installed from the Jagex apt repository.
+    Rs3,
+    /// A client declared in the user's client definitions file, by name.
+    Template(String)
+}
+
+impl fmt::Display for ClientType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientType::Configured => f.write_str("configured"),
+            ClientType::RuneLite => f.write_str("rune-lite"),
+            ClientType::Rs3 => f.write_str("rs3"),
+            ClientType::Template(name) => write!(f, "{}{}", TEMPLATE_PREFIX, name)
+        }
+    }
+}
+
+impl FromStr for ClientType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "configured" => Ok(ClientType::Configured),
+            "rune-lite" => Ok(ClientType::RuneLite),
+            "rs3" => Ok(ClientType::Rs3),
+            _ => match value.strip_prefix(TEMPLATE_PREFIX) {
+                Some(name) if !name.is_empty() => Ok(ClientType::Template(name.to_string())),
+                _ => Err(format!("Unknown client type {}", value))
+            }
+        }
+    }
+}
+
+impl From<ClientType> for String {
+    fn from(client_type: ClientType) -> Self {
+        client_type.to_string()
+    }
+}
+
+impl TryFrom<String> for ClientType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Everything needed to start one client process. Every launch path builds one of these and
+/// hands it to `spawn`, so there is a single place that turns settings into a process.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LaunchPlan {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<PathBuf>
+}
+
+impl LaunchPlan {
+    pub fn spawn(&self) -> error::Result<Child> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(&self.env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+
+        command.spawn().map_err(|e| {
+            log::error!("Failed to start {}: {}", self.program.display(), e);
+            Error::Io(e)
+        })
+    }
+}
+
+pub fn client_config<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<ClientConfig> {
+    let store = app_handle.store(SETTINGS_STORE)?;
+    let value = store.get(CLIENT_CONFIG_KEY).ok_or(Error::ClientNotConfigured)?;
+    serde_json::from_value::<ClientConfig>(value).map_err(Error::local_state)
+}
+
+pub fn set_client_config<R: Runtime>(app_handle: &AppHandle<R>, config: &ClientConfig) -> error::Result<()> {
+    let store = app_handle.store(SETTINGS_STORE)?;
+    store.set(CLIENT_CONFIG_KEY, serde_json::to_value(config)?);
+    store.save()?;
+
+    log::info!("Game client set to {}", config.program.display());
+    Ok(())
+}
+
+/// How a client of `client_type` is started, with template placeholders filled in from `values`.
+/// Jar clients are started with the Java runtime `java` selects, or else the one the client type
+/// is pinned to.
+pub async fn client_config_for<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    client_type: &ClientType,
+    values: &TemplateValues,
+    java: Option<&JavaSelection>
+) -> error::Result<ClientConfig> {
+    let config = match client_type {
+        ClientType::Configured => client_config(app_handle)?,
+        ClientType::RuneLite => {
+            let installed = installer::installed_runelite(app_handle)?
+                .ok_or_else(|| Error::ClientNotInstalled("RuneLite".to_string()))?;
+            match installer::runelite_client_config(&installed) {
+                Some(config) => config,
+                None => {
+                    let classpath = installer::runelite_classpath(&installed);
+                    let runtime = java_runtime(app_handle, client_type, java, &installed.dir).await?;
+                    return Ok(java::classpath_config(&runtime, &classpath, installer::RUNELITE_MAIN_CLASS, Some(installed.dir)));
+                }
+            }
+        }
+        ClientType::Rs3 => installer::installed_rs3(app_handle)?
+            .map(|installed| installer::rs3_client_config(&installed))
+            .ok_or_else(|| Error::ClientNotInstalled("RS3".to_string()))?,
+        ClientType::Template(name) => client_templates::find(app_handle, name)?.config(values)?
+    };
+
+    if !is_jar(&config.program) {
+        return Ok(config);
+    }
+    let runtime = java_runtime(app_handle, client_type, java, &config.program).await?;
+    Ok(java::jar_config(&runtime, config))
+}
+
+async fn java_runtime<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    client_type: &ClientType,
+    java: Option<&JavaSelection>,
+    client: &Path
+) -> error::Result<java::JavaRuntime> {
+    let runtime = java::runtime_for(app_handle, client_type.clone(), java.cloned()).await?;
+    log::info!("Starting {} with Java {} from {}", client.display(), runtime.version, runtime.java.display());
+    Ok(runtime)
+}
+
+fn is_jar(program: &Path) -> bool {
+    program.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("jar"))
+}
+
+pub fn template_values(session: &GameSession, character: &GameCharacter, account_id: &str, data_dir: PathBuf) -> TemplateValues {
+    let env = session_env(session, character);
+    TemplateValues {
+        session_id: env["JX_SESSION_ID"].clone(),
+        character_id: character.account_id.clone(),
+        display_name: env.get("JX_DISPLAY_NAME").cloned().unwrap_or_default(),
+        account_id: account_id.to_string(),
+        data_dir
+    }
+}
+
+/// The environment the Jagex launcher hands to game clients so they can log in without a password.
+pub fn session_env(session: &GameSession, character: &GameCharacter) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::new();
+    env.insert("JX_SESSION_ID".to_string(), session.session_id.clone());
+    env.insert("JX_CHARACTER_ID".to_string(), character.account_id.clone());
+    // Characters that were never named have no display name yet, and clients then ask for one.
+    if !character.display_name.is_empty() {
+        env.insert("JX_DISPLAY_NAME".to_string(), character.display_name.clone());
+    }
+    env
+}
+
+/// The process to start for `character`: the client config with `profile` merged into it.
+pub fn plan_for(config: &ClientConfig, profile: &LaunchProfile, session: &GameSession, character: &GameCharacter) -> LaunchPlan {
+    let mut args = config.args.clone();
+    if let Some(jvm_args) = &profile.jvm_args {
+        if args.first().is_some_and(|arg| java::is_entry_flag(arg)) {
+            args.splice(0..0, jvm_args.iter().cloned());
+        } else if !jvm_args.is_empty() {
+            log::info!("Ignoring JVM arguments for {}, which is not started with Java", config.program.display());
+        }
+    }
+    args.extend(profile.client_args.iter().flatten().cloned());
+
+    // The session variables come last so a profile can never log a client in as someone else.
+    let mut env = config.env.clone();
+    env.extend(profile.env.clone());
+    env.extend(session_env(session, character));
+
+    LaunchPlan {
+        program: config.program.clone(),
+        args,
+        env,
+        cwd: profile.working_dir.clone().or_else(|| config.working_dir.clone())
+    }
+}
+
+/// Checks the stored game session of `account_id` is still accepted by Jagex and returns it with
+/// the up to date entry of `character_id`.
+pub async fn validated_session<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str
+) -> error::Result<(GameSession, GameCharacter)> {
+    let session = accounts::account_secrets(app_handle, account_id)?.game_session
+        .ok_or_else(|| Error::SessionExpired(account_id.to_string()))?;
+
+    let endpoints = app_handle.state::<AuthEndpoints>();
+    let characters = match auth::characters(&endpoints, session.clone()).await {
+        Err(Error::JagexApi { status: 401 | 403, .. }) => {
+            credentials::revoke_account(app_handle, account_id);
+            return Err(Error::SessionExpired(account_id.to_string()));
+        }
+        result => result?
+    };
+
+    let character = characters.into_iter()
+        .find(|character| character.account_id == character_id)
+        .ok_or_else(|| Error::CharacterNotFound(character_id.to_string()))?;
+    Ok((session, character))
+}
+
+/// The stored game session of `account_id` and the last known entry of `character_id`, as they
+/// are, without asking Jagex.
+pub fn stored_session<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str
+) -> error::Result<(GameSession, GameCharacter)> {
+    let session = accounts::account_secrets(app_handle, account_id)?.game_session
+        .ok_or_else(|| Error::SessionExpired(account_id.to_string()))?;
+    let character = accounts::get_account(app_handle, account_id)?.characters.into_iter()
+        .find(|character| character.account_id == character_id)
+        .ok_or_else(|| Error::CharacterNotFound(character_id.to_string()))?;
+    Ok((session, character))
+}
+
+/// Where a launch takes the game session of its character from.
+pub enum SessionSource {
+    /// `validated_session`, which revokes the stored session if Jagex no longer accepts it.
+    Validate,
+    /// What `validated_session` just returned for the character.
+    Validated((GameSession, GameCharacter)),
+    /// `stored_session`, for dry runs, which must neither call Jagex nor change anything.
+    Stored
+}
+
+/// A launch resolved down to the process to start.
+pub struct PreparedLaunch {
+    pub plan: LaunchPlan,
+    pub client_type: ClientType,
+    pub config: ClientConfig,
+    pub values: TemplateValues,
+    /// Whether the plan points the client at the character's own data directory.
+    pub isolate_data: bool
+}
+
+/// Resolves everything about launching `character_id` of `account_id` without starting anything or
+/// creating any directories. What `overrides` sets takes precedence over the character's launch profile.
+pub async fn prepare_launch<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str,
+    overrides: &LaunchProfile,
+    session: SessionSource
+) -> error::Result<PreparedLaunch> {
+    let profile = overrides.inherit(&launch_profiles::effective_profile(app_handle, account_id, character_id)?);
+    let client_type = profile.client.clone().unwrap_or_default();
+    let (session, character) = match session {
+        SessionSource::Validate => validated_session(app_handle, account_id, character_id).await?,
+        SessionSource::Validated(validated) => validated,
+        SessionSource::Stored => stored_session(app_handle, account_id, character_id)?
+    };
+    let data_dir = character_data::data_dir(app_handle, character_id)?;
+    let values = template_values(&session, &character, account_id, data_dir.clone());
+    let config = client_config_for(app_handle, &client_type, &values, profile.java.as_ref()).await?;
+
+    let mut plan = plan_for(&config, &profile, &session, &character);
+    let isolate_data = profile.isolate_data.unwrap_or(true);
+    if isolate_data {
+        character_data::isolate(&mut plan, &data_dir, app_handle.path().home_dir().ok().as_deref());
+    }
+    if let Some(wine) = &profile.wine {
+        let prefix = match &wine.prefix {
+            Some(prefix) => prefix.clone(),
+            None => wrappers::managed_prefix(app_handle, &client_type)?
+        };
+        plan = wrappers::wine(plan, wine, prefix);
+    }
+    let plan = wrappers::wrap(plan, profile.wrappers.as_deref().unwrap_or_default());
+
+    Ok(PreparedLaunch { plan, client_type, config, values, isolate_data })
+}
+
+/// The command line `launch_character` would run, with the session id redacted. Uses the stored
+/// session as it is, so a dry run never logs anyone out.
+pub async fn dry_run<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str,
+    overrides: &LaunchProfile
+) -> error::Result<DryRun> {
+    let prepared = prepare_launch(app_handle, account_id, character_id, overrides, SessionSource::Stored).await?;
+    Ok(wrappers::dry_run(prepared.plan, &prepared.values.session_id))
+}
+
+/// Starts a client logged in as `character_id` of `account_id` and hands it to the supervisor,
+/// returning its pid. What `overrides` sets takes precedence over the character's launch profile.
+pub async fn launch_character<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str,
+    overrides: &LaunchProfile
+) -> error::Result<u32> {
+    launch_validated(app_handle, account_id, character_id, overrides, None).await
+}
+
+/// Like `launch_character`, reusing a session `validated_session` just returned for the character
+/// instead of asking Jagex again.
+pub async fn launch_validated<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str,
+    overrides: &LaunchProfile,
+    validated: Option<(GameSession, GameCharacter)>
+) -> error::Result<u32> {
+    let supervisor = app_handle.state::<Supervisor>();
+    let reservation = supervisor.reserve(character_id)?;
+
+    let PreparedLaunch { plan, client_type, config, values, isolate_data } =
+        prepare_launch(app_handle, account_id, character_id, overrides, match validated {
+            Some(validated) => SessionSource::Validated(validated),
+            None => SessionSource::Validate
+        }).await?;
+    if isolate_data {
+        // Seeding a new home copies the user's settings, which is no work for the async runtime.
+        let (app_handle, character_id) = (app_handle.clone(), character_id.to_string());
+        tauri::async_runtime::spawn_blocking(move || character_data::ensure_data_dir(&app_handle, &character_id)).await??;
+    }
+    wrappers::prepare_prefix(&plan)?;
+    let child = plan.spawn()?;
+    let pid = child.id().unwrap_or_default();
+
+    app_handle.state::<Relauncher>().remember(character_id, overrides);
+    supervisor::supervise(app_handle, reservation, RunningClient {
+        pid,
+        account_id: account_id.to_string(),
+        character_id: character_id.to_string(),
+        display_name: values.display_name,
+        client: client_name(&config),
+        client_type,
+        started_at: accounts::unix_now(),
+        log_session: None
+    }, child);
+
+    Ok(pid)
+}
+
+/// The name a client is shown under: its executable, or the jar for clients started with Java.
+fn client_name(config: &ClientConfig) -> String {
+    let program = match config.args.as_slice() {
+        [flag, jar, ..] if flag == "-jar" => Path::new(jar),
+        [flag, _, main_class, ..] if flag == "-cp" => return main_class.rsplit('.').next().unwrap_or(main_class).to_string(),
+        _ => config.program.as_path()
+    };
+    program.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| program.display().to_string())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,178 @@
+use super::*;
+use crate::state::wrappers::WineConfig;
+use crate::test_support::mock_app::MockApp;
+
+fn session() -> GameSession {
+    GameSession {
+        code: "code".to_string(),
+        id_token: "id-token".to_string(),
+        state: "state".to_string(),
+        session_id: "mock-session-id".to_string()
+    }
+}
+
+fn character(display_name: &str) -> GameCharacter {
+    GameCharacter {
+        account_id: "1001".to_string(),
+        display_name: display_name.to_string(),
+        user_hash: "hash-1001".to_string(),
+        is_members: false,
+        levels: None
+    }
+}
+
+#[test]
+fn session_env_carries_session_and_character() {
+    let env = session_env(&session(), &character("MockHero"));
+    assert_eq!(env["JX_SESSION_ID"], "mock-session-id");
+    assert_eq!(env["JX_CHARACTER_ID"], "1001");
+    assert_eq!(env["JX_DISPLAY_NAME"], "MockHero");
+}
+
+#[test]
+fn unnamed_character_has_no_display_name() {
+    let env = session_env(&session(), &character(""));
+    assert!(!env.contains_key("JX_DISPLAY_NAME"));
+}
+
+#[test]
+fn plan_uses_client_config() {
+    let config = ClientConfig {
+        program: PathBuf::from("/opt/client/run"),
+        args: vec!["--safe-mode".to_string()],
+        working_dir: Some(PathBuf::from("/opt/client")),
+        env: BTreeMap::new()
+    };
+
+    let plan = plan_for(&config, &LaunchProfile::default(), &session(), &character("MockHero"));
+    assert_eq!(plan.program, config.program);
+    assert_eq!(plan.args, config.args);
+    assert_eq!(plan.cwd, config.working_dir);
+    assert_eq!(plan.env.len(), 3);
+}
+
+#[test]
+fn plan_merges_launch_profile() {
+    let config = ClientConfig {
+        program: PathBuf::from("/usr/bin/java"),
+        args: vec!["-jar".to_string(), "/opt/runelite/RuneLite.jar".to_string(), "--safe-mode".to_string()],
+        working_dir: Some(PathBuf::from("/opt/runelite")),
+        env: BTreeMap::new()
+    };
+    let profile = LaunchProfile {
+        jvm_args: Some(vec!["-Xmx2g".to_string()]),
+        client_args: Some(vec!["--profile=skiller".to_string()]),
+        env: BTreeMap::from([
+            ("GDK_SCALE".to_string(), "2".to_string()),
+            ("JX_DISPLAY_NAME".to_string(), "Impostor".to_string())
+        ]),
+        working_dir: Some(PathBuf::from("/home/mock/skiller")),
+        ..LaunchProfile::default()
+    };
+
+    let plan = plan_for(&config, &profile, &session(), &character("MockHero"));
+    assert_eq!(plan.args, vec!["-Xmx2g", "-jar", "/opt/runelite/RuneLite.jar", "--safe-mode", "--profile=skiller"]);
+    assert_eq!(plan.env["GDK_SCALE"], "2");
+    assert_eq!(plan.env["JX_DISPLAY_NAME"], "MockHero");
+    assert_eq!(plan.cwd, Some(PathBuf::from("/home/mock/skiller")));
+}
+
+#[test]
+fn jvm_args_are_not_passed_to_native_clients() {
+    let config = ClientConfig {
+        program: PathBuf::from("/opt/rs3/runescape"),
+        args: vec!["--configURI".to_string(), "https://example.invalid/jav_config.ws".to_string()],
+        working_dir: None,
+        env: BTreeMap::new()
+    };
+    let profile = LaunchProfile { jvm_args: Some(vec!["-Xmx2g".to_string()]), ..LaunchProfile::default() };
+
+    assert_eq!(plan_for(&config, &profile, &session(), &character("MockHero")).args, config.args);
+}
+
+#[test]
+fn classpath_clients_get_jvm_args_and_are_named_after_their_main_class() {
+    let runtime = java::JavaRuntime {
+        id: "temurin-17".to_string(),
+        java: PathBuf::from("/jvm/temurin-17/bin/java"),
+        version: "17.0.8".to_string(),
+        major: 17,
+        source: java::RuntimeSource::Managed
+    };
+    let classpath = [PathBuf::from("/clients/runelite/client-1.11.9.jar"), PathBuf::from("/clients/runelite/guava-33.2.1-jre.jar")];
+    let config = java::classpath_config(&runtime, &classpath, "net.runelite.client.RuneLite", None);
+    let profile = LaunchProfile { jvm_args: Some(vec!["-Xmx2g".to_string()]), ..LaunchProfile::default() };
+
+    let plan = plan_for(&config, &profile, &session(), &character("MockHero"));
+    assert_eq!(plan.args[..2], ["-Xmx2g", "-cp"]);
+    assert_eq!(plan.args[3], "net.runelite.client.RuneLite");
+    assert_eq!(client_name(&config), "RuneLite");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn spawned_client_sees_session_env() {
+    let plan = LaunchPlan {
+        program: PathBuf::from("/bin/sh"),
+        args: vec!["-c".to_string(), "test \"$JX_SESSION_ID\" = mock-session-id".to_string()],
+        env: session_env(&session(), &character("MockHero")),
+        cwd: None
+    };
+
+    let status = plan.spawn().unwrap().wait().await.unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn client_types_round_trip_as_strings() {
+    let types = [ClientType::Configured, ClientType::RuneLite, ClientType::Rs3, ClientType::Template("HDOS".to_string())];
+    for client_type in types {
+        let value = serde_json::to_value(&client_type).unwrap();
+        assert!(value.is_string());
+        assert_eq!(serde_json::from_value::<ClientType>(value).unwrap(), client_type);
+    }
+    assert_eq!(serde_json::to_value(ClientType::Template("Bolt".to_string())).unwrap(), "template:Bolt");
+    assert!(serde_json::from_str::<ClientType>("\"template:\"").is_err());
+    assert!(serde_json::from_str::<ClientType>("\"hdos\"").is_err());
+}
+
+/// Every file below `dir` with its size and modification time.
+fn snapshot(dir: &Path) -> BTreeMap<PathBuf, (u64, std::time::SystemTime)> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let entry = entry.unwrap();
+            let metadata = entry.metadata().unwrap();
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            }
+            files.insert(entry.path(), (metadata.len(), metadata.modified().unwrap()));
+        }
+    }
+    files
+}
+
+#[tokio::test]
+async fn dry_run_leaves_app_data_untouched() {
+    let app = MockApp::new();
+    let app_handle = app.handle();
+    app.save_account("account-1", vec![character("MockHero")], session());
+    set_client_config(app_handle, &ClientConfig {
+        program: PathBuf::from("/opt/client/run"),
+        args: Vec::new(),
+        working_dir: None,
+        env: BTreeMap::new()
+    }).unwrap();
+
+    let data_dir = app_handle.path().app_local_data_dir().unwrap();
+    let before = snapshot(&data_dir);
+    let overrides = LaunchProfile { wine: Some(WineConfig::default()), ..LaunchProfile::default() };
+    let dry_run = dry_run(app_handle, "account-1", "1001", &overrides).await.unwrap();
+
+    let home = character_data::data_dir(app_handle, "1001").unwrap();
+    assert_eq!(dry_run.plan.env["HOME"], home.to_string_lossy());
+    assert!(!home.exists());
+    assert!(!wrappers::managed_prefix(app_handle, &ClientType::Configured).unwrap().exists());
+    assert_eq!(snapshot(&data_dir), before);
+}
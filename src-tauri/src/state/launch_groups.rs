@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_store::StoreExt;
+use uuid::Uuid;
+use crate::error;
+use crate::error::{Error, ErrorPayload};
+use crate::state::auth::{GameCharacter, GameSession};
+use crate::state::launch;
+use crate::state::launch_profiles::LaunchProfile;
+
+const LAUNCH_GROUPS_STORE: &str = "launch_groups.json";
+
+/// What a group does when one of its members cannot be validated or started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailurePolicy {
+    /// Skip the member and start the rest.
+    #[default]
+    Continue,
+    /// Start nothing more once a member fails.
+    Abort
+}
+
+/// One character of a group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub account_id: String,
+    pub character_id: String,
+    /// How long to wait after the previous member before starting this one.
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Applied on top of the character's own launch profile.
+    #[serde(default)]
+    pub profile: LaunchProfile
+}
+
+/// Characters that are started together, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchGroup {
+    /// Generated when the group is first saved.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub members: Vec<GroupMember>,
+    #[serde(default)]
+    pub on_failure: FailurePolicy
+}
+
+impl LaunchGroup {
+    pub fn validate(&self) -> error::Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(Error::InvalidLaunchGroup("The group has no name".to_string()));
+        }
+        if self.members.is_empty() {
+            return Err(Error::InvalidLaunchGroup(format!("{} has no members", self.name)));
+        }
+        let mut characters = HashSet::new();
+        for member in &self.members {
+            if !characters.insert(member.character_id.as_str()) {
+                return Err(Error::InvalidLaunchGroup(format!("Character {} is in {} twice", member.character_id, self.name)));
+            }
+            member.profile.validate()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+pub enum MemberState {
+    Pending,
+    Validated,
+    /// Waiting out the member's delay.
+    Waiting { delay_ms: u64 },
+    Starting,
+    Started { pid: u32 },
+    Failed { error: ErrorPayload },
+    /// Not started because an earlier member failed and the group aborts on failure.
+    Skipped
+}
+
+/// Emitted as `launch-group-progress` whenever a member changes state.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupProgress {
+    pub group_id: String,
+    pub index: usize,
+    pub character_id: String,
+    #[serde(flatten)]
+    pub state: MemberState
+}
+
+/// How each member of a group ended up.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupReport {
+    pub group_id: String,
+    pub members: Vec<MemberState>,
+    pub aborted: bool
+}
+
+/// Validates sessions of and starts the members of a group. Kept apart from the app so the
+/// ordering and failure handling can be exercised without Jagex.
+pub(crate) trait MemberLauncher {
+    async fn validate(&self, member: &GroupMember) -> error::Result<()>;
+    async fn start(&self, member: &GroupMember) -> error::Result<u32>;
+}
+
+struct AppLauncher<'a, R: Runtime> {
+    app_handle: &'a AppHandle<R>,
+    /// Sessions checked by `validate`, keyed by character id, so starting a member does not
+    /// ask Jagex a second time.
+    validated: Mutex<HashMap<String, (GameSession, GameCharacter)>>
+}
+
+impl<R: Runtime> MemberLauncher for AppLauncher<'_, R> {
+    async fn validate(&self, member: &GroupMember) -> error::Result<()> {
+        let validated = launch::validated_session(self.app_handle, &member.account_id, &member.character_id).await?;
+        if let Ok(mut sessions) = self.validated.lock() {
+            sessions.insert(member.character_id.clone(), validated);
+        }
+        Ok(())
+    }
+
+    async fn start(&self, member: &GroupMember) -> error::Result<u32> {
+        let validated = self.validated.lock().ok().and_then(|mut sessions| sessions.remove(&member.character_id));
+        launch::launch_validated(self.app_handle, &member.account_id, &member.character_id, &member.profile, validated).await
+    }
+}
+
+/// Validates every member first, then starts them in order with their delays, following the
+/// group's failure policy. `on_progress` sees every state change.
+pub(crate) async fn run(
+    group: &LaunchGroup,
+    launcher: &impl MemberLauncher,
+    mut on_progress: impl FnMut(GroupProgress)
+) -> GroupReport {
+    let mut states = vec![MemberState::Pending; group.members.len()];
+    let mut report = |states: &mut Vec<MemberState>, index: usize, state: MemberState| {
+        on_progress(GroupProgress {
+            group_id: group.id.clone(),
+            index,
+            character_id: group.members[index].character_id.clone(),
+            state: state.clone()
+        });
+        states[index] = state;
+    };
+
+    let mut aborted = false;
+    for (index, member) in group.members.iter().enumerate() {
+        match launcher.validate(member).await {
+            Ok(()) => report(&mut states, index, MemberState::Validated),
+            Err(e) => {
+                log::error!("Session of character {} in group {} is not valid: {}", member.character_id, group.name, e);
+                report(&mut states, index, MemberState::Failed { error: e.payload() });
+                aborted |= group.on_failure == FailurePolicy::Abort;
+            }
+        }
+    }
+
+    for (index, member) in group.members.iter().enumerate() {
+        if !matches!(states[index], MemberState::Validated) {
+            continue;
+        }
+        if aborted {
+            report(&mut states, index, MemberState::Skipped);
+            continue;
+        }
+
+        if member.delay_ms > 0 {
+            report(&mut states, index, MemberState::Waiting { delay_ms: member.delay_ms });
+            tokio::time::sleep(Duration::from_millis(member.delay_ms)).await;
+        }
+        report(&mut states, index, MemberState::Starting);
+        match launcher.start(member).await {
+            Ok(pid) => report(&mut states, index, MemberState::Started { pid }),
+            Err(e) => {
+                log::error!("Failed to start character {} of group {}: {}", member.character_id, group.name, e);
+                report(&mut states, index, MemberState::Failed { error: e.payload() });
+                aborted |= group.on_failure == FailurePolicy::Abort;
+            }
+        }
+    }
+
+    GroupReport {
+        group_id: group.id.clone(),
+        members: states,
+        aborted
+    }
+}
+
+pub fn list_groups<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<Vec<LaunchGroup>> {
+    let store = app_handle.store(LAUNCH_GROUPS_STORE)?;
+    let mut groups = Vec::new();
+    for value in store.values() {
+        groups.push(serde_json::from_value::<LaunchGroup>(value).map_err(Error::local_state)?);
+    }
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(groups)
+}
+
+pub fn get_group<R: Runtime>(app_handle: &AppHandle<R>, group_id: &str) -> error::Result<LaunchGroup> {
+    let value = app_handle.store(LAUNCH_GROUPS_STORE)?.get(group_id)
+        .ok_or_else(|| Error::LaunchGroupNotFound(group_id.to_string()))?;
+    serde_json::from_value::<LaunchGroup>(value).map_err(Error::local_state)
+}
+
+/// Creates or replaces a group, returning it with its id.
+pub fn save_group<R: Runtime>(app_handle: &AppHandle<R>, mut group: LaunchGroup) -> error::Result<LaunchGroup> {
+    group.validate()?;
+    if group.id.is_empty() {
+        group.id = Uuid::new_v4().to_string();
+    }
+
+    let store = app_handle.store(LAUNCH_GROUPS_STORE)?;
+    store.set(group.id.clone(), serde_json::to_value(&group)?);
+    store.save()?;
+    Ok(group)
+}
+
+pub fn delete_group<R: Runtime>(app_handle: &AppHandle<R>, group_id: &str) -> error::Result<()> {
+    let store = app_handle.store(LAUNCH_GROUPS_STORE)?;
+    if !store.delete(group_id) {
+        return Err(Error::LaunchGroupNotFound(group_id.to_string()));
+    }
+    store.save()?;
+    Ok(())
+}
+
+/// Launches a stored group, emitting `launch-group-progress` as members are validated and started.
+pub async fn launch_group<R: Runtime>(app_handle: &AppHandle<R>, group_id: &str) -> error::Result<GroupReport> {
+    let group = get_group(app_handle, group_id)?;
+    group.validate()?;
+    log::info!("Launching group {} with {} members.", group.name, group.members.len());
+
+    let launcher = AppLauncher {
+        app_handle,
+        validated: Mutex::new(HashMap::new())
+    };
+    let report = run(&group, &launcher, |progress| {
+        let _ = app_handle.emit("launch-group-progress", progress);
+    }).await;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,112 @@
+use std::sync::Mutex;
+use super::*;
+
+/// Records what it was asked to do and fails for the characters it is told to.
+#[derive(Default)]
+struct FakeLauncher {
+    invalid: Vec<&'static str>,
+    broken: Vec<&'static str>,
+    calls: Mutex<Vec<String>>
+}
+
+impl MemberLauncher for FakeLauncher {
+    async fn validate(&self, member: &GroupMember) -> error::Result<()> {
+        self.calls.lock().unwrap().push(format!("validate {}", member.character_id));
+        if self.invalid.contains(&member.character_id.as_str()) {
+            return Err(Error::SessionExpired(member.account_id.clone()));
+        }
+        Ok(())
+    }
+
+    async fn start(&self, member: &GroupMember) -> error::Result<u32> {
+        self.calls.lock().unwrap().push(format!("start {}", member.character_id));
+        if self.broken.contains(&member.character_id.as_str()) {
+            return Err(Error::ClientNotInstalled(member.character_id.clone()));
+        }
+        Ok(member.character_id.parse().unwrap())
+    }
+}
+
+fn group(characters: &[&str], on_failure: FailurePolicy) -> LaunchGroup {
+    LaunchGroup {
+        id: "group".to_string(),
+        name: "Mains".to_string(),
+        members: characters.iter().map(|character| GroupMember {
+            account_id: "account".to_string(),
+            character_id: character.to_string(),
+            delay_ms: 0,
+            profile: LaunchProfile::default()
+        }).collect(),
+        on_failure
+    }
+}
+
+fn states(report: &GroupReport) -> Vec<String> {
+    report.members.iter().map(|state| match state {
+        MemberState::Started { pid } => format!("started {}", pid),
+        MemberState::Failed { error } => format!("failed {}", error.code),
+        state => serde_json::to_value(state).unwrap()["state"].as_str().unwrap().to_string()
+    }).collect()
+}
+
+#[tokio::test]
+async fn validates_every_member_before_starting_in_order() {
+    let launcher = FakeLauncher::default();
+    let mut progress = Vec::new();
+    let report = run(&group(&["1", "2", "3"], FailurePolicy::Abort), &launcher, |update| progress.push(update)).await;
+
+    assert_eq!(*launcher.calls.lock().unwrap(), ["validate 1", "validate 2", "validate 3", "start 1", "start 2", "start 3"]);
+    assert_eq!(states(&report), ["started 1", "started 2", "started 3"]);
+    assert!(!report.aborted);
+    // Validated, starting and started for each member.
+    assert_eq!(progress.len(), 9);
+}
+
+#[tokio::test]
+async fn invalid_session_aborts_before_anything_starts() {
+    let launcher = FakeLauncher { invalid: vec!["2"], ..FakeLauncher::default() };
+    let report = run(&group(&["1", "2", "3"], FailurePolicy::Abort), &launcher, |_| {}).await;
+
+    assert!(!launcher.calls.lock().unwrap().iter().any(|call| call.starts_with("start")));
+    assert_eq!(states(&report), ["skipped", "failed session-expired", "skipped"]);
+    assert!(report.aborted);
+}
+
+#[tokio::test]
+async fn continue_policy_skips_only_failed_members() {
+    let launcher = FakeLauncher { invalid: vec!["1"], broken: vec!["2"], ..FakeLauncher::default() };
+    let report = run(&group(&["1", "2", "3"], FailurePolicy::Continue), &launcher, |_| {}).await;
+
+    assert_eq!(states(&report), ["failed session-expired", "failed client-not-installed", "started 3"]);
+    assert!(!report.aborted);
+}
+
+#[tokio::test]
+async fn start_failure_skips_the_rest_when_aborting() {
+    let launcher = FakeLauncher { broken: vec!["2"], ..FakeLauncher::default() };
+    let report = run(&group(&["1", "2", "3"], FailurePolicy::Abort), &launcher, |_| {}).await;
+
+    assert_eq!(states(&report), ["started 1", "failed client-not-installed", "skipped"]);
+    assert!(report.aborted);
+}
+
+#[tokio::test]
+async fn waits_out_member_delays() {
+    let mut group = group(&["1", "2"], FailurePolicy::Continue);
+    group.members[1].delay_ms = 50;
+    let mut waited = Vec::new();
+    let started = std::time::Instant::now();
+    run(&group, &FakeLauncher::default(), |update| if let MemberState::Waiting { delay_ms } = update.state {
+        waited.push((update.index, delay_ms));
+    }).await;
+
+    assert_eq!(waited, [(1, 50)]);
+    assert!(started.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn rejects_empty_groups_and_duplicate_members() {
+    assert!(matches!(group(&[], FailurePolicy::Continue).validate(), Err(Error::InvalidLaunchGroup(_))));
+    assert!(matches!(group(&["1", "1"], FailurePolicy::Continue).validate(), Err(Error::InvalidLaunchGroup(_))));
+    assert!(group(&["1", "2"], FailurePolicy::Continue).validate().is_ok());
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+use crate::error;
+use crate::error::Error;
+use crate::state::java::JavaSelection;
+use crate::state::launch::ClientType;
+use crate::state::relaunch::RelaunchPolicy;
+use crate::state::wrappers;
+use crate::state::wrappers::WineConfig;
+
+const LAUNCH_PROFILES_STORE: &str = "launch_profiles.json";
+
+/// How a character's client is started on top of the client config. Unset fields are inherited
+/// from the account default, and `env` is merged with it key by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchProfile {
+    /// The client launched when none is asked for explicitly.
+    pub client: Option<ClientType>,
+    /// Passed to the Java runtime before `-jar` or `-cp`, e.g. `-Xmx2g`. Ignored for native clients.
+    pub jvm_args: Option<Vec<String>>,
+    /// The Java runtime jar clients are started with, instead of the one their client type is pinned to.
+    pub java: Option<JavaSelection>,
+    /// Appended to the arguments of the client config, e.g. `--profile=pker`.
+    pub client_args: Option<Vec<String>>,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+    /// Commands the client is started through, outermost first, e.g. `[["gamemoderun"], ["mangohud"]]`.
+    pub wrappers: Option<Vec<Vec<String>>>,
+    /// Runs the client under Wine, inside the wrappers.
+    pub wine: Option<WineConfig>,
+    /// Whether the client gets the character's own home directory. On unless turned off.
+    pub isolate_data: Option<bool>,
+    /// Whether and how the client is started again after crashing.
+    pub relaunch: Option<RelaunchPolicy>
+}
+
+impl LaunchProfile {
+    /// This profile with everything it leaves unset taken from `parent`.
+    pub fn inherit(&self, parent: &LaunchProfile) -> LaunchProfile {
+        let mut env = parent.env.clone();
+        env.extend(self.env.clone());
+        LaunchProfile {
+            client: self.client.clone().or_else(|| parent.client.clone()),
+            jvm_args: self.jvm_args.clone().or_else(|| parent.jvm_args.clone()),
+            java: self.java.clone().or_else(|| parent.java.clone()),
+            client_args: self.client_args.clone().or_else(|| parent.client_args.clone()),
+            env,
+            working_dir: self.working_dir.clone().or_else(|| parent.working_dir.clone()),
+            wrappers: self.wrappers.clone().or_else(|| parent.wrappers.clone()),
+            wine: self.wine.clone().or_else(|| parent.wine.clone()),
+            isolate_data: self.isolate_data.or(parent.isolate_data),
+            relaunch: self.relaunch.clone().or_else(|| parent.relaunch.clone())
+        }
+    }
+
+    pub fn validate(&self) -> error::Result<()> {
+        // Session variables are set by the launcher and must reach the client untouched.
+        if let Some(name) = self.env.keys().find(|name| name.starts_with("JX_")) {
+            return Err(Error::InvalidLaunchProfile(format!("{} is set by the launcher", name)));
+        }
+        if let Some(name) = self.env.keys().find(|name| name.is_empty() || name.contains(['=', '\0'])) {
+            return Err(Error::InvalidLaunchProfile(format!("{:?} is not a valid environment variable name", name)));
+        }
+        if let Some(dir) = self.working_dir.as_ref().filter(|dir| !dir.is_absolute()) {
+            return Err(Error::InvalidLaunchProfile(format!("Working directory {} is not an absolute path", dir.display())));
+        }
+        if let Some(JavaSelection::System { java }) = &self.java {
+            if !java.is_absolute() {
+                return Err(Error::InvalidLaunchProfile(format!("Java runtime {} is not an absolute path", java.display())));
+            }
+        }
+        if let Some(relaunch) = &self.relaunch {
+            relaunch.validate()?;
+        }
+        wrappers::validate(self.wrappers.as_deref().unwrap_or_default())
+    }
+}
+
+/// The launch profiles of one account: its default and the overrides of its characters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountProfiles {
+    pub default: LaunchProfile,
+    /// Keyed by character id.
+    pub characters: BTreeMap<String, LaunchProfile>
+}
+
+impl AccountProfiles {
+    /// The profile `character_id` is launched with after inheriting from the account default.
+    pub fn effective(&self, character_id: &str) -> LaunchProfile {
+        match self.characters.get(character_id) {
+            Some(profile) => profile.inherit(&self.default),
+            None => self.default.clone()
+        }
+    }
+}
+
+pub fn account_profiles<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<AccountProfiles> {
+    match app_handle.store(LAUNCH_PROFILES_STORE)?.get(account_id) {
+        Some(value) => serde_json::from_value::<AccountProfiles>(value).map_err(Error::local_state),
+        None => Ok(AccountProfiles::default())
+    }
+}
+
+fn save_profiles<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, profiles: &AccountProfiles) -> error::Result<()> {
+    let store = app_handle.store(LAUNCH_PROFILES_STORE)?;
+    store.set(account_id, serde_json::to_value(profiles)?);
+    store.save()?;
+    Ok(())
+}
+
+pub fn set_account_profile<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, profile: LaunchProfile) -> error::Result<()> {
+    profile.validate()?;
+    let mut profiles = account_profiles(app_handle, account_id)?;
+    profiles.default = profile;
+    save_profiles(app_handle, account_id, &profiles)?;
+
+    log::info!("Updated the default launch profile of account {}.", account_id);
+    Ok(())
+}
+
+/// Sets the profile of `character_id`, or makes it use the account default when `profile` is `None`.
+pub fn set_character_profile<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    account_id: &str,
+    character_id: &str,
+    profile: Option<LaunchProfile>
+) -> error::Result<()> {
+    let mut profiles = account_profiles(app_handle, account_id)?;
+    match profile {
+        Some(profile) => {
+            profile.validate()?;
+            profiles.characters.insert(character_id.to_string(), profile);
+        }
+        None => {
+            profiles.characters.remove(character_id);
+        }
+    }
+    save_profiles(app_handle, account_id, &profiles)?;
+
+    log::info!("Updated the launch profile of character {}.", character_id);
+    Ok(())
+}
+
+pub fn effective_profile<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, character_id: &str) -> error::Result<LaunchProfile> {
+    Ok(account_profiles(app_handle, account_id)?.effective(character_id))
+}
+
+pub fn remove_profiles<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<()> {
+    let store = app_handle.store(LAUNCH_PROFILES_STORE)?;
+    if store.delete(account_id) {
+        store.save()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
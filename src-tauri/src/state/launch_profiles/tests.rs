@@ -0,0 +1,81 @@
+use super::*;
+
+fn strings(values: &[&str]) -> Option<Vec<String>> {
+    Some(values.iter().map(|value| value.to_string()).collect())
+}
+
+fn account_profiles() -> AccountProfiles {
+    AccountProfiles {
+        default: LaunchProfile {
+            client: Some(ClientType::RuneLite),
+            jvm_args: strings(&["-Xmx1g"]),
+            java: Some(JavaSelection::Managed { id: "temurin-17".to_string() }),
+            client_args: strings(&["--profile=main"]),
+            env: BTreeMap::from([("_JAVA_OPTIONS".to_string(), "-Dsun.java2d.uiScale=2".to_string())]),
+            working_dir: Some(PathBuf::from("/games/runelite")),
+            wrappers: Some(vec![vec!["gamemoderun".to_string()]]),
+            wine: None,
+            isolate_data: Some(false),
+            relaunch: Some(RelaunchPolicy { enabled: true, ..RelaunchPolicy::default() })
+        },
+        characters: BTreeMap::from([("1001".to_string(), LaunchProfile {
+            jvm_args: strings(&["-Xmx4g"]),
+            client_args: strings(&[]),
+            env: BTreeMap::from([("GDK_SCALE".to_string(), "2".to_string())]),
+            ..LaunchProfile::default()
+        })])
+    }
+}
+
+#[test]
+fn character_profile_inherits_unset_fields() {
+    let profile = account_profiles().effective("1001");
+
+    assert_eq!(profile.client, Some(ClientType::RuneLite));
+    assert_eq!(profile.jvm_args, strings(&["-Xmx4g"]));
+    assert_eq!(profile.java, Some(JavaSelection::Managed { id: "temurin-17".to_string() }));
+    // An empty list is a choice, not something to inherit.
+    assert_eq!(profile.client_args, strings(&[]));
+    assert_eq!(profile.env.len(), 2);
+    assert_eq!(profile.working_dir, Some(PathBuf::from("/games/runelite")));
+    assert_eq!(profile.wrappers, Some(vec![vec!["gamemoderun".to_string()]]));
+    assert_eq!(profile.isolate_data, Some(false));
+    assert!(profile.relaunch.is_some_and(|relaunch| relaunch.enabled));
+}
+
+#[test]
+fn characters_without_profile_use_the_default() {
+    let profiles = account_profiles();
+    assert_eq!(profiles.effective("2002"), profiles.default);
+}
+
+#[test]
+fn profiles_are_validated() {
+    let valid = account_profiles().default;
+    assert!(valid.validate().is_ok());
+
+    let session = LaunchProfile {
+        env: BTreeMap::from([("JX_SESSION_ID".to_string(), "stolen".to_string())]),
+        ..valid.clone()
+    };
+    assert!(matches!(session.validate(), Err(Error::InvalidLaunchProfile(_))));
+
+    let name = LaunchProfile {
+        env: BTreeMap::from([("A=B".to_string(), "1".to_string())]),
+        ..valid.clone()
+    };
+    assert!(matches!(name.validate(), Err(Error::InvalidLaunchProfile(_))));
+
+    let relative = LaunchProfile { working_dir: Some(PathBuf::from("runelite")), ..valid.clone() };
+    assert!(matches!(relative.validate(), Err(Error::InvalidLaunchProfile(_))));
+
+    let java = LaunchProfile { java: Some(JavaSelection::System { java: PathBuf::from("bin/java") }), ..valid };
+    assert!(matches!(java.validate(), Err(Error::InvalidLaunchProfile(_))));
+}
+
+#[test]
+fn stored_profiles_may_omit_fields() {
+    let profiles = serde_json::from_str::<AccountProfiles>(r#"{"characters": {"1001": {"jvm_args": ["-Xmx2g"]}}}"#).unwrap();
+    assert_eq!(profiles.default, LaunchProfile::default());
+    assert_eq!(profiles.effective("1001").jvm_args, strings(&["-Xmx2g"]));
+}
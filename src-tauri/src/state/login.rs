@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Runtime, WebviewWindow};
+use tokio::sync::watch;
+use crate::error;
+use crate::error::Error;
+
+/// The user has this long to finish logging in inside the login window.
+pub const AUTHORIZE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Upper bound for stages that are plain requests to Jagex, or a hidden window following redirects.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Far longer than a game session request lives, after which its state cannot be matched anyway.
+const CONSUMED_STATE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Tracks the login in progress so the UI can cancel it.
+#[derive(Default)]
+pub struct LoginState {
+    cancel: Mutex<Option<watch::Sender<bool>>>
+}
+
+impl LoginState {
+    /// Registers a new login, cancelling any login that was still running.
+    pub fn begin(&self) -> watch::Receiver<bool> {
+        let (tx, rx) = watch::channel(false);
+        if let Ok(mut cancel) = self.cancel.lock() {
+            if let Some(previous) = cancel.replace(tx) {
+                let _ = previous.send(true);
+            }
+        }
+        rx
+    }
+
+    /// Cancels the running login. Returns false if there was none.
+    pub fn cancel(&self) -> bool {
+        match self.cancel.lock() {
+            Ok(mut cancel) => match cancel.take() {
+                Some(tx) => tx.send(true).is_ok(),
+                None => false
+            },
+            Err(_) => false
+        }
+    }
+
+    /// Unregisters the login `cancel` belongs to, unless a newer login has already replaced it.
+    pub fn finish(&self, cancel: &watch::Receiver<bool>) {
+        if let Ok(mut current) = self.cancel.lock() {
+            if current.as_ref().is_some_and(|tx| tx.subscribe().same_channel(cancel)) {
+                current.take();
+            }
+        }
+    }
+}
+
+/// `state` values of game-session redirects that were already redeemed for a session id.
+#[derive(Default)]
+pub struct ConsumedSessionStates {
+    states: Mutex<HashMap<String, Instant>>
+}
+
+impl ConsumedSessionStates {
+    /// Records `state` as consumed, returning false if a redirect carrying it was already accepted.
+    pub fn consume(&self, state: &str) -> bool {
+        self.consume_at(state, Instant::now())
+    }
+
+    /// Forgets states consumed more than `CONSUMED_STATE_TTL` before `now` on the way, so the set
+    /// does not grow for as long as the launcher runs.
+    fn consume_at(&self, state: &str, now: Instant) -> bool {
+        let Ok(mut states) = self.states.lock() else {
+            return false;
+        };
+        states.retain(|_, consumed_at| now.saturating_duration_since(*consumed_at) < CONSUMED_STATE_TTL);
+        match states.entry(state.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(now);
+                true
+            }
+        }
+    }
+}
+
+/// Runs one stage of the login, giving up when it takes longer than `limit` or the login is cancelled.
+///
+/// The stage future is dropped on either outcome, which also tears down any window it opened.
+pub async fn stage<T>(
+    cancel: &mut watch::Receiver<bool>,
+    name: &str,
+    limit: Duration,
+    stage: impl Future<Output = error::Result<T>>
+) -> error::Result<T> {
+    tokio::select! {
+        result = stage => result,
+        _ = cancel.wait_for(|cancelled| *cancelled) => {
+            log::info!("Login cancelled while {}.", name);
+            Err(Error::Cancelled(format!("Login was cancelled while {}.", name)))
+        }
+        _ = tokio::time::sleep(limit) => {
+            log::error!("Login timed out while {}.", name);
+            Err(Error::Timeout(format!("Timed out after {}s while {}.", limit.as_secs(), name)))
+        }
+    }
+}
+
+/// Destroys an auth webview when dropped, so it never outlives the stage that opened it.
+pub struct AuthWindowGuard<R: Runtime>(pub WebviewWindow<R>);
+
+impl<R: Runtime> Drop for AuthWindowGuard<R> {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.destroy() {
+            log::error!("Failed to destroy auth window {}: {}", self.0.label(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+use super::*;
+
+#[tokio::test]
+async fn cancelling_interrupts_running_stage() {
+    let login_state = LoginState::default();
+    let mut cancel = login_state.begin();
+
+    let stage = stage(&mut cancel, "testing", Duration::from_secs(30), std::future::pending::<error::Result<()>>());
+    let (result, cancelled) = tokio::join!(stage, async { login_state.cancel() });
+
+    assert!(cancelled);
+    assert!(matches!(result, Err(Error::Cancelled(_))));
+}
+
+#[tokio::test]
+async fn cancel_before_stage_starts_is_not_lost() {
+    let login_state = LoginState::default();
+    let mut cancel = login_state.begin();
+    assert!(login_state.cancel());
+
+    let result = stage(&mut cancel, "testing", Duration::from_secs(30), std::future::pending::<error::Result<()>>()).await;
+    assert!(matches!(result, Err(Error::Cancelled(_))));
+}
+
+#[tokio::test]
+async fn slow_stage_times_out() {
+    let login_state = LoginState::default();
+    let mut cancel = login_state.begin();
+
+    let result = stage(&mut cancel, "testing", Duration::from_millis(50), std::future::pending::<error::Result<()>>()).await;
+    assert!(matches!(result, Err(Error::Timeout(_))));
+}
+
+#[tokio::test]
+async fn new_login_cancels_previous_one() {
+    let login_state = LoginState::default();
+    let mut first = login_state.begin();
+    let second = login_state.begin();
+
+    let result = stage(&mut first, "testing", Duration::from_secs(30), std::future::pending::<error::Result<()>>()).await;
+    assert!(matches!(result, Err(Error::Cancelled(_))));
+
+    // The superseded login finishing must not unregister the one that replaced it.
+    login_state.finish(&first);
+    assert!(login_state.cancel());
+    login_state.finish(&second);
+    assert!(!login_state.cancel());
+}
+
+#[test]
+fn consumed_states_expire() {
+    let consumed = ConsumedSessionStates::default();
+    let start = Instant::now();
+
+    assert!(consumed.consume_at("state-1", start));
+    assert!(!consumed.consume_at("state-1", start + Duration::from_secs(1)));
+
+    // Inserting after the TTL prunes what came before, so the state could be redeemed again.
+    assert!(consumed.consume_at("state-2", start + CONSUMED_STATE_TTL));
+    assert_eq!(consumed.states.lock().unwrap().len(), 1);
+    assert!(consumed.consume_at("state-1", start + CONSUMED_STATE_TTL));
+}
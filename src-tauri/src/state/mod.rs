@@ -1 +1,23 @@
-pub mod auth;
\ No newline at end of file
+pub mod archive;
+pub mod auth;
+pub mod character_data;
+pub mod client_logs;
+pub mod client_templates;
+pub mod credentials;
+pub mod accounts;
+pub mod debian;
+pub mod endpoints;
+pub mod hiscores;
+pub mod installer;
+pub mod java;
+pub mod launch;
+pub mod launch_groups;
+pub mod launch_profiles;
+pub mod login;
+pub mod paths;
+pub mod playtime;
+pub mod relaunch;
+pub mod supervisor;
+pub mod tokens;
+pub mod webview_profiles;
+pub mod wrappers;
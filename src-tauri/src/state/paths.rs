@@ -0,0 +1,19 @@
+use sha2::{Digest, Sha256};
+
+/// Turns an id that came from outside the launcher, such as an account or character id, into a
+/// single path component. Ids that needed characters replaced get a short hash of the original
+/// appended, so two ids never share a directory.
+pub fn dir_name(id: &str) -> String {
+    let name = id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect::<String>();
+    if name == id && !name.is_empty() {
+        return name;
+    }
+
+    let hash = hex::encode(Sha256::digest(id.as_bytes()));
+    format!("{}-{}", name, &hash[..8])
+}
+
+#[cfg(test)]
+mod tests;
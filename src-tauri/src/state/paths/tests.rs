@@ -0,0 +1,16 @@
+use super::*;
+
+#[test]
+fn ids_cannot_escape_their_directory() {
+    assert_eq!(dir_name("mock-user-0001"), "mock-user-0001");
+    assert!(dir_name("../../etc").starts_with("______etc-"));
+    assert!(dir_name("a/b\\c:d").starts_with("a_b_c_d-"));
+    assert!(!dir_name("").is_empty());
+}
+
+#[test]
+fn replaced_characters_do_not_make_ids_collide() {
+    assert_ne!(dir_name("a/b"), dir_name("a:b"));
+    assert_ne!(dir_name("a/b"), dir_name("a_b"));
+    assert_eq!(dir_name("a/b"), dir_name("a/b"));
+}
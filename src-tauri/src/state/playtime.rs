@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+use crate::error;
+use crate::error::Error;
+use crate::state::accounts;
+use crate::state::launch::ClientType;
+use crate::state::supervisor::{ClientExited, RunningClient};
+
+const PLAYTIME_STORE: &str = "playtime.json";
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// One run of a client, recorded when it is launched and closed once it exits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaySession {
+    pub character_id: String,
+    pub client_type: ClientType,
+    /// Unix timestamps (seconds).
+    pub started_at: u64,
+    /// `None` while the client runs. Also left unset, with no duration, when the launcher crashed
+    /// before the client exited.
+    pub ended_at: Option<u64>,
+    pub duration_secs: u64,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub killed: bool
+}
+
+impl PlaySession {
+    pub fn open(client: &RunningClient) -> PlaySession {
+        PlaySession {
+            character_id: client.character_id.clone(),
+            client_type: client.client_type.clone(),
+            started_at: client.started_at,
+            ended_at: None,
+            duration_secs: 0,
+            exit_code: None,
+            signal: None,
+            killed: false
+        }
+    }
+
+    pub fn from_exit(exited: &ClientExited, ended_at: u64) -> PlaySession {
+        PlaySession {
+            ended_at: Some(ended_at),
+            duration_secs: ended_at.saturating_sub(exited.client.started_at),
+            exit_code: exited.exit_code,
+            signal: exited.signal,
+            killed: exited.killed,
+            ..PlaySession::open(&exited.client)
+        }
+    }
+
+    /// When the session ended, or as far as it is known to have lasted if it is still open.
+    fn end(&self) -> u64 {
+        self.ended_at.unwrap_or(self.started_at)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PlaytimeSummary {
+    pub sessions: u32,
+    pub total_secs: u64,
+    /// When the last session ended, as a Unix timestamp. `None` if the character was never played.
+    pub last_played: Option<u64>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountPlaytime {
+    pub account_id: String,
+    #[serde(flatten)]
+    pub total: PlaytimeSummary,
+    /// Keyed by character id. Characters that were never played are left out.
+    pub characters: BTreeMap<String, PlaytimeSummary>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DailyPlaytime {
+    /// `YYYY-MM-DD` in the time zone the days were asked for in.
+    pub date: String,
+    /// Sessions that started on this day.
+    pub sessions: u32,
+    pub total_secs: u64
+}
+
+pub fn summarize<'a>(sessions: impl IntoIterator<Item = &'a PlaySession>) -> PlaytimeSummary {
+    let mut summary = PlaytimeSummary::default();
+    for session in sessions {
+        summary.sessions += 1;
+        summary.total_secs += session.duration_secs;
+        summary.last_played = summary.last_played.max(Some(session.end()));
+    }
+    summary
+}
+
+pub fn account_summary(account_id: &str, sessions: &[PlaySession]) -> AccountPlaytime {
+    let mut characters = BTreeMap::<String, Vec<&PlaySession>>::new();
+    for session in sessions {
+        characters.entry(session.character_id.clone()).or_default().push(session);
+    }
+    AccountPlaytime {
+        account_id: account_id.to_string(),
+        total: summarize(sessions),
+        characters: characters.into_iter()
+            .map(|(character_id, sessions)| (character_id, summarize(sessions)))
+            .collect()
+    }
+}
+
+/// Play time per calendar day, oldest first. Days are counted `utc_offset_secs` away from UTC,
+/// and sessions running past midnight count towards both days.
+pub fn daily<'a>(sessions: impl IntoIterator<Item = &'a PlaySession>, utc_offset_secs: i64) -> Vec<DailyPlaytime> {
+    let mut days = BTreeMap::<i64, (u32, u64)>::new();
+    for session in sessions {
+        let mut from = session.started_at as i64 + utc_offset_secs;
+        let end = session.end().max(session.started_at) as i64 + utc_offset_secs;
+        let mut day = from.div_euclid(SECS_PER_DAY);
+        days.entry(day).or_default().0 += 1;
+
+        loop {
+            let midnight = (day + 1) * SECS_PER_DAY;
+            days.entry(day).or_default().1 += (end.min(midnight) - from) as u64;
+            if end <= midnight {
+                break;
+            }
+            from = midnight;
+            day += 1;
+        }
+    }
+
+    days.into_iter()
+        .map(|(day, (sessions, total_secs))| DailyPlaytime { date: date(day), sessions, total_secs })
+        .collect()
+}
+
+/// The date `days` days after 1970-01-01, from Howard Hinnant's `civil_from_days`.
+fn date(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Every recorded session of an account, oldest first.
+pub fn sessions<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<Vec<PlaySession>> {
+    match app_handle.store(PLAYTIME_STORE)?.get(account_id) {
+        Some(value) => serde_json::from_value::<Vec<PlaySession>>(value).map_err(Error::local_state),
+        None => Ok(Vec::new())
+    }
+}
+
+/// The session still open for the run of a client that started at `started_at`.
+fn open_session<'a>(history: &'a mut [PlaySession], character_id: &str, started_at: u64) -> Option<&'a mut PlaySession> {
+    history.iter_mut().find(|session| session.ended_at.is_none()
+        && session.character_id == character_id
+        && session.started_at == started_at)
+}
+
+/// Replaces the open session `closed` was started as, or adds it if there is none.
+pub fn close(history: &mut Vec<PlaySession>, closed: PlaySession) {
+    match open_session(history, &closed.character_id, closed.started_at) {
+        Some(session) => *session = closed,
+        None => history.push(closed)
+    }
+}
+
+/// Writes play sessions to the history of their accounts. Clients start and exit on their own
+/// tasks, so each update of a history is serialized.
+#[derive(Default)]
+pub struct PlaytimeRecorder {
+    writing: Mutex<()>
+}
+
+impl PlaytimeRecorder {
+    /// Records an open session for a client that was just launched, so it is not lost if the
+    /// launcher goes away before the client does.
+    pub fn started<R: Runtime>(&self, app_handle: &AppHandle<R>, client: &RunningClient) -> error::Result<()> {
+        self.update(app_handle, &client.account_id, |history| history.push(PlaySession::open(client)))
+    }
+
+    /// Closes the session of a client that just exited.
+    pub fn exited<R: Runtime>(&self, app_handle: &AppHandle<R>, exited: &ClientExited) -> error::Result<()> {
+        let closed = PlaySession::from_exit(exited, accounts::unix_now());
+        self.update(app_handle, &exited.client.account_id, |history| close(history, closed))
+    }
+
+    /// Closes the sessions of clients still running when the launcher quits. The clients themselves
+    /// keep running, but the launcher can no longer tell when they exit.
+    pub fn flush<R: Runtime>(&self, app_handle: &AppHandle<R>, running: &[RunningClient]) {
+        let now = accounts::unix_now();
+        for client in running {
+            let exited = ClientExited {
+                client: client.clone(),
+                exit_code: None,
+                signal: None,
+                killed: false
+            };
+            let closed = PlaySession::from_exit(&exited, now);
+            // Left alone if the client exited in the meantime and closed it already.
+            let result = self.update(app_handle, &client.account_id, |history| {
+                if let Some(session) = open_session(history, &closed.character_id, closed.started_at) {
+                    *session = closed;
+                }
+            });
+            if let Err(e) = result {
+                log::error!("Failed to record the play session of character {}: {}", client.character_id, e);
+            }
+        }
+    }
+
+    fn update<R: Runtime>(
+        &self,
+        app_handle: &AppHandle<R>,
+        account_id: &str,
+        change: impl FnOnce(&mut Vec<PlaySession>)
+    ) -> error::Result<()> {
+        let _writing = self.writing.lock()
+            .map_err(|_| Error::LocalState("Playtime history is poisoned.".to_string()))?;
+        let mut history = sessions(app_handle, account_id)?;
+        change(&mut history);
+
+        let store = app_handle.store(PLAYTIME_STORE)?;
+        store.set(account_id, serde_json::to_value(&history)?);
+        store.save()?;
+        Ok(())
+    }
+}
+
+pub fn remove_history<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<()> {
+    let store = app_handle.store(PLAYTIME_STORE)?;
+    if store.delete(account_id) {
+        store.save()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
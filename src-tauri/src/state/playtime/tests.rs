@@ -0,0 +1,71 @@
+use super::*;
+
+fn session(character_id: &str, started_at: u64, ended_at: u64) -> PlaySession {
+    PlaySession {
+        character_id: character_id.to_string(),
+        client_type: ClientType::RuneLite,
+        started_at,
+        ended_at: Some(ended_at),
+        duration_secs: ended_at - started_at,
+        exit_code: Some(0),
+        signal: None,
+        killed: false
+    }
+}
+
+#[test]
+fn summarizes_accounts_per_character() {
+    let sessions = [session("1001", 100, 400), session("1002", 500, 600), session("1001", 1_000, 1_100)];
+    let playtime = account_summary("account", &sessions);
+
+    assert_eq!(playtime.total, PlaytimeSummary { sessions: 3, total_secs: 500, last_played: Some(1_100) });
+    assert_eq!(playtime.characters["1001"], PlaytimeSummary { sessions: 2, total_secs: 400, last_played: Some(1_100) });
+    assert_eq!(playtime.characters["1002"], PlaytimeSummary { sessions: 1, total_secs: 100, last_played: Some(600) });
+}
+
+#[test]
+fn open_sessions_are_closed_in_place() {
+    let mut open = session("1001", 100, 100);
+    open.ended_at = None;
+    open.exit_code = None;
+    let mut history = vec![session("1001", 10, 50), open.clone()];
+
+    // The launcher crashed during it, so it counts without a duration.
+    assert_eq!(summarize(&history), PlaytimeSummary { sessions: 2, total_secs: 40, last_played: Some(100) });
+
+    close(&mut history, session("1001", 100, 400));
+    assert_eq!(history, [session("1001", 10, 50), session("1001", 100, 400)]);
+
+    // Never recorded as started, so it is added.
+    close(&mut history, session("1002", 500, 600));
+    assert_eq!(history.len(), 3);
+}
+
+#[test]
+fn never_played_has_no_last_played() {
+    assert_eq!(summarize(&[]), PlaytimeSummary::default());
+}
+
+#[test]
+fn sessions_past_midnight_count_towards_both_days() {
+    // 2024-03-01 23:00 until 2024-03-02 01:30 UTC.
+    let midnight = 1_709_337_600;
+    let sessions = [session("1001", midnight - 3_600, midnight + 5_400)];
+
+    assert_eq!(daily(&sessions, 0), [
+        DailyPlaytime { date: "2024-03-01".to_string(), sessions: 1, total_secs: 3_600 },
+        DailyPlaytime { date: "2024-03-02".to_string(), sessions: 0, total_secs: 5_400 }
+    ]);
+    // At UTC+2 it all happened on the 2nd.
+    assert_eq!(daily(&sessions, 2 * 3_600), [
+        DailyPlaytime { date: "2024-03-02".to_string(), sessions: 1, total_secs: 9_000 }
+    ]);
+}
+
+#[test]
+fn dates_handle_leap_years_and_the_epoch() {
+    assert_eq!(date(0), "1970-01-01");
+    assert_eq!(date(-1), "1969-12-31");
+    assert_eq!(date(19_782), "2024-02-29");
+    assert_eq!(date(19_783), "2024-03-01");
+}
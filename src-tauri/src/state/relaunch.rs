@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use crate::error;
+use crate::error::{Error, ErrorPayload};
+use crate::state::{accounts, launch, launch_profiles, tokens};
+use crate::state::launch_profiles::LaunchProfile;
+use crate::state::supervisor::ClientExited;
+
+/// Starts a character's client again after it crashed. Off unless a launch profile turns it on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelaunchPolicy {
+    pub enabled: bool,
+    /// Relaunches in a row before the client is considered to be crash looping.
+    pub max_retries: u32,
+    /// Wait before the first relaunch, doubled for each one after it.
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// A client that ran at least this long before crashing starts counting retries from zero again.
+    pub stable_after_secs: u64
+}
+
+impl Default for RelaunchPolicy {
+    fn default() -> Self {
+        RelaunchPolicy {
+            enabled: false,
+            max_retries: 3,
+            initial_delay_ms: 5_000,
+            max_delay_ms: 120_000,
+            stable_after_secs: 300
+        }
+    }
+}
+
+impl RelaunchPolicy {
+    /// How long to wait before relaunch number `attempt`, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+        Duration::from_millis(self.initial_delay_ms.saturating_mul(factor).min(self.max_delay_ms))
+    }
+
+    pub fn validate(&self) -> error::Result<()> {
+        if self.max_delay_ms < self.initial_delay_ms {
+            return Err(Error::InvalidLaunchProfile("The maximum relaunch delay is shorter than the initial one".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the client went away on its own with a non-zero status or a signal.
+pub fn crashed(exited: &ClientExited) -> bool {
+    !exited.killed && (exited.signal.is_some() || exited.exit_code.is_some_and(|code| code != 0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// The client exited normally, was killed, or relaunching is off.
+    Ignore,
+    Relaunch { attempt: u32, delay: Duration },
+    /// It crashed again after `retries` relaunches. Counting starts over on the next launch.
+    CrashLoop { retries: u32 }
+}
+
+/// The relaunches of one character since its client last ran stably.
+#[derive(Debug, Default)]
+pub struct CrashHistory {
+    retries: u32
+}
+
+impl CrashHistory {
+    pub fn record(&mut self, policy: &RelaunchPolicy, crashed: bool, ran_for: Duration) -> Decision {
+        if !crashed || !policy.enabled {
+            self.retries = 0;
+            return Decision::Ignore;
+        }
+        if ran_for >= Duration::from_secs(policy.stable_after_secs) {
+            self.retries = 0;
+        }
+        if self.retries >= policy.max_retries {
+            let retries = self.retries;
+            self.retries = 0;
+            return Decision::CrashLoop { retries };
+        }
+
+        self.retries += 1;
+        Decision::Relaunch { attempt: self.retries, delay: policy.delay(self.retries) }
+    }
+}
+
+struct Tracked {
+    /// What the client was last launched with, so a relaunch starts the same client.
+    overrides: LaunchProfile,
+    history: CrashHistory,
+    /// Set while a relaunch waits out its delay. Clearing it cancels the relaunch.
+    pending: bool
+}
+
+/// Remembers how each character was launched and how often it crashed.
+#[derive(Default)]
+pub struct Relauncher {
+    characters: Mutex<HashMap<String, Tracked>>
+}
+
+impl Relauncher {
+    /// Called for every launch, relaunches included. Keeps the crash history of the character but
+    /// cancels a relaunch still waiting, since the character runs again.
+    pub fn remember(&self, character_id: &str, overrides: &LaunchProfile) {
+        if let Ok(mut characters) = self.characters.lock() {
+            let tracked = characters.entry(character_id.to_string()).or_insert_with(|| Tracked {
+                overrides: LaunchProfile::default(),
+                history: CrashHistory::default(),
+                pending: false
+            });
+            tracked.overrides = overrides.clone();
+            tracked.pending = false;
+        }
+    }
+
+    /// Cancels a relaunch that is waiting out its delay, or claims it once the delay is over.
+    /// Returns false if none was waiting.
+    pub fn cancel(&self, character_id: &str) -> bool {
+        match self.characters.lock() {
+            Ok(mut characters) => characters.get_mut(character_id)
+                .map(|tracked| std::mem::replace(&mut tracked.pending, false))
+                .unwrap_or(false),
+            Err(_) => false
+        }
+    }
+
+    fn record(&self, character_id: &str, policy: &RelaunchPolicy, crashed: bool, ran_for: Duration) -> Decision {
+        let Ok(mut characters) = self.characters.lock() else {
+            return Decision::Ignore;
+        };
+        let Some(tracked) = characters.get_mut(character_id) else {
+            return Decision::Ignore;
+        };
+        let decision = tracked.history.record(policy, crashed, ran_for);
+        tracked.pending = matches!(decision, Decision::Relaunch { .. });
+        decision
+    }
+
+    /// Forgets the crashes of a character whose relaunch could not be started.
+    fn reset(&self, character_id: &str) {
+        if let Ok(mut characters) = self.characters.lock() {
+            if let Some(tracked) = characters.get_mut(character_id) {
+                tracked.history = CrashHistory::default();
+            }
+        }
+    }
+
+    fn overrides(&self, character_id: &str) -> LaunchProfile {
+        self.characters.lock().ok()
+            .and_then(|characters| characters.get(character_id).map(|tracked| tracked.overrides.clone()))
+            .unwrap_or_default()
+    }
+}
+
+/// Emitted as `client-relaunching` when a crashed client is about to be started again.
+#[derive(Debug, Clone, Serialize)]
+pub struct Relaunching {
+    pub account_id: String,
+    pub character_id: String,
+    pub attempt: u32,
+    pub delay_ms: u64
+}
+
+/// Emitted as `client-crash-loop` when relaunching is given up on.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashLoop {
+    pub account_id: String,
+    pub character_id: String,
+    pub display_name: String,
+    pub retries: u32,
+    /// Why the last relaunch could not be started, if that is what ended it.
+    pub error: Option<ErrorPayload>
+}
+
+/// Decides whether an exited client is relaunched, and does so after the policy's delay.
+pub fn client_exited<R: Runtime>(app_handle: &AppHandle<R>, exited: &ClientExited) {
+    let client = &exited.client;
+    let policy = match launch_profiles::effective_profile(app_handle, &client.account_id, &client.character_id) {
+        Ok(profile) => app_handle.state::<Relauncher>().overrides(&client.character_id).inherit(&profile).relaunch.unwrap_or_default(),
+        Err(e) => {
+            log::error!("Unable to read the relaunch policy of character {}: {}", client.character_id, e);
+            return;
+        }
+    };
+    let ran_for = Duration::from_secs(accounts::unix_now().saturating_sub(client.started_at));
+
+    match app_handle.state::<Relauncher>().record(&client.character_id, &policy, crashed(exited), ran_for) {
+        Decision::Ignore => {}
+        Decision::CrashLoop { retries } => {
+            log::error!("Client of character {} keeps crashing, giving up after {} relaunches.", client.character_id, retries);
+            report_crash_loop(app_handle, CrashLoop {
+                account_id: client.account_id.clone(),
+                character_id: client.character_id.clone(),
+                display_name: client.display_name.clone(),
+                retries,
+                error: None
+            });
+        }
+        Decision::Relaunch { attempt, delay } => {
+            log::info!("Client of character {} crashed, relaunching in {:?} (attempt {} of {}).",
+                client.character_id, delay, attempt, policy.max_retries);
+            let _ = app_handle.emit("client-relaunching", Relaunching {
+                account_id: client.account_id.clone(),
+                character_id: client.character_id.clone(),
+                attempt,
+                delay_ms: delay.as_millis() as u64
+            });
+
+            let app_handle = app_handle.clone();
+            let client = client.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if !app_handle.state::<Relauncher>().cancel(&client.character_id) {
+                    log::info!("Relaunch of character {} was cancelled.", client.character_id);
+                    return;
+                }
+
+                match relaunch(&app_handle, &client.account_id, &client.character_id).await {
+                    Ok(_) => {}
+                    Err(Error::ClientAlreadyRunning(_)) => {
+                        log::info!("Character {} was started again before its relaunch.", client.character_id);
+                    }
+                    Err(e) => {
+                        log::error!("Failed to relaunch character {}: {}", client.character_id, e);
+                        app_handle.state::<Relauncher>().reset(&client.character_id);
+                        report_crash_loop(&app_handle, CrashLoop {
+                            account_id: client.account_id,
+                            character_id: client.character_id,
+                            display_name: client.display_name,
+                            retries: attempt,
+                            error: Some(e.payload())
+                        });
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Launches the character like it was launched last, renewing the game session first if it expired.
+async fn relaunch<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str, character_id: &str) -> error::Result<u32> {
+    let overrides = app_handle.state::<Relauncher>().overrides(character_id);
+    match launch::launch_character(app_handle, account_id, character_id, &overrides).await {
+        Err(Error::SessionExpired(_)) => {
+            log::info!("Game session of account {} expired, renewing it before relaunching.", account_id);
+            let secrets = tokens::renew(app_handle, account_id).await?;
+            app_handle.state::<tokens::TokenLifecycle>().track(account_id, secrets.token_expires_at);
+            let _ = app_handle.emit("account-renewed", account_id);
+            launch::launch_character(app_handle, account_id, character_id, &overrides).await
+        }
+        result => result
+    }
+}
+
+fn report_crash_loop<R: Runtime>(app_handle: &AppHandle<R>, crash_loop: CrashLoop) {
+    let name = if crash_loop.display_name.is_empty() { &crash_loop.character_id } else { &crash_loop.display_name };
+    let body = match &crash_loop.error {
+        Some(error) => format!("{} could not be relaunched: {}", name, error.message),
+        None => format!("{} crashed {} times in a row and will not be relaunched.", name, crash_loop.retries + 1)
+    };
+    if let Err(e) = app_handle.notification().builder().title("Client keeps crashing").body(body).show() {
+        log::error!("Failed to show crash notification: {}", e);
+    }
+    let _ = app_handle.emit("client-crash-loop", crash_loop);
+}
+
+#[cfg(test)]
+mod tests;
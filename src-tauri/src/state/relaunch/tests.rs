@@ -0,0 +1,90 @@
+use super::*;
+use crate::state::launch::ClientType;
+use crate::state::supervisor::RunningClient;
+
+fn policy() -> RelaunchPolicy {
+    RelaunchPolicy {
+        enabled: true,
+        max_retries: 2,
+        initial_delay_ms: 1_000,
+        max_delay_ms: 3_000,
+        stable_after_secs: 60
+    }
+}
+
+fn exited(exit_code: Option<i32>, signal: Option<i32>, killed: bool) -> ClientExited {
+    ClientExited {
+        client: RunningClient {
+            pid: 1,
+            account_id: "account".to_string(),
+            character_id: "1001".to_string(),
+            display_name: "Zezima".to_string(),
+            client: "RuneLite".to_string(),
+            client_type: ClientType::RuneLite,
+            started_at: 0,
+            log_session: None
+        },
+        exit_code,
+        signal,
+        killed
+    }
+}
+
+#[test]
+fn delay_doubles_up_to_the_maximum() {
+    let policy = policy();
+    assert_eq!(policy.delay(1), Duration::from_secs(1));
+    assert_eq!(policy.delay(2), Duration::from_secs(2));
+    assert_eq!(policy.delay(3), Duration::from_secs(3));
+    assert_eq!(policy.delay(100), Duration::from_secs(3));
+}
+
+#[test]
+fn only_unrequested_failures_are_crashes() {
+    assert!(crashed(&exited(Some(1), None, false)));
+    assert!(crashed(&exited(None, Some(11), false)));
+    assert!(!crashed(&exited(Some(0), None, false)));
+    assert!(!crashed(&exited(None, Some(9), true)));
+    assert!(!crashed(&exited(None, None, false)));
+}
+
+#[test]
+fn crash_loop_is_reported_after_max_retries() {
+    let policy = policy();
+    let mut history = CrashHistory::default();
+    let quickly = Duration::from_secs(5);
+
+    assert_eq!(history.record(&policy, true, quickly), Decision::Relaunch { attempt: 1, delay: Duration::from_secs(1) });
+    assert_eq!(history.record(&policy, true, quickly), Decision::Relaunch { attempt: 2, delay: Duration::from_secs(2) });
+    assert_eq!(history.record(&policy, true, quickly), Decision::CrashLoop { retries: 2 });
+    // Giving up starts the count over for the next launch.
+    assert_eq!(history.record(&policy, true, quickly), Decision::Relaunch { attempt: 1, delay: Duration::from_secs(1) });
+}
+
+#[test]
+fn stable_runs_and_clean_exits_reset_the_count() {
+    let policy = policy();
+    let mut history = CrashHistory::default();
+    history.record(&policy, true, Duration::from_secs(5));
+    history.record(&policy, true, Duration::from_secs(5));
+
+    assert_eq!(history.record(&policy, true, Duration::from_secs(600)), Decision::Relaunch { attempt: 1, delay: Duration::from_secs(1) });
+    assert_eq!(history.record(&policy, false, Duration::from_secs(5)), Decision::Ignore);
+    assert_eq!(history.record(&policy, true, Duration::from_secs(5)), Decision::Relaunch { attempt: 1, delay: Duration::from_secs(1) });
+}
+
+#[test]
+fn nothing_is_relaunched_unless_enabled() {
+    let mut history = CrashHistory::default();
+    assert_eq!(history.record(&RelaunchPolicy::default(), true, Duration::ZERO), Decision::Ignore);
+}
+
+#[test]
+fn launching_again_cancels_a_pending_relaunch() {
+    let relauncher = Relauncher::default();
+    relauncher.remember("1001", &LaunchProfile::default());
+    assert!(matches!(relauncher.record("1001", &policy(), true, Duration::ZERO), Decision::Relaunch { .. }));
+
+    relauncher.remember("1001", &LaunchProfile::default());
+    assert!(!relauncher.cancel("1001"));
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::process::Child;
+use tokio::sync::oneshot;
+use crate::error;
+use crate::error::Error;
+use crate::state::{client_logs, relaunch};
+use crate::state::client_logs::ClientLogs;
+use crate::state::launch::ClientType;
+use crate::state::playtime::PlaytimeRecorder;
+
+/// A game client started by the launcher that has not exited yet.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningClient {
+    pub pid: u32,
+    pub account_id: String,
+    pub character_id: String,
+    pub display_name: String,
+    /// What was launched, e.g. the executable name of the client.
+    pub client: String,
+    pub client_type: ClientType,
+    /// Unix timestamp (seconds) of the launch.
+    pub started_at: u64,
+    /// The session log the client's output is written to, if it could be created.
+    pub log_session: Option<String>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientExited {
+    #[serde(flatten)]
+    pub client: RunningClient,
+    pub exit_code: Option<i32>,
+    /// The signal that terminated the client, on platforms that have them.
+    pub signal: Option<i32>,
+    pub killed: bool
+}
+
+enum Slot {
+    /// Reserved while the session is validated and the process spawned.
+    Launching,
+    Running {
+        client: RunningClient,
+        kill: Option<oneshot::Sender<()>>
+    }
+}
+
+/// Keeps track of every client process per character, so that a character only ever runs once.
+#[derive(Default)]
+pub struct Supervisor {
+    slots: Mutex<HashMap<String, Slot>>
+}
+
+/// Holds a character's slot while it is being launched and frees it again if the launch fails.
+pub struct Reservation<'a> {
+    supervisor: &'a Supervisor,
+    character_id: String,
+    committed: bool
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Ok(mut slots) = self.supervisor.slots.lock() {
+                slots.remove(&self.character_id);
+            }
+        }
+    }
+}
+
+impl Supervisor {
+    /// Claims `character_id` for a launch, failing if it is already running or being launched.
+    pub fn reserve(&self, character_id: &str) -> error::Result<Reservation<'_>> {
+        let mut slots = self.slots.lock()
+            .map_err(|_| Error::LocalState("Client supervisor is poisoned.".to_string()))?;
+        if slots.contains_key(character_id) {
+            return Err(Error::ClientAlreadyRunning(character_id.to_string()));
+        }
+        slots.insert(character_id.to_string(), Slot::Launching);
+
+        Ok(Reservation {
+            supervisor: self,
+            character_id: character_id.to_string(),
+            committed: false
+        })
+    }
+
+    pub fn running(&self) -> Vec<RunningClient> {
+        let mut clients = match self.slots.lock() {
+            Ok(slots) => slots.values()
+                .filter_map(|slot| match slot {
+                    Slot::Running { client, .. } => Some(client.clone()),
+                    Slot::Launching => None
+                })
+                .collect::<Vec<RunningClient>>(),
+            Err(_) => Vec::new()
+        };
+        clients.sort_by_key(|client| client.started_at);
+        clients
+    }
+
+    /// Asks the client of `character_id` to be killed. Returns false if it is not running.
+    pub fn kill(&self, character_id: &str) -> bool {
+        if let Ok(mut slots) = self.slots.lock() {
+            if let Some(Slot::Running { kill, .. }) = slots.get_mut(character_id) {
+                if let Some(kill) = kill.take() {
+                    return kill.send(()).is_ok();
+                }
+            }
+        }
+        false
+    }
+
+    fn remove(&self, character_id: &str) {
+        if let Ok(mut slots) = self.slots.lock() {
+            slots.remove(character_id);
+        }
+    }
+}
+
+/// Hands a freshly spawned client to the supervisor, which waits for it to exit and reports it.
+pub fn supervise<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    mut reservation: Reservation<'_>,
+    mut client: RunningClient,
+    mut child: Child
+) {
+    client.log_session = match client_logs::capture(app_handle, &client, &mut child) {
+        Ok(session_id) => Some(session_id),
+        Err(e) => {
+            log::error!("Failed to capture output of client (pid {}): {}", client.pid, e);
+            None
+        }
+    };
+
+    let (kill_tx, kill_rx) = oneshot::channel::<()>();
+    if let Ok(mut slots) = reservation.supervisor.slots.lock() {
+        slots.insert(client.character_id.clone(), Slot::Running {
+            client: client.clone(),
+            kill: Some(kill_tx)
+        });
+    }
+    reservation.committed = true;
+
+    log::info!("Client {} started for character {} (pid {}).", client.client, client.character_id, client.pid);
+    if let Err(e) = app_handle.state::<PlaytimeRecorder>().started(app_handle, &client) {
+        log::error!("Failed to record the play session of character {}: {}", client.character_id, e);
+    }
+    let _ = app_handle.emit("client-started", &client);
+    update_tray(app_handle);
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let (status, killed) = tokio::select! {
+            status = child.wait() => (status, false),
+            Ok(()) = kill_rx => {
+                log::info!("Killing client of character {} (pid {}).", client.character_id, client.pid);
+                if let Err(e) = child.kill().await {
+                    log::error!("Failed to kill client (pid {}): {}", client.pid, e);
+                }
+                (child.wait().await, true)
+            }
+        };
+
+        app_handle.state::<Supervisor>().remove(&client.character_id);
+        if let Some(session_id) = &client.log_session {
+            app_handle.state::<ClientLogs>().finish(session_id);
+        }
+
+        let (exit_code, signal) = match &status {
+            Ok(status) => (status.code(), exit_signal(status)),
+            Err(e) => {
+                log::error!("Failed to wait for client (pid {}): {}", client.pid, e);
+                (None, None)
+            }
+        };
+        log::info!("Client of character {} (pid {}) exited with code {:?}, signal {:?}.",
+            client.character_id, client.pid, exit_code, signal);
+
+        let exited = ClientExited {
+            client,
+            exit_code,
+            signal,
+            killed
+        };
+        if let Err(e) = app_handle.state::<PlaytimeRecorder>().exited(&app_handle, &exited) {
+            log::error!("Failed to record the play session of character {}: {}", exited.client.character_id, e);
+        }
+        let _ = app_handle.emit("client-exited", &exited);
+        update_tray(&app_handle);
+        relaunch::client_exited(&app_handle, &exited);
+    });
+}
+
+#[cfg(unix)]
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Lists the running clients in the tray tooltip.
+fn update_tray<R: Runtime>(app_handle: &AppHandle<R>) {
+    let Some(tray) = app_handle.tray_by_id(crate::TRAY_ID) else {
+        return;
+    };
+
+    let running = app_handle.state::<Supervisor>().running();
+    let tooltip = match running.len() {
+        0 => "Rune Launcher".to_string(),
+        count => {
+            let names = running.iter()
+                .map(|client| if client.display_name.is_empty() { client.character_id.as_str() } else { client.display_name.as_str() })
+                .collect::<Vec<&str>>()
+                .join(", ");
+            format!("Rune Launcher - {} running: {}", count, names)
+        }
+    };
+
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        log::error!("Failed to update tray tooltip: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn character_can_only_be_reserved_once() {
+    let supervisor = Supervisor::default();
+
+    let reservation = supervisor.reserve("1001").unwrap();
+    assert!(matches!(supervisor.reserve("1001"), Err(Error::ClientAlreadyRunning(_))));
+    assert!(supervisor.reserve("1002").is_ok());
+
+    // A failed launch frees the character again.
+    drop(reservation);
+    assert!(supervisor.reserve("1001").is_ok());
+}
+
+#[test]
+fn launching_clients_are_not_listed_or_killable() {
+    let supervisor = Supervisor::default();
+    let _reservation = supervisor.reserve("1001").unwrap();
+
+    assert!(supervisor.running().is_empty());
+    assert!(!supervisor.kill("1001"));
+}
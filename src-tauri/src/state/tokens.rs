@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::Notify;
+use crate::error;
+use crate::error::{Error, ErrorPayload};
+use crate::state::{accounts, auth, credentials};
+use crate::state::accounts::AccountSecrets;
+use crate::state::endpoints::AuthEndpoints;
+use crate::state::webview_profiles::WebviewProfile;
+
+/// Renew this long before the access token actually expires.
+const REFRESH_MARGIN_SECS: u64 = 5 * 60;
+/// How long to wait before retrying a failed renewal while the token is still valid.
+const RETRY_DELAY_SECS: u64 = 60;
+/// Upper bound for how long the service sleeps when nothing is due.
+const IDLE_POLL_SECS: u64 = 60 * 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenewalFailed {
+    pub account_id: String,
+    pub error: ErrorPayload
+}
+
+/// Tracks when each stored account's access token expires so it can be renewed in the background.
+#[derive(Default)]
+pub struct TokenLifecycle {
+    expiries: Mutex<HashMap<String, u64>>,
+    wake: Notify
+}
+
+impl TokenLifecycle {
+    pub fn track(&self, account_id: &str, expires_at: u64) {
+        if let Ok(mut expiries) = self.expiries.lock() {
+            expiries.insert(account_id.to_string(), expires_at);
+        }
+        self.wake.notify_one();
+    }
+
+    pub fn untrack(&self, account_id: &str) {
+        if let Ok(mut expiries) = self.expiries.lock() {
+            expiries.remove(account_id);
+        }
+        self.wake.notify_one();
+    }
+
+    fn next_due(&self) -> Option<u64> {
+        self.expiries.lock().ok()?
+            .values()
+            .map(|expires_at| expires_at.saturating_sub(REFRESH_MARGIN_SECS))
+            .min()
+    }
+
+    fn due_accounts(&self, now: u64) -> Vec<(String, u64)> {
+        match self.expiries.lock() {
+            Ok(expiries) => expiries.iter()
+                .filter(|(_, expires_at)| expires_at.saturating_sub(REFRESH_MARGIN_SECS) <= now)
+                .map(|(id, expires_at)| (id.clone(), *expires_at))
+                .collect(),
+            Err(_) => Vec::new()
+        }
+    }
+}
+
+/// Loads the expiry of every stored account and spawns the renewal loop.
+pub fn start<R: Runtime>(app_handle: AppHandle<R>) {
+    let lifecycle = app_handle.state::<TokenLifecycle>();
+    // A broken vault must not keep the launcher from starting, renewal then simply starts empty
+    let stored = accounts::list_accounts(&app_handle).unwrap_or_else(|e| {
+        log::error!("Unable to read stored accounts, no tokens will be renewed: {}", e);
+        Vec::new()
+    });
+    for account in stored {
+        match accounts::account_secrets(&app_handle, &account.id) {
+            Ok(secrets) => lifecycle.track(&account.id, secrets.token_expires_at),
+            Err(e) => log::error!("Unable to read secrets of account {}: {}", account.id, e)
+        }
+    }
+
+    tauri::async_runtime::spawn(run(app_handle));
+}
+
+async fn run<R: Runtime>(app_handle: AppHandle<R>) {
+    loop {
+        let lifecycle = app_handle.state::<TokenLifecycle>();
+        let now = accounts::unix_now();
+
+        for (account_id, expires_at) in lifecycle.due_accounts(now) {
+            match renew(&app_handle, &account_id).await {
+                Ok(secrets) => {
+                    log::info!("Renewed tokens for account {}.", account_id);
+                    lifecycle.track(&account_id, secrets.token_expires_at);
+                    let _ = app_handle.emit("account-renewed", &account_id);
+                }
+                Err(e) => {
+                    log::error!("Failed to renew tokens for account {}: {}", account_id, e);
+                    if expires_at > now + RETRY_DELAY_SECS {
+                        // Still valid for a while, try again shortly instead of giving up.
+                        lifecycle.track(&account_id, now + RETRY_DELAY_SECS + REFRESH_MARGIN_SECS);
+                    } else {
+                        lifecycle.untrack(&account_id);
+                        credentials::revoke_account(&app_handle, &account_id);
+                    }
+
+                    let _ = app_handle.emit("account-renewal-failed", RenewalFailed {
+                        account_id: account_id.clone(),
+                        error: e.payload()
+                    });
+                }
+            }
+        }
+
+        let sleep_secs = lifecycle.next_due()
+            .map(|due| due.saturating_sub(accounts::unix_now()))
+            .unwrap_or(IDLE_POLL_SECS)
+            .min(IDLE_POLL_SECS);
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(sleep_secs)) => {}
+            _ = lifecycle.wake.notified() => {}
+        }
+    }
+}
+
+/// Performs the `refresh_token` grant for a stored account and re-derives its game session.
+pub async fn renew<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<AccountSecrets> {
+    let endpoints = app_handle.state::<AuthEndpoints>();
+    let secrets = accounts::account_secrets(app_handle, account_id)?;
+
+    let oauth_token = match auth::refresh_oauth_token(&endpoints, &secrets.oauth_token.refresh_token).await {
+        Ok(mut oauth_token) => {
+            if oauth_token.refresh_token.is_empty() {
+                oauth_token.refresh_token = secrets.oauth_token.refresh_token.clone();
+            }
+            oauth_token
+        }
+        Err(Error::Auth(e)) => {
+            // The provider session cookie can outlive the refresh token.
+            log::info!("Refresh token for account {} was rejected ({}). Trying silent re-authentication.", account_id, e);
+            let (_, oauth_token) = auth::silent_login(app_handle.clone(), &endpoints, account_id).await?;
+            oauth_token
+        }
+        Err(e) => return Err(e)
+    };
+
+    let profile = WebviewProfile::account(account_id);
+    let game_session = auth::game_session(app_handle.clone(), &endpoints, oauth_token.clone(), &profile).await?;
+
+    let secrets = AccountSecrets::new(oauth_token, Some(game_session.clone()));
+    accounts::update_secrets(app_handle, account_id, &secrets)?;
+    credentials::renew_account(app_handle, account_id, &game_session);
+    Ok(secrets)
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime, WebviewWindowBuilder};
+use uuid::Uuid;
+use crate::error;
+use crate::state::paths::dir_name;
+
+const PROFILES_DIR: &str = "webview";
+const ACCOUNT_PROFILES_DIR: &str = "accounts";
+const FRESH_PROFILES_DIR: &str = "fresh";
+
+/// WKWebView cannot be pointed at a data directory, so on macOS every auth window runs in the
+/// default browser profile and there are no per-account profiles.
+pub const SUPPORTED: bool = cfg!(not(target_os = "macos"));
+
+/// The browser profile (cookies, local storage) an auth window runs in.
+///
+/// Each stored account gets its own so the provider session of one account is never offered to another,
+/// and a new login starts from an empty profile that is only kept once we know whose it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebviewProfile {
+    Account(String),
+    Fresh(String)
+}
+
+impl WebviewProfile {
+    pub fn account(account_id: &str) -> Self {
+        WebviewProfile::Account(account_id.to_string())
+    }
+
+    pub fn fresh() -> Self {
+        WebviewProfile::Fresh(Uuid::new_v4().simple().to_string())
+    }
+
+    pub fn data_directory<R: Runtime>(&self, app_handle: &AppHandle<R>) -> error::Result<PathBuf> {
+        Ok(self.dir_in(&profiles_root(app_handle)?))
+    }
+
+    fn dir_in(&self, root: &Path) -> PathBuf {
+        match self {
+            WebviewProfile::Account(account_id) => root.join(ACCOUNT_PROFILES_DIR).join(dir_name(account_id)),
+            WebviewProfile::Fresh(id) => root.join(FRESH_PROFILES_DIR).join(id)
+        }
+    }
+}
+
+/// Runs the windows a builder makes in a browser profile, on platforms that support them.
+pub trait WebviewProfileExt<R: Runtime>: Sized {
+    fn webview_profile(self, profile: &WebviewProfile, app_handle: &AppHandle<R>) -> error::Result<Self>;
+}
+
+impl<R: Runtime, M: Manager<R>> WebviewProfileExt<R> for WebviewWindowBuilder<'_, R, M> {
+    fn webview_profile(self, profile: &WebviewProfile, app_handle: &AppHandle<R>) -> error::Result<Self> {
+        if !SUPPORTED {
+            return Ok(self);
+        }
+        Ok(self.data_directory(profile.data_directory(app_handle)?))
+    }
+}
+
+fn profiles_root<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?.join(PROFILES_DIR))
+}
+
+fn remove_dir(path: &Path) -> error::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(())
+    }
+}
+
+/// Keeps the profile a fresh login ran in as the profile of `account_id`, replacing its old one.
+pub fn adopt<R: Runtime>(app_handle: &AppHandle<R>, profile: &WebviewProfile, account_id: &str) -> error::Result<()> {
+    adopt_in(&profiles_root(app_handle)?, profile, account_id)
+}
+
+fn adopt_in(root: &Path, profile: &WebviewProfile, account_id: &str) -> error::Result<()> {
+    if !matches!(profile, WebviewProfile::Fresh(_)) {
+        return Ok(());
+    }
+
+    let source = profile.dir_in(root);
+    if !source.exists() {
+        return Ok(());
+    }
+
+    let target = WebviewProfile::account(account_id).dir_in(root);
+    remove_dir(&target)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&source, &target)?;
+    log::info!("Stored browser profile for account {}.", account_id);
+    Ok(())
+}
+
+/// Deletes a fresh profile that did not lead to a stored account. Account profiles are left alone.
+pub fn discard<R: Runtime>(app_handle: &AppHandle<R>, profile: &WebviewProfile) {
+    if let Err(e) = profiles_root(app_handle).and_then(|root| discard_in(&root, profile)) {
+        log::error!("Failed to delete unused browser profile: {}", e);
+    }
+}
+
+fn discard_in(root: &Path, profile: &WebviewProfile) -> error::Result<()> {
+    match profile {
+        WebviewProfile::Fresh(_) => remove_dir(&profile.dir_in(root)),
+        WebviewProfile::Account(_) => Ok(())
+    }
+}
+
+/// Deletes the cookies and storage of `account_id`, so its next login starts signed out.
+pub fn clear<R: Runtime>(app_handle: &AppHandle<R>, account_id: &str) -> error::Result<()> {
+    remove_dir(&WebviewProfile::account(account_id).data_directory(app_handle)?)?;
+    log::info!("Cleared browser data of account {}.", account_id);
+    Ok(())
+}
+
+/// Removes fresh profiles left behind by logins that were interrupted by the launcher exiting.
+pub fn remove_stale<R: Runtime>(app_handle: &AppHandle<R>) -> error::Result<()> {
+    remove_stale_in(&profiles_root(app_handle)?)
+}
+
+fn remove_stale_in(root: &Path) -> error::Result<()> {
+    remove_dir(&root.join(FRESH_PROFILES_DIR))
+}
+
+#[cfg(test)]
+mod tests;
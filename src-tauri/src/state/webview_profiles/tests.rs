@@ -0,0 +1,59 @@
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+#[test]
+fn fresh_profiles_are_unique() {
+    assert_ne!(WebviewProfile::fresh(), WebviewProfile::fresh());
+    assert_eq!(WebviewProfile::account("abc"), WebviewProfile::Account("abc".to_string()));
+}
+
+#[test]
+fn fresh_profiles_are_adopted_by_their_account() {
+    let root = TempDir::new("webview");
+    let fresh = WebviewProfile::fresh();
+    fs::create_dir_all(fresh.dir_in(&root)).unwrap();
+    fs::write(fresh.dir_in(&root).join("Cookies"), b"new").unwrap();
+    let account = WebviewProfile::account("mock-user-0001");
+    fs::create_dir_all(account.dir_in(&root)).unwrap();
+    fs::write(account.dir_in(&root).join("Cookies"), b"old").unwrap();
+
+    adopt_in(&root, &fresh, "mock-user-0001").unwrap();
+    assert_eq!(fs::read(account.dir_in(&root).join("Cookies")).unwrap(), b"new");
+    assert!(!fresh.dir_in(&root).exists());
+
+    // Account profiles and fresh ones that were never written to are left as they are.
+    adopt_in(&root, &account, "mock-user-0002").unwrap();
+    adopt_in(&root, &WebviewProfile::fresh(), "mock-user-0001").unwrap();
+    assert_eq!(fs::read(account.dir_in(&root).join("Cookies")).unwrap(), b"new");
+    assert!(!WebviewProfile::account("mock-user-0002").dir_in(&root).exists());
+}
+
+#[test]
+fn only_fresh_profiles_are_discarded() {
+    let root = TempDir::new("webview");
+    let fresh = WebviewProfile::fresh();
+    let account = WebviewProfile::account("mock-user-0001");
+    fs::create_dir_all(fresh.dir_in(&root)).unwrap();
+    fs::create_dir_all(account.dir_in(&root)).unwrap();
+
+    discard_in(&root, &fresh).unwrap();
+    discard_in(&root, &account).unwrap();
+    assert!(!fresh.dir_in(&root).exists());
+    assert!(account.dir_in(&root).exists());
+    // Already gone.
+    discard_in(&root, &fresh).unwrap();
+}
+
+#[test]
+fn stale_fresh_profiles_are_removed() {
+    let root = TempDir::new("webview");
+    let stale = WebviewProfile::fresh();
+    let account = WebviewProfile::account("mock-user-0001");
+    fs::create_dir_all(stale.dir_in(&root)).unwrap();
+    fs::create_dir_all(account.dir_in(&root)).unwrap();
+
+    remove_stale_in(&root).unwrap();
+    assert!(!stale.dir_in(&root).exists());
+    assert!(account.dir_in(&root).exists());
+    remove_stale_in(&root).unwrap();
+}
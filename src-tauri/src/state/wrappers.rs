@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use crate::error;
+use crate::error::Error;
+use crate::state::launch::{ClientType, LaunchPlan};
+use crate::state::paths;
+
+const WINE_PREFIXES_DIR: &str = "wine-prefixes";
+/// Shown instead of the session id in dry runs, so the command line can be shared safely.
+pub const REDACTED: &str = "<redacted>";
+
+/// Runs a Windows client under Wine, in a prefix of its own unless `prefix` says otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WineConfig {
+    /// The wine binary, `wine` from `$PATH` when unset.
+    pub wine: Option<PathBuf>,
+    /// Used as `WINEPREFIX`. The launcher manages a prefix per client when unset.
+    pub prefix: Option<PathBuf>,
+    /// `win32` or `win64`, only applied when the prefix is created.
+    pub arch: Option<String>
+}
+
+/// A resolved launch plan and the command line it amounts to, with the session id redacted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DryRun {
+    pub plan: LaunchPlan,
+    pub command_line: String
+}
+
+/// Checks every wrapper names a program, e.g. `["gamescope", "-f", "--"]` or `["gamemoderun"]`.
+pub fn validate(wrappers: &[Vec<String>]) -> error::Result<()> {
+    if wrappers.iter().any(|wrapper| wrapper.first().is_none_or(|program| program.trim().is_empty())) {
+        return Err(Error::InvalidLaunchProfile("A wrapper has no program".to_string()));
+    }
+    Ok(())
+}
+
+/// The prefix of `client_type` the launcher manages under its data dir.
+pub fn managed_prefix<R: Runtime>(app_handle: &AppHandle<R>, client_type: &ClientType) -> error::Result<PathBuf> {
+    Ok(app_handle.path().app_local_data_dir()?
+        .join(WINE_PREFIXES_DIR)
+        .join(paths::dir_name(&client_type.to_string())))
+}
+
+/// Creates the Wine prefix the plan runs in, if it runs under Wine and the prefix does not exist yet.
+/// Wine fills in an empty prefix on its first start.
+pub fn prepare_prefix(plan: &LaunchPlan) -> error::Result<()> {
+    if let Some(prefix) = plan.env.get("WINEPREFIX") {
+        fs::create_dir_all(prefix)?;
+    }
+    Ok(())
+}
+
+/// Runs `plan` under Wine with `prefix` as its `WINEPREFIX`.
+pub fn wine(plan: LaunchPlan, wine: &WineConfig, prefix: PathBuf) -> LaunchPlan {
+    let mut args = vec![plan.program.to_string_lossy().to_string()];
+    args.extend(plan.args);
+
+    let mut env = plan.env;
+    // The prefix directory is created before the first start, but Wine only writes the registry
+    // once it has set the prefix up, so a start that failed before that still counts as the first.
+    let first_use = !prefix.join("system.reg").exists();
+    env.insert("WINEPREFIX".to_string(), prefix.to_string_lossy().to_string());
+    if let Some(arch) = wine.arch.as_ref().filter(|_| first_use) {
+        env.insert("WINEARCH".to_string(), arch.clone());
+    }
+
+    LaunchPlan {
+        program: wine.wine.clone().unwrap_or_else(|| PathBuf::from("wine")),
+        args,
+        env,
+        cwd: plan.cwd
+    }
+}
+
+/// Prefixes `plan` with `wrappers`, the first of them outermost.
+pub fn wrap(plan: LaunchPlan, wrappers: &[Vec<String>]) -> LaunchPlan {
+    let mut args = wrappers.iter().flatten().cloned().collect::<Vec<_>>();
+    if args.is_empty() {
+        return plan;
+    }
+    let program = args.remove(0);
+    args.push(plan.program.to_string_lossy().to_string());
+    args.extend(plan.args);
+
+    LaunchPlan {
+        program: PathBuf::from(program),
+        args,
+        env: plan.env,
+        cwd: plan.cwd
+    }
+}
+
+/// `plan` as a shell command line, with `secret` replaced wherever it appears.
+pub fn dry_run(mut plan: LaunchPlan, secret: &str) -> DryRun {
+    let redact = |value: &str| if secret.is_empty() {
+        value.to_string()
+    } else {
+        value.replace(secret, REDACTED)
+    };
+    plan.args = plan.args.iter().map(|arg| redact(arg)).collect();
+    plan.env = plan.env.iter().map(|(name, value)| (name.clone(), redact(value))).collect();
+
+    let mut words = plan.env.iter()
+        .map(|(name, value)| format!("{}={}", name, shell_quote(value)))
+        .collect::<Vec<_>>();
+    words.push(shell_quote(&plan.program.to_string_lossy()));
+    words.extend(plan.args.iter().map(|arg| shell_quote(arg)));
+    let mut command_line = words.join(" ");
+    if let Some(cwd) = &plan.cwd {
+        command_line = format!("cd {} && {}", shell_quote(&cwd.to_string_lossy()), command_line);
+    }
+
+    DryRun { plan, command_line }
+}
+
+fn shell_quote(word: &str) -> String {
+    let plain = !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests;
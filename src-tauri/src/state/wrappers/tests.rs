@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use crate::test_support::temp_dir::TempDir;
+use super::*;
+
+fn wrappers(wrappers: &[&[&str]]) -> Vec<Vec<String>> {
+    wrappers.iter()
+        .map(|wrapper| wrapper.iter().map(|word| word.to_string()).collect())
+        .collect()
+}
+
+fn plan() -> LaunchPlan {
+    LaunchPlan {
+        program: PathBuf::from("/opt/osrs/OldSchool.exe"),
+        args: vec!["--session".to_string(), "secret-session".to_string()],
+        env: BTreeMap::from([("JX_SESSION_ID".to_string(), "secret-session".to_string())]),
+        cwd: Some(PathBuf::from("/opt/osrs"))
+    }
+}
+
+#[test]
+fn wrappers_are_chained_outermost_first() {
+    let wrapped = wrap(plan(), &wrappers(&[&["gamescope", "-f", "--"], &["gamemoderun"], &["mangohud"]]));
+
+    assert_eq!(wrapped.program, PathBuf::from("gamescope"));
+    assert_eq!(wrapped.args, vec!["-f", "--", "gamemoderun", "mangohud", "/opt/osrs/OldSchool.exe", "--session", "secret-session"]);
+    assert_eq!(wrapped.env, plan().env);
+    assert_eq!(wrapped.cwd, plan().cwd);
+    assert_eq!(wrap(plan(), &[]), plan());
+}
+
+#[test]
+fn wine_runs_the_client_in_its_prefix() {
+    let dir = TempDir::new("wine");
+    let prefix = dir.join("prefix");
+    let config = WineConfig { arch: Some("win64".to_string()), ..WineConfig::default() };
+
+    let plan = wine(plan(), &config, prefix.clone());
+    assert_eq!(plan.program, PathBuf::from("wine"));
+    assert_eq!(plan.args[0], "/opt/osrs/OldSchool.exe");
+    assert_eq!(plan.env["WINEPREFIX"], prefix.to_string_lossy());
+    assert_eq!(plan.env["WINEARCH"], "win64");
+
+    // The architecture of an existing prefix cannot be changed, so it is only set for new ones.
+    // An empty prefix has not been set up by Wine yet.
+    prepare_prefix(&plan).unwrap();
+    assert!(prefix.is_dir());
+    assert_eq!(wine(self::plan(), &config, prefix.clone()).env["WINEARCH"], "win64");
+    fs::write(prefix.join("system.reg"), "WINE REGISTRY Version 2\n").unwrap();
+    assert!(!wine(self::plan(), &config, prefix.clone()).env.contains_key("WINEARCH"));
+}
+
+#[test]
+fn dry_run_redacts_the_session() {
+    let dry_run = dry_run(wrap(plan(), &wrappers(&[&["prime-run"]])), "secret-session");
+
+    assert_eq!(dry_run.plan.env["JX_SESSION_ID"], REDACTED);
+    assert!(!dry_run.command_line.contains("secret-session"));
+    assert_eq!(dry_run.command_line,
+        "cd /opt/osrs && JX_SESSION_ID='<redacted>' prime-run /opt/osrs/OldSchool.exe --session '<redacted>'");
+}
+
+#[test]
+fn shell_words_are_quoted() {
+    assert_eq!(shell_quote("plain-word"), "plain-word");
+    assert_eq!(shell_quote("two words"), "'two words'");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    assert_eq!(shell_quote(""), "''");
+}
+
+#[test]
+fn wrappers_need_a_program() {
+    assert!(validate(&wrappers(&[&["gamemoderun"]])).is_ok());
+    assert!(validate(&wrappers(&[&[]])).is_err());
+    assert!(validate(&wrappers(&[&[" ", "--"]])).is_err());
+}
@@ -0,0 +1,93 @@
+use std::io::Write;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// An entry of a tar archive: a regular file, a directory, or a symbolic or hard link to `link`.
+pub struct TarEntry<'a> {
+    pub path: &'a str,
+    pub kind: u8,
+    pub contents: &'a [u8],
+    pub link: &'a str,
+    pub mode: u32
+}
+
+pub fn file<'a>(path: &'a str, contents: &'a [u8], mode: u32) -> TarEntry<'a> {
+    TarEntry { path, kind: b'0', contents, link: "", mode }
+}
+
+pub fn dir(path: &str) -> TarEntry<'_> {
+    TarEntry { path, kind: b'5', contents: &[], link: "", mode: 0o755 }
+}
+
+pub fn symlink<'a>(path: &'a str, link: &'a str) -> TarEntry<'a> {
+    TarEntry { path, kind: b'2', contents: &[], link, mode: 0o777 }
+}
+
+/// A hard link to the entry at `link`, which tar records as a path in the archive.
+pub fn hardlink<'a>(path: &'a str, link: &'a str) -> TarEntry<'a> {
+    TarEntry { path, kind: b'1', contents: &[], link, mode: 0o644 }
+}
+
+/// A ustar archive of `entries`. Paths longer than 100 bytes get a GNU long name entry.
+pub fn tar(entries: &[TarEntry]) -> Vec<u8> {
+    let mut tar = Vec::new();
+    for entry in entries {
+        if entry.path.len() > 100 {
+            push_entry(&mut tar, "././@LongLink", b'L', 0o644, &[entry.path.as_bytes(), b"\0"].concat(), "");
+        }
+        push_entry(&mut tar, entry.path, entry.kind, entry.mode, entry.contents, entry.link);
+    }
+    tar.extend_from_slice(&[0; 1024]);
+    tar
+}
+
+fn push_entry(tar: &mut Vec<u8>, path: &str, kind: u8, mode: u32, contents: &[u8], link: &str) {
+    let mut header = [0u8; 512];
+    let name = &path.as_bytes()[..path.len().min(100)];
+    header[..name.len()].copy_from_slice(name);
+    header[100..108].copy_from_slice(format!("{:07o}\0", mode).as_bytes());
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", contents.len()).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = kind;
+    header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    header[148..156].copy_from_slice(b"        ");
+    let checksum = header.iter().map(|byte| *byte as u32).sum::<u32>();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(contents);
+    tar.resize(tar.len().div_ceil(512) * 512, 0);
+}
+
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// An ar archive of `members`, padded the way `ar` does.
+pub fn ar(members: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = b"!<arch>\n".to_vec();
+    for (name, data) in members {
+        archive.extend_from_slice(format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", format!("{}/", name), 0, 0, 0, 100644, data.len()).as_bytes());
+        archive.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            archive.push(b'\n');
+        }
+    }
+    archive
+}
+
+/// A .deb with the given data archive entries, compressed with gzip.
+pub fn deb(entries: &[TarEntry]) -> Vec<u8> {
+    ar(&[
+        ("debian-binary", b"2.0\n"),
+        ("control.tar.gz", &gzip(&tar(&[file("./control", b"Package: runescape-launcher\n", 0o644)]))),
+        ("data.tar.gz", &gzip(&tar(entries)))
+    ])
+}
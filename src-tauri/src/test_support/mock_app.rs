@@ -0,0 +1,68 @@
+use std::fs;
+use std::ops::Deref;
+use tauri::{App, Manager};
+use tauri::test::MockRuntime;
+use uuid::Uuid;
+use crate::state::accounts;
+use crate::state::accounts::AccountSecrets;
+use crate::state::auth::{Account, AccountInfo, GameCharacter, GameSession, OAuthToken};
+
+/// An app on the mock runtime with the store plugin, whose data lives under an identifier of its
+/// own. Its data dir is removed again when dropped.
+pub struct MockApp {
+    app: App<MockRuntime>
+}
+
+impl MockApp {
+    pub fn new() -> MockApp {
+        let mut context = tauri::test::mock_context(tauri::test::noop_assets());
+        context.config_mut().identifier = format!("com.runelauncher.test-{}", Uuid::new_v4().simple());
+        let app = tauri::test::mock_builder()
+            .plugin(tauri_plugin_store::Builder::default().build())
+            .build(context)
+            .unwrap();
+        MockApp { app }
+    }
+
+    /// Stores account `account_id` with `characters` and `session`, as a login would.
+    pub fn save_account(&self, account_id: &str, characters: Vec<GameCharacter>, session: GameSession) {
+        let token = OAuthToken {
+            access_token: "access-token".to_string(),
+            refresh_token: "refresh-token".to_string(),
+            expires_in: 3600,
+            id_token: "id-token".to_string(),
+            scope: "openid".to_string(),
+            token_type: "Bearer".to_string()
+        };
+        let account = Account {
+            id: account_id.to_string(),
+            email: "mock@example.invalid".to_string(),
+            account_name: "Mock".to_string(),
+            characters
+        };
+        let info = AccountInfo {
+            nickname: "Mock".to_string(),
+            display_name: "Mock".to_string(),
+            id: account_id.to_string(),
+            user_id: account_id.to_string(),
+            email: None
+        };
+        accounts::save_account(self.app.handle(), &account, &info, &AccountSecrets::new(token, Some(session))).unwrap();
+    }
+}
+
+impl Deref for MockApp {
+    type Target = App<MockRuntime>;
+
+    fn deref(&self) -> &App<MockRuntime> {
+        &self.app
+    }
+}
+
+impl Drop for MockApp {
+    fn drop(&mut self) {
+        for dir in [self.app.path().app_local_data_dir(), self.app.path().app_data_dir()].into_iter().flatten() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
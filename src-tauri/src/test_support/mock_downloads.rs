@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+
+type Files = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Serves whatever files a test puts in it, standing in for client and runtime download servers.
+pub struct MockDownloads {
+    pub base_url: String,
+    files: Files
+}
+
+impl MockDownloads {
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await
+            .expect("Failed to bind mock download server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let files = Files::default();
+
+        let router = Router::new()
+            .route("/{*name}", get(|State(files): State<Files>, Path(name): Path<String>| async move {
+                match files.lock().unwrap().get(&name) {
+                    Some(contents) => (StatusCode::OK, contents.clone()).into_response(),
+                    None => StatusCode::NOT_FOUND.into_response()
+                }
+            }))
+            .with_state(files.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("Mock download server stopped");
+        });
+
+        Self { base_url, files }
+    }
+
+    pub fn serve(&self, name: &str, contents: &[u8]) {
+        self.files.lock().unwrap().insert(name.to_string(), contents.to_vec());
+    }
+
+    pub fn size(&self, name: &str) -> Option<usize> {
+        self.files.lock().unwrap().get(name).map(|contents| contents.len())
+    }
+
+    pub fn url(&self, name: &str) -> String {
+        format!("{}/{}", self.base_url, name)
+    }
+}
@@ -0,0 +1,329 @@
+// The app only uses the routes, the rest is there for tests.
+#![cfg_attr(not(test), allow(dead_code))]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use base64::Engine;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
+use serde_json::{json, Value};
+use url::Url;
+use uuid::Uuid;
+use crate::state::accounts::unix_now;
+use crate::state::endpoints::AuthEndpoints;
+
+const SIGNING_KEY_PEM: &str = include_str!("mock_identity_key.pem");
+pub const SIGNING_KEY_ID: &str = "mock-key";
+
+pub const MOCK_SUB: &str = "mock-user-0001";
+pub const MOCK_NICKNAME: &str = "mocknick";
+pub const MOCK_DISPLAY_NAME: &str = "MockAccount";
+pub const MOCK_SESSION_ID: &str = "mock-session-id";
+
+/// Every route served by the mock, used to override individual responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockRoute {
+    Discovery,
+    Authorize,
+    Token,
+    Jwks,
+    Sessions,
+    Accounts,
+    DisplayName
+}
+
+struct IssuedCode {
+    client_id: String,
+    nonce: Option<String>
+}
+
+struct MockState {
+    base_url: String,
+    encoding_key: EncodingKey,
+    jwks: Value,
+    codes: Mutex<HashMap<String, IssuedCode>>,
+    provider_session: AtomicBool,
+    overrides: Mutex<HashMap<MockRoute, (StatusCode, String)>>
+}
+
+/// A local stand-in for the Jagex identity provider and game-session APIs.
+///
+/// Serves the discovery document, authorize and token endpoints, a JWKS, and the
+/// `/game-session/v1/*` and `/v1/users/{sub}/displayName` APIs on a random loopback port.
+pub struct MockIdentity {
+    pub base_url: String,
+    state: Arc<MockState>
+}
+
+impl MockIdentity {
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await
+            .expect("Failed to bind mock identity server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let private_key = RsaPrivateKey::from_pkcs1_pem(SIGNING_KEY_PEM)
+            .expect("Mock signing key is not a valid PKCS#1 key");
+        let encode_b64 = |bytes: Vec<u8>| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        let jwks = json!({
+            "keys": [{
+                "kty": "RSA",
+                "use": "sig",
+                "alg": "RS256",
+                "kid": SIGNING_KEY_ID,
+                "n": encode_b64(private_key.n().to_bytes_be()),
+                "e": encode_b64(private_key.e().to_bytes_be())
+            }]
+        });
+
+        let state = Arc::new(MockState {
+            base_url: base_url.clone(),
+            encoding_key: EncodingKey::from_rsa_pem(SIGNING_KEY_PEM.as_bytes()).unwrap(),
+            jwks,
+            codes: Mutex::new(HashMap::new()),
+            provider_session: AtomicBool::new(true),
+            overrides: Mutex::new(HashMap::new())
+        });
+
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/oauth2/auth", get(authorize))
+            .route("/oauth2/token", post(token))
+            .route("/oauth2/keys", get(keys))
+            .route("/game-session/v1/sessions", post(sessions))
+            .route("/game-session/v1/accounts", get(accounts))
+            .route("/v1/users/{sub}/displayName", get(display_name))
+            .with_state(state.clone());
+
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("Mock identity server stopped");
+        });
+
+        Self { base_url, state }
+    }
+
+    /// Endpoints pointing every request of the auth pipeline at this server.
+    pub fn endpoints(&self) -> AuthEndpoints {
+        AuthEndpoints {
+            oidc_discovery_url: format!("{}/.well-known/openid-configuration", self.base_url),
+            auth_api_url: self.base_url.clone(),
+            api_url: self.base_url.clone(),
+            ..AuthEndpoints::default()
+        }
+    }
+
+    /// Makes `route` answer with `status` and a raw `body` instead of its normal response.
+    pub fn override_response(&self, route: MockRoute, status: u16, body: &str) {
+        self.state.overrides.lock().unwrap()
+            .insert(route, (StatusCode::from_u16(status).unwrap(), body.to_string()));
+    }
+
+    /// Forgets the browser session, so `prompt=none` requests answer `login_required`.
+    pub fn end_provider_session(&self) {
+        self.state.provider_session.store(false, Ordering::SeqCst);
+    }
+
+    /// Signs arbitrary claims with the key published in the mock JWKS.
+    pub fn sign(&self, claims: &Value) -> String {
+        sign(&self.state, claims)
+    }
+
+    /// The claims the mock puts into a valid ID token for `client_id`.
+    pub fn id_token_claims(&self, client_id: &str, nonce: Option<&str>) -> Value {
+        id_token_claims(&self.state, client_id, nonce)
+    }
+}
+
+fn sign(state: &MockState, claims: &Value) -> String {
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(SIGNING_KEY_ID.to_string());
+    encode(&header, claims, &state.encoding_key).expect("Failed to sign mock token")
+}
+
+fn id_token_claims(state: &MockState, client_id: &str, nonce: Option<&str>) -> Value {
+    let now = unix_now();
+    let mut claims = json!({
+        "iss": state.base_url,
+        "sub": MOCK_SUB,
+        "aud": [client_id],
+        "iat": now,
+        "exp": now + 600,
+        "nickname": MOCK_NICKNAME
+    });
+    if let Some(nonce) = nonce {
+        claims["nonce"] = json!(nonce);
+    }
+    claims
+}
+
+fn overridden(state: &MockState, route: MockRoute) -> Option<Response> {
+    state.overrides.lock().unwrap()
+        .get(&route)
+        .map(|(status, body)| (*status, [(header::CONTENT_TYPE, "application/json")], body.clone()).into_response())
+}
+
+fn oauth_error(status: StatusCode, error: &str, description: &str) -> Response {
+    (status, Json(json!({ "error": error, "error_description": description }))).into_response()
+}
+
+async fn discovery(State(state): State<Arc<MockState>>) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::Discovery) {
+        return response;
+    }
+
+    let base = &state.base_url;
+    Json(json!({
+        "issuer": base,
+        "authorization_endpoint": format!("{}/oauth2/auth", base),
+        "token_endpoint": format!("{}/oauth2/token", base),
+        "jwks_uri": format!("{}/oauth2/keys", base),
+        "response_types_supported": ["code", "id_token", "code id_token"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["RS256"],
+        "scopes_supported": ["openid", "offline"]
+    })).into_response()
+}
+
+async fn keys(State(state): State<Arc<MockState>>) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::Jwks) {
+        return response;
+    }
+    Json(state.jwks.clone()).into_response()
+}
+
+/// Immediately "logs the user in" and redirects back with a code, plus an id token in the
+/// fragment when one was requested, like the real provider does for the game-session leg.
+async fn authorize(State(state): State<Arc<MockState>>, Query(params): Query<HashMap<String, String>>) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::Authorize) {
+        return response;
+    }
+
+    let (Some(client_id), Some(redirect_uri), Some(csrf_state)) =
+        (params.get("client_id"), params.get("redirect_uri"), params.get("state")) else {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "Missing client_id, redirect_uri or state.");
+    };
+    let Ok(mut redirect) = Url::parse(redirect_uri) else {
+        return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "redirect_uri is not a valid URL.");
+    };
+
+    let silent = params.get("prompt").is_some_and(|prompt| prompt == "none");
+    if silent && !state.provider_session.load(Ordering::SeqCst) {
+        redirect.query_pairs_mut()
+            .append_pair("error", "login_required")
+            .append_pair("error_description", "The user is not logged in.")
+            .append_pair("state", csrf_state);
+        return (StatusCode::FOUND, [(header::LOCATION, redirect.to_string())]).into_response();
+    }
+
+    let nonce = params.get("nonce").cloned();
+    let code = Uuid::new_v4().simple().to_string();
+    state.codes.lock().unwrap().insert(code.clone(), IssuedCode {
+        client_id: client_id.clone(),
+        nonce: nonce.clone()
+    });
+
+    let response_type = params.get("response_type").cloned().unwrap_or_default();
+    if response_type.split(' ').any(|t| t == "id_token") {
+        let id_token = sign(&state, &id_token_claims(&state, client_id, nonce.as_deref()));
+        let fragment = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("code", &code)
+            .append_pair("id_token", &id_token)
+            .append_pair("state", csrf_state)
+            .finish();
+        redirect.set_fragment(Some(&fragment));
+    } else {
+        redirect.query_pairs_mut()
+            .append_pair("code", &code)
+            .append_pair("state", csrf_state);
+    }
+
+    (StatusCode::FOUND, [(header::LOCATION, redirect.to_string())]).into_response()
+}
+
+async fn token(State(state): State<Arc<MockState>>, Form(params): Form<HashMap<String, String>>) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::Token) {
+        return response;
+    }
+
+    let client_id = params.get("client_id").cloned().unwrap_or_default();
+    let nonce = match params.get("grant_type").map(String::as_str) {
+        Some("authorization_code") => {
+            let issued = params.get("code").and_then(|code| state.codes.lock().unwrap().remove(code));
+            match issued {
+                Some(issued) if issued.client_id == client_id && params.contains_key("code_verifier") => issued.nonce,
+                _ => return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "Unknown or already used authorization code.")
+            }
+        }
+        Some("refresh_token") => {
+            let valid = params.get("refresh_token").is_some_and(|token| token.starts_with("mock-refresh-"));
+            if !valid {
+                return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", "Refresh token is invalid or revoked.");
+            }
+            None
+        }
+        _ => return oauth_error(StatusCode::BAD_REQUEST, "unsupported_grant_type", "Unsupported grant type.")
+    };
+
+    let id_token = sign(&state, &id_token_claims(&state, &client_id, nonce.as_deref()));
+    Json(json!({
+        "access_token": format!("mock-access-{}", Uuid::new_v4().simple()),
+        "refresh_token": format!("mock-refresh-{}", Uuid::new_v4().simple()),
+        "expires_in": 1800,
+        "id_token": id_token,
+        "scope": "openid offline",
+        "token_type": "bearer"
+    })).into_response()
+}
+
+async fn sessions(State(state): State<Arc<MockState>>, Json(body): Json<Value>) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::Sessions) {
+        return response;
+    }
+
+    match body.get("idToken").and_then(Value::as_str) {
+        Some(id_token) if !id_token.is_empty() => Json(json!({ "sessionId": MOCK_SESSION_ID })).into_response(),
+        _ => oauth_error(StatusCode::BAD_REQUEST, "invalid_request", "Missing idToken.")
+    }
+}
+
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")
+}
+
+async fn accounts(State(state): State<Arc<MockState>>, headers: HeaderMap) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::Accounts) {
+        return response;
+    }
+
+    if bearer(&headers) != Some(MOCK_SESSION_ID) {
+        return oauth_error(StatusCode::UNAUTHORIZED, "invalid_token", "Unknown session id.");
+    }
+
+    Json(json!([
+        { "accountId": "1001", "displayName": "MockHero", "userHash": "hash-1001" },
+        { "accountId": "1002", "displayName": null, "userHash": "hash-1002" }
+    ])).into_response()
+}
+
+async fn display_name(State(state): State<Arc<MockState>>, Path(sub): Path<String>, headers: HeaderMap) -> Response {
+    if let Some(response) = overridden(&state, MockRoute::DisplayName) {
+        return response;
+    }
+
+    if !bearer(&headers).is_some_and(|token| token.starts_with("mock-access-")) {
+        return oauth_error(StatusCode::UNAUTHORIZED, "invalid_token", "Unknown access token.");
+    }
+
+    Json(json!({
+        "displayName": MOCK_DISPLAY_NAME,
+        "id": format!("display-{}", sub),
+        "userId": sub
+    })).into_response()
+}
@@ -0,0 +1,9 @@
+#[cfg(test)]
+pub mod debian_package;
+#[cfg(test)]
+pub mod mock_app;
+#[cfg(test)]
+pub mod mock_downloads;
+pub mod mock_identity;
+#[cfg(test)]
+pub mod temp_dir;
@@ -0,0 +1,39 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// A fresh directory below the system temp dir. Removed again when dropped, so a failing
+/// assertion does not leave it behind.
+pub struct TempDir {
+    path: PathBuf
+}
+
+impl TempDir {
+    /// Creates `runelauncher-<prefix>-<random>`.
+    pub fn new(prefix: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("runelauncher-{}-{}", prefix, Uuid::new_v4().simple()));
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -17,26 +17,33 @@ export interface GameAccount {
   accountName: string;
   email: string;
   characters: Character[];
+  needsRelogin?: boolean;
 }
 
 interface AccountCardProps {
   account: GameAccount;
   isExpanded: boolean;
   playingCharacter: string | null;
+  runningCharacters: Set<string>;
   onToggle: (accountId: string) => void;
   onRemove: (accountId: string) => void;
+  onRelogin: (accountId: string) => void;
   onCreateCharacter: (accountId: string) => void;
-  onPlayCharacter: (characterId: string) => void;
+  onPlayCharacter: (accountId: string, characterId: string) => void;
+  onStopCharacter: (characterId: string) => void;
 }
 
 export function AccountCard({
   account,
   isExpanded,
   playingCharacter,
+  runningCharacters,
   onToggle,
   onRemove,
+  onRelogin,
   onCreateCharacter,
   onPlayCharacter,
+  onStopCharacter,
 }: AccountCardProps) {
   const getAccountInitials = (accountName: string) => {
     return accountName
@@ -70,6 +77,21 @@ export function AccountCard({
               <div className="flex-1">
                 <h3 className="text-white">{account.accountName}</h3>
                 <p className="text-slate-400 text-sm">{account.email}</p>
+                {account.needsRelogin && (
+                  <p className="text-amber-400 text-xs">
+                    Session expired.{" "}
+                    <button
+                      className="underline hover:text-amber-300"
+                      onClick={(event) => {
+                        // Don't toggle the card
+                        event.stopPropagation();
+                        onRelogin(account.id);
+                      }}
+                    >
+                      Log in again
+                    </button>
+                  </p>
+                )}
               </div>
             </div>
             
@@ -169,7 +191,9 @@ export function AccountCard({
                         <CharacterCard
                           character={character}
                           isPlaying={playingCharacter === character.id}
-                          onPlay={onPlayCharacter}
+                          isRunning={runningCharacters.has(character.id)}
+                          onPlay={(characterId) => onPlayCharacter(account.id, characterId)}
+                          onStop={onStopCharacter}
                         />
                       </motion.div>
                     ))}
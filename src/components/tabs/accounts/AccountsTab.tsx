@@ -6,77 +6,199 @@ import { AddAccountDialog } from "./AddAccountDialog";
 import { Character } from "./CharacterCard";
 import { Button } from "../../ui/button";
 import { LoadingSpinner } from "../../LoadingSpinner";
-import { Plus } from "lucide-react";
+import { Plus, X } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
+import { LauncherError, toLauncherError } from "../../../lib/launcherError";
+import { AccountPlaytime, formatLastPlayed, formatPlaytime } from "../../../lib/playtime";
 
 interface AccountsTabProps {
   accounts: GameAccount[];
   onAccountsChange: (accounts: GameAccount[]) => void;
 }
 
-const mockAccounts: GameAccount[] = [];
+interface RunningClient {
+  pid: number;
+  account_id: string;
+  character_id: string;
+  display_name: string;
+  client: string;
+  client_type: string;
+  started_at: number;
+}
+
+interface RustCharacter {
+  account_id: string;
+  display_name: string;
+  user_hash: string;
+  is_members: boolean;
+  levels: { game: "osrs" | "rs3"; total_level: number; combat_level: number } | null;
+}
+
+interface RustAccount {
+  id: string;
+  email: string;
+  account_name: string;
+  characters: RustCharacter[];
+}
+
+// Map Rust Account/GameCharacter to the frontend GameAccount/Character interfaces
+function mapRustAccount(rustAccount: RustAccount, playtime?: AccountPlaytime): GameAccount {
+  const mappedCharacters: Character[] = rustAccount.characters.map(char => ({
+    id: char.account_id,
+    name: char.display_name,
+    level: char.levels?.combat_level,
+    totalLevel: char.levels?.total_level,
+    className: char.display_name ? (char.is_members ? "Member" : "Free Player") : "New Character",
+    lastPlayed: formatLastPlayed(playtime?.characters[char.account_id]?.last_played),
+    totalPlaytime: formatPlaytime(playtime?.characters[char.account_id]?.total_secs ?? 0)
+  }));
+
+  return {
+    id: rustAccount.id,
+    accountName: rustAccount.account_name,
+    email: rustAccount.email,
+    characters: mappedCharacters
+  };
+}
 
 export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: AccountsTabProps) {
-  const [accounts, setAccounts] = useState<GameAccount[]>(initialAccounts.length > 0 ? initialAccounts : mockAccounts);
-  const [expandedAccount, setExpandedAccount] = useState<string | null>("1");
+  const [accounts, setAccounts] = useState<GameAccount[]>(initialAccounts);
+  const [expandedAccount, setExpandedAccount] = useState<string | null>(null);
   const [playingCharacter, setPlayingCharacter] = useState<string | null>(null);
+  const [runningCharacters, setRunningCharacters] = useState<Set<string>>(new Set());
   const [isLoggingIn, setisLoggingIn] = useState<boolean>(false);
   const [buttonText, setButtonText] = useState<string>("Add Jagex Account");
+  const [loginError, setLoginError] = useState<LauncherError | null>(null);
 
   // Update parent whenever accounts change
   React.useEffect(() => {
     onAccountsChange(accounts);
   }, [accounts, onAccountsChange]);
 
+  // Hiscores lookups are slow, so levels are filled in once the accounts are already shown
+  const loadLevels = (accountId: string) => {
+    invoke<RustCharacter[]>("get_character_levels", { accountId })
+      .then((characters) => setAccounts(prevAccounts => prevAccounts.map(account =>
+        account.id === accountId ? {
+          ...account,
+          characters: account.characters.map(character => {
+            const levels = characters.find(char => char.account_id === character.id)?.levels;
+            return levels ? { ...character, level: levels.combat_level, totalLevel: levels.total_level } : character;
+          })
+        } : account
+      )))
+      .catch((error) => console.error(`Failed to load levels of account ${accountId}:`, toLauncherError(error).detail));
+  };
+
+  // Hydrate accounts from the Rust account vault on startup
+  useEffect(() => {
+    invoke<RustAccount[]>("list_accounts")
+      .then((storedAccounts) => Promise.all(storedAccounts.map(async (account) => {
+        const playtime = await invoke<AccountPlaytime>("get_account_playtime", { accountId: account.id })
+          .catch((error) => {
+            console.error(`Failed to load playtime of account ${account.id}:`, toLauncherError(error).detail);
+            return undefined;
+          });
+        return mapRustAccount(account, playtime);
+      })))
+      .then((hydratedAccounts) => {
+        setAccounts(hydratedAccounts);
+        hydratedAccounts.forEach(account => loadLevels(account.id));
+      })
+      .catch((error) => console.error("Failed to load stored accounts:", error));
+  }, []);
+
+  // Track which characters have a client running, including ones started before the UI loaded
+  useEffect(() => {
+    let unlistenStarted: (() => void) | undefined;
+    let unlistenExited: (() => void) | undefined;
+
+    const setRunning = (characterId: string, running: boolean) => {
+      setRunningCharacters(prevRunning => {
+        const nextRunning = new Set(prevRunning);
+        if (running) {
+          nextRunning.add(characterId);
+        } else {
+          nextRunning.delete(characterId);
+        }
+        return nextRunning;
+      });
+    };
+
+    // The session that just ended has been recorded by the time the client-exited event arrives
+    const refreshPlaytime = (accountId: string) => {
+      invoke<AccountPlaytime>("get_account_playtime", { accountId })
+        .then((playtime) => setAccounts(prevAccounts => prevAccounts.map(account =>
+          account.id === accountId ? {
+            ...account,
+            characters: account.characters.map(character => ({
+              ...character,
+              lastPlayed: formatLastPlayed(playtime.characters[character.id]?.last_played),
+              totalPlaytime: formatPlaytime(playtime.characters[character.id]?.total_secs ?? 0)
+            }))
+          } : account
+        )))
+        .catch((error) => console.error(`Failed to refresh playtime of account ${accountId}:`, toLauncherError(error).detail));
+    };
+
+    const setupListeners = async () => {
+      [unlistenStarted, unlistenExited] = await Promise.all([listen("client-started", (event) => {
+        setRunning((event.payload as RunningClient).character_id, true);
+      }), listen("client-exited", (event) => {
+        const exited = event.payload as RunningClient;
+        setRunning(exited.character_id, false);
+        refreshPlaytime(exited.account_id);
+      })]);
+
+      const running = await invoke<RunningClient[]>("list_running_clients");
+      setRunningCharacters(new Set(running.map(client => client.character_id)));
+    };
+
+    setupListeners().catch((error) => console.error("Failed to load running clients:", error));
+
+    return () => {
+      if (unlistenStarted) unlistenStarted();
+      if (unlistenExited) unlistenExited();
+    };
+  }, []);
+
   // Set up event listeners for login progress updates
   useEffect(() => {
     let unlistenProgress: (() => void) | undefined;
     let unlistenComplete: (() => void) | undefined;
     let unlistenAccountAdded: (() => void) | undefined;
+    let unlistenRenewed: (() => void) | undefined;
+    let unlistenRenewalFailed: (() => void) | undefined;
+
+    const setReloginRequired = (accountId: string, needsRelogin: boolean) => {
+      setAccounts(prevAccounts => prevAccounts.map(account =>
+        account.id === accountId ? { ...account, needsRelogin } : account
+      ));
+    };
 
     const setupListeners = async () => {
       // Listen for login progress events
-      [unlistenProgress, unlistenComplete, unlistenAccountAdded] = await Promise.all([listen("login-progress", (event) => {
+      [unlistenProgress, unlistenComplete, unlistenAccountAdded, unlistenRenewed, unlistenRenewalFailed] = await Promise.all([listen("login-progress", (event) => {
         setButtonText(event.payload as string);
       }), listen("login-complete", () => {
         setButtonText("Add Jagex Account");
       }), listen("account-added", (event) => {
-        const rustAccount = event.payload as {
-          email: string;
-          account_name: string;
-          characters: Array<{
-            account_id: string;
-            display_name: string;
-            user_hash: string;
-            is_members: boolean;
-          }>;
-        };
-
-        // Map Rust GameCharacter to frontend Character interface
-        const mappedCharacters: Character[] = rustAccount.characters.map(char => ({
-          id: char.account_id,
-          name: char.display_name !== "null" ? char.display_name : "",
-          level: 1, // Default level since isn't provided by Jagex API
-          className: char.display_name !== "null" ? (char.is_members ? "Member" : "Free Player") : "New Character",
-          lastPlayed: char.display_name !== "null" ? "Never" : "Never", // Not provided by API
-          totalPlaytime: char.display_name !== "null" ? "Unknown" : "0h 0m" // Not provided by API
-        }));
-
-        // Create GameAccount for frontend
-        const newAccount: GameAccount = {
-          id: Date.now().toString(),
-          accountName: rustAccount.account_name,
-          email: rustAccount.email,
-          characters: mappedCharacters
-        };
-
-        // Add the account
-        setAccounts(prevAccounts => [...prevAccounts, newAccount]);
-      })]);
-      // Listen for login complete events
+        const newAccount = mapRustAccount(event.payload as RustAccount);
 
-      // Listen for account-added events from Rust
+        // Add the account, replacing it if the same Jagex account logged in again
+        setAccounts(prevAccounts => [
+          ...prevAccounts.filter(account => account.id !== newAccount.id),
+          newAccount
+        ]);
+        loadLevels(newAccount.id);
+      }), listen("account-renewed", (event) => {
+        setReloginRequired(event.payload as string, false);
+      }), listen("account-renewal-failed", (event) => {
+        const failure = event.payload as { account_id: string; error: LauncherError };
+        console.error(`Token renewal failed for account ${failure.account_id}:`, failure.error.detail);
+        setReloginRequired(failure.account_id, true);
+      })]);
     };
 
     setupListeners();
@@ -86,6 +208,8 @@ export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: Acc
       if (unlistenProgress) unlistenProgress();
       if (unlistenComplete) unlistenComplete();
       if (unlistenAccountAdded) unlistenAccountAdded();
+      if (unlistenRenewed) unlistenRenewed();
+      if (unlistenRenewalFailed) unlistenRenewalFailed();
     };
   }, []);
 
@@ -95,7 +219,14 @@ export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: Acc
     setExpandedAccount(expandedAccount === accountId ? null : accountId);
   };
 
-  const removeAccount = (accountId: string) => {
+  const removeAccount = async (accountId: string) => {
+    try {
+      await invoke("remove_account", { accountId });
+    } catch (error) {
+      console.error("Failed to remove account:", error);
+      return;
+    }
+
     setAccounts(accounts.filter((account) => account.id !== accountId));
     // If we're removing the currently expanded account, clear the expansion
     if (expandedAccount === accountId) {
@@ -117,7 +248,6 @@ export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: Acc
     const newCharacter: Character = {
       id: Date.now().toString(),
       name: "", // Blank name until set in-game
-      level: 1,
       className: "New Character",
       lastPlayed: "Never",
       totalPlaytime: "0h 0m",
@@ -135,25 +265,73 @@ export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: Acc
     );
   };
 
-  const playCharacter = (characterId: string) => {
+  const playCharacter = async (accountId: string, characterId: string) => {
     setPlayingCharacter(characterId);
-    // Simulate launching game
-    setTimeout(() => {
+    setLoginError(null);
+    try {
+      await invoke<number>("launch_character", { accountId, characterId });
+    } catch (error) {
+      const launcherError = toLauncherError(error);
+      console.error("Launch failed:", launcherError.detail);
+
+      if (launcherError.code === "client-not-configured") {
+        // Ask for the client once, then try again
+        if (await invoke<boolean>("pick_client_executable")) {
+          setPlayingCharacter(null);
+          return playCharacter(accountId, characterId);
+        }
+      } else {
+        if (launcherError.code === "session-expired") {
+          setAccounts(prevAccounts => prevAccounts.map(account =>
+            account.id === accountId ? { ...account, needsRelogin: true } : account
+          ));
+        }
+        setLoginError(launcherError);
+      }
+    } finally {
       setPlayingCharacter(null);
-    }, 2000);
+    }
   };
 
-  const loginWithJagexAuth = async () => {
+  const stopCharacter = async (characterId: string) => {
+    try {
+      await invoke<boolean>("kill_client", { characterId });
+    } catch (error) {
+      console.error("Failed to stop client:", toLauncherError(error).detail);
+    }
+  };
+
+  const loginWithJagexAuth = async (accountId?: string) => {
     setisLoggingIn(true);
+    setLoginError(null);
     try {
-      await invoke("login", {});
+      // Re-logins try the provider session silently before showing the login window
+      if (accountId) {
+        await invoke("relogin", { accountId });
+      } else {
+        await invoke("login", {});
+      }
     } catch (error) {
-      console.error("Login failed:", error);
+      const launcherError = toLauncherError(error);
+      console.error("Login failed:", launcherError.detail);
+      // Closing the login window is a deliberate choice, not something to report
+      if (launcherError.category !== "cancelled") {
+        setLoginError(launcherError);
+      }
+      setButtonText("Add Jagex Account");
     } finally {
       setisLoggingIn(false);
     }
   };
 
+  const cancelLogin = async () => {
+    try {
+      await invoke<boolean>("cancel_login");
+    } catch (error) {
+      console.error("Failed to cancel login:", toLauncherError(error).detail);
+    }
+  };
+
   return (
     <div className="flex flex-col h-full min-w-xl">
       <LauncherHeader />
@@ -166,19 +344,33 @@ export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: Acc
               account={account}
               isExpanded={expandedAccount === account.id}
               playingCharacter={playingCharacter}
+              runningCharacters={runningCharacters}
               onToggle={toggleAccount}
               onRemove={removeAccount}
+              onRelogin={loginWithJagexAuth}
               onCreateCharacter={createCharacter}
               onPlayCharacter={playCharacter}
+              onStopCharacter={stopCharacter}
             />
           ))}
         </AnimatePresence>
       </div>
 
       <div className="border-t border-slate-700">
+        {loginError && (
+          <div className="px-4 py-3 text-sm">
+            <p className="text-red-400">{loginError.message}</p>
+            {loginError.hints.map((hint) => (
+              <p key={hint} className="text-slate-400 text-xs">{hint}</p>
+            ))}
+            {loginError.retryable && (
+              <p className="text-slate-500 text-xs">You can try adding the account again.</p>
+            )}
+          </div>
+        )}
         <motion.div whileHover={{ scale: 1.02 }} whileTap={{ scale: 0.98 }}>
           <Button
-            onClick={loginWithJagexAuth}
+            onClick={() => loginWithJagexAuth()}
             disabled={isLoggingIn}
             className="w-full bg-emerald-600 hover:bg-emerald-700 disabled:bg-emerald-700 disabled:opacity-75 text-white py-6"
           >
@@ -190,6 +382,16 @@ export function AccountsTab({ accounts: initialAccounts, onAccountsChange }: Acc
             {buttonText}
           </Button>
         </motion.div>
+        {isLoggingIn && (
+          <Button
+            onClick={cancelLogin}
+            variant="ghost"
+            className="w-full text-slate-400 hover:text-white"
+          >
+            <X className="w-4 h-4 mr-2" />
+            Cancel login
+          </Button>
+        )}
       </div>
     </div>
   );
@@ -1,14 +1,17 @@
-import React from "react";
+import React, { useState } from "react";
 import { motion } from "motion/react";
 import { Button } from "../../ui/button";
 import { Badge } from "../../ui/badge";
-import { Play } from "lucide-react";
+import { Play, ScrollText, Square } from "lucide-react";
 import { LoadingSpinner } from "../../LoadingSpinner";
+import { ClientLogDialog } from "./ClientLogDialog";
 
 export interface Character {
   id: string;
   name: string;
-  level: number;
+  // Combat and total level from the hiscores, unknown for characters that are not ranked
+  level?: number;
+  totalLevel?: number;
   className: string;
   lastPlayed: string;
   totalPlaytime: string;
@@ -17,10 +20,14 @@ export interface Character {
 interface CharacterCardProps {
   character: Character;
   isPlaying: boolean;
+  isRunning: boolean;
   onPlay: (characterId: string) => void;
+  onStop: (characterId: string) => void;
 }
 
-export function CharacterCard({ character, isPlaying, onPlay }: CharacterCardProps) {
+export function CharacterCard({ character, isPlaying, isRunning, onPlay, onStop }: CharacterCardProps) {
+  const [showLogs, setShowLogs] = useState(false);
+
   return (
     <motion.div
       className="bg-slate-750 rounded-lg p-3 border border-slate-700"
@@ -39,12 +46,15 @@ export function CharacterCard({ character, isPlaying, onPlay }: CharacterCardPro
             >
               {character.name || "Unnamed Character"}
             </h4>
-            <Badge
-              variant="secondary"
-              className="bg-slate-600 text-slate-200 text-xs"
-            >
-              Lvl {character.level}
-            </Badge>
+            {character.level !== undefined && (
+              <Badge
+                variant="secondary"
+                className="bg-slate-600 text-slate-200 text-xs"
+                title={character.totalLevel !== undefined ? `Total level ${character.totalLevel}` : undefined}
+              >
+                Lvl {character.level}
+              </Badge>
+            )}
             <Badge
               variant="outline"
               className={`border-slate-600 text-xs ${
@@ -55,6 +65,11 @@ export function CharacterCard({ character, isPlaying, onPlay }: CharacterCardPro
             >
               {character.className}
             </Badge>
+            {isRunning && (
+              <Badge className="bg-emerald-600/20 text-emerald-400 text-xs">
+                Running
+              </Badge>
+            )}
           </div>
           <div className="flex gap-4 text-sm text-slate-400">
             <span>Last played: {character.lastPlayed}</span>
@@ -62,20 +77,46 @@ export function CharacterCard({ character, isPlaying, onPlay }: CharacterCardPro
           </div>
         </div>
         <Button
-          onClick={() => onPlay(character.id)}
-          disabled={isPlaying}
-          className="bg-emerald-600 hover:bg-emerald-700 text-white px-6"
+          onClick={() => setShowLogs(true)}
+          variant="ghost"
+          size="icon"
+          title="Client logs"
+          className="text-slate-400 hover:text-white mr-2"
         >
-          {isPlaying ? (
-            <LoadingSpinner />
-          ) : (
-            <>
-              <Play className="w-4 h-4 mr-2" />
-              {character.name ? "Play" : "Create & Play"}
-            </>
-          )}
+          <ScrollText className="w-4 h-4" />
         </Button>
+        {isRunning ? (
+          <Button
+            onClick={() => onStop(character.id)}
+            variant="outline"
+            className="border-slate-600 text-slate-300 hover:text-white px-6"
+          >
+            <Square className="w-4 h-4 mr-2" />
+            Stop
+          </Button>
+        ) : (
+          <Button
+            onClick={() => onPlay(character.id)}
+            disabled={isPlaying}
+            className="bg-emerald-600 hover:bg-emerald-700 text-white px-6"
+          >
+            {isPlaying ? (
+              <LoadingSpinner />
+            ) : (
+              <>
+                <Play className="w-4 h-4 mr-2" />
+                {character.name ? "Play" : "Create & Play"}
+              </>
+            )}
+          </Button>
+        )}
       </div>
+      <ClientLogDialog
+        characterId={character.id}
+        characterName={character.name}
+        isOpen={showLogs}
+        onOpenChange={setShowLogs}
+      />
     </motion.div>
   );
 }
\ No newline at end of file
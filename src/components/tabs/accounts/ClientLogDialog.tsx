@@ -0,0 +1,126 @@
+import React, { useEffect, useRef, useState } from "react";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogHeader,
+  DialogTitle,
+} from "../../ui/dialog";
+import { Button } from "../../ui/button";
+import { FolderOpen } from "lucide-react";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { toLauncherError } from "../../../lib/launcherError";
+
+// Mirror `LogSession` and `LogLine` in src-tauri/src/state/client_logs.rs
+interface LogSession {
+  character_id: string;
+  session_id: string;
+  started_at: number;
+  size: number;
+  live: boolean;
+}
+
+interface LogLine {
+  stream: "stdout" | "stderr";
+  text: string;
+}
+
+interface ClientLogDialogProps {
+  characterId: string;
+  characterName: string;
+  isOpen: boolean;
+  onOpenChange: (open: boolean) => void;
+}
+
+export function ClientLogDialog({ characterId, characterName, isOpen, onOpenChange }: ClientLogDialogProps) {
+  const [sessions, setSessions] = useState<LogSession[]>([]);
+  const [sessionId, setSessionId] = useState<string | null>(null);
+  const [lines, setLines] = useState<LogLine[]>([]);
+  const bottomRef = useRef<HTMLDivElement>(null);
+
+  // Load the sessions each time the dialog opens and show the newest one
+  useEffect(() => {
+    if (!isOpen) return;
+    invoke<LogSession[]>("list_log_sessions", { characterId })
+      .then((loaded) => {
+        setSessions(loaded);
+        setSessionId(loaded.length > 0 ? loaded[0].session_id : null);
+      })
+      .catch((error) => console.error("Failed to list client logs:", toLauncherError(error).detail));
+  }, [isOpen, characterId]);
+
+  // Stream the selected session, following it while the client runs
+  useEffect(() => {
+    if (!isOpen || !sessionId) return;
+    setLines([]);
+
+    const onLine = new Channel<LogLine>();
+    onLine.onmessage = (line) => setLines((prevLines) => [...prevLines, line]);
+    invoke("tail_client_log", { characterId, sessionId, onLine })
+      .catch((error) => console.error("Failed to tail client log:", toLauncherError(error).detail));
+
+    return () => {
+      invoke("stop_client_log_tail", { channelId: onLine.id }).catch(() => {});
+    };
+  }, [isOpen, characterId, sessionId]);
+
+  useEffect(() => {
+    bottomRef.current?.scrollIntoView({ block: "end" });
+  }, [lines]);
+
+  const openFolder = async () => {
+    try {
+      await invoke("open_client_logs_folder", { characterId });
+    } catch (error) {
+      console.error("Failed to open client logs folder:", toLauncherError(error).detail);
+    }
+  };
+
+  return (
+    <Dialog open={isOpen} onOpenChange={onOpenChange}>
+      <DialogContent className="bg-slate-800 border-slate-700 text-white max-w-3xl">
+        <DialogHeader>
+          <DialogTitle className="text-white">Client logs</DialogTitle>
+          <DialogDescription className="text-slate-400">
+            Output of the game client for {characterName || "this character"}.
+          </DialogDescription>
+        </DialogHeader>
+        <div className="flex gap-2 items-center">
+          <select
+            value={sessionId ?? ""}
+            onChange={(e) => setSessionId(e.target.value)}
+            disabled={sessions.length === 0}
+            className="flex-1 bg-slate-700 border border-slate-600 rounded-md px-2 py-1 text-sm"
+          >
+            {sessions.length === 0 && <option value="">No sessions yet</option>}
+            {sessions.map((session) => (
+              <option key={session.session_id} value={session.session_id}>
+                {new Date(session.started_at * 1000).toLocaleString()}
+                {session.live ? " (running)" : ""}
+              </option>
+            ))}
+          </select>
+          <Button
+            variant="outline"
+            onClick={openFolder}
+            className="border-slate-600 text-slate-300 hover:bg-slate-700"
+          >
+            <FolderOpen className="w-4 h-4 mr-2" />
+            Open folder
+          </Button>
+        </div>
+        <div className="h-80 overflow-y-auto bg-slate-900 rounded-md p-2 font-mono text-xs">
+          {lines.map((line, index) => (
+            <div
+              key={index}
+              className={`whitespace-pre-wrap ${line.stream === "stderr" ? "text-red-400" : "text-slate-300"}`}
+            >
+              {line.text}
+            </div>
+          ))}
+          <div ref={bottomRef} />
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -0,0 +1,32 @@
+// Mirrors `ErrorPayload` in src-tauri/src/error.rs, which every failing command rejects with.
+export type ErrorCategory = "network" | "auth-rejected" | "cancelled" | "server" | "local-io";
+
+export interface LauncherError {
+  code: string;
+  category: ErrorCategory;
+  retryable: boolean;
+  message: string;
+  hints: string[];
+  detail: string;
+}
+
+export function isLauncherError(error: unknown): error is LauncherError {
+  return typeof error === "object" && error !== null && "code" in error && "category" in error;
+}
+
+// Normalizes anything a rejected `invoke` can produce into a LauncherError
+export function toLauncherError(error: unknown): LauncherError {
+  if (isLauncherError(error)) {
+    return error;
+  }
+
+  const detail = error instanceof Error ? error.message : String(error);
+  return {
+    code: "unknown",
+    category: "local-io",
+    retryable: false,
+    message: "Something went wrong.",
+    hints: [],
+    detail
+  };
+}
@@ -0,0 +1,33 @@
+// Mirrors `AccountPlaytime` and `PlaytimeSummary` in src-tauri/src/state/playtime.rs.
+export interface PlaytimeSummary {
+  sessions: number;
+  total_secs: number;
+  last_played: number | null;
+}
+
+export interface AccountPlaytime extends PlaytimeSummary {
+  account_id: string;
+  characters: Record<string, PlaytimeSummary>;
+}
+
+export function formatPlaytime(totalSecs: number): string {
+  const minutes = Math.floor(totalSecs / 60);
+  return `${Math.floor(minutes / 60)}h ${minutes % 60}m`;
+}
+
+// A Unix timestamp (seconds) relative to now, e.g. "5 minutes ago"
+export function formatLastPlayed(lastPlayed: number | null | undefined): string {
+  if (lastPlayed == null) {
+    return "Never";
+  }
+
+  const elapsed = Math.max(0, Date.now() / 1000 - lastPlayed);
+  const units: Array<[string, number]> = [["day", 86400], ["hour", 3600], ["minute", 60]];
+  for (const [unit, secs] of units) {
+    const count = Math.floor(elapsed / secs);
+    if (count > 0) {
+      return `${count} ${unit}${count === 1 ? "" : "s"} ago`;
+    }
+  }
+  return "Just now";
+}